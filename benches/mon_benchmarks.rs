@@ -166,6 +166,28 @@ fn bench_lexer_scaling(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_lexer_long_tokens(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lexer_long_tokens");
+
+    for size in [1_000, 10_000, 100_000, 1_000_000] {
+        let source = format!(
+            "{{ value: \"{}\", {}: 1 }} // {}",
+            "a".repeat(size),
+            "k".repeat(size),
+            "c".repeat(size)
+        );
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &source, |b, src| {
+            b.iter(|| {
+                let mut lexer = Lexer::new(black_box(src));
+                lexer.lex()
+            })
+        });
+    }
+
+    group.finish();
+}
+
 // ============================================================================
 // Parser Benchmarks
 // ============================================================================
@@ -358,7 +380,8 @@ criterion_group!(
     lexer_benches,
     bench_lexer_tiny,
     bench_lexer_sizes,
-    bench_lexer_scaling
+    bench_lexer_scaling,
+    bench_lexer_long_tokens
 );
 
 criterion_group!(parser_benches, bench_parser_sizes, bench_parser_scaling);
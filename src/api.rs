@@ -39,29 +39,35 @@
 //! let yaml_output = result.to_yaml().unwrap();
 //!
 //! println!("{}", yaml_output);
-//! assert!(yaml_output.contains("version: 1.0"));
+//! assert!(yaml_output.contains("version: 1\n"));
 //! # Ok(())
 //! # }
 //! ```
 #[allow(dead_code)]
-use crate::ast::{MonDocument, MonValue, SymbolTable, TypeSpec};
-use crate::error::MonError;
+use crate::ast::{
+    ImportStatement, Member, MonDocument, MonValue, MonValueKind, Pair, SymbolTable,
+    TypeDefinition, TypeSpec,
+};
+use crate::de::from_value;
+use crate::error::{DeserializeError, MonError, MonWarning, PathError, ResolverError};
 
 #[cfg(feature = "lsp")]
-use crate::ast::{Member, MonValueKind};
+use crate::ast::TypeDef;
 #[cfg(feature = "lsp")]
 use crate::lsp;
-use crate::parser::Parser;
-use crate::resolver::Resolver;
-use crate::serialization::{to_value, Value};
-#[cfg(feature = "lsp")]
+use crate::parser::{DocumentSink, Parser};
+pub use crate::resolver::AnalyzeMetrics;
+use crate::resolver::{absolute_file_path, unwrap_guards, ResolveOptions, Resolver};
+use crate::serialization::{to_value, to_value_with, BytesEncoding, Value, WholeNumbers};
+use crate::utils::extract_mon;
 use miette::SourceSpan;
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json;
 use serde_yaml;
 use std::collections::HashMap;
-use std::fmt::Display;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 /// The result of a successful analysis of a MON document.
 ///
@@ -77,6 +83,13 @@ pub struct AnalysisResult {
     pub symbol_table: SymbolTable,
     /// A map of all declared anchors.
     pub anchors: HashMap<String, MonValue>,
+    /// The import edges of the document and its transitive imports, as
+    /// (importing file, imported file) pairs of absolute paths.
+    pub dependencies: Vec<(PathBuf, PathBuf)>,
+    /// Warnings found while resolving, e.g. spreads whose keys are all overridden.
+    pub warnings: Vec<MonWarning>,
+    /// The source text of the document, which the spans of `unresolved_document` point into.
+    pub source: String,
 }
 
 impl Serialize for AnalysisResult {
@@ -90,12 +103,58 @@ impl Serialize for AnalysisResult {
 }
 
 impl AnalysisResult {
+    /// Returns the files the document depends on through its imports, directly or transitively.
+    ///
+    /// The paths are absolute, sorted and unique. A build tool can re-run the analysis when
+    /// any of them changes.
+    #[must_use]
+    pub fn imported_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .dependencies
+            .iter()
+            .map(|(_, imported)| imported.clone())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Returns the import edges of the document and its imports, as
+    /// (importing file, imported file) pairs in the order they were discovered.
+    #[must_use]
+    pub fn dependency_graph(&self) -> &[(PathBuf, PathBuf)] {
+        &self.dependencies
+    }
+
     /// Serializes the resolved MON data into a generic, serializable `Value`.
     #[must_use]
     pub fn to_value(&self) -> Value {
         to_value(&self.document.root)
     }
 
+    /// Serializes the resolved MON data like [`to_value`](Self::to_value), writing the data of
+    /// `Bytes` values as `bytes` says, e.g. as arrays of numbers.
+    #[must_use]
+    pub fn to_value_with(&self, bytes: BytesEncoding) -> Value {
+        to_value_with(&self.document.root, bytes)
+    }
+
+    /// Returns a stable hash of the resolved data, e.g. to check whether the effective
+    /// configuration changed. See [`Value::content_hash`].
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        self.to_value().content_hash()
+    }
+
+    /// Deep-merges the resolved data of `other` on top of this result and returns the combined value.
+    ///
+    /// This is meant for layered configuration, e.g. a base file with environment overrides.
+    /// See [`Value::merge`] for the merge rules.
+    #[must_use]
+    pub fn merged_with(&self, other: &AnalysisResult) -> Value {
+        Value::merge(self.to_value(), other.to_value())
+    }
+
     /// Serializes the resolved MON data into a pretty-printed JSON string.
     ///
     /// # Errors
@@ -104,12 +163,189 @@ impl AnalysisResult {
         serde_json::to_string_pretty(&self)
     }
 
+    /// Serializes the resolved MON data into JSON whose bytes only change when the data does,
+    /// independent of the `serde_json` version. See [`Value::to_canonical_json`].
+    #[must_use]
+    pub fn to_canonical_json(&self) -> String {
+        self.to_value().to_canonical_json()
+    }
+
+    /// Serializes the resolved MON data into a compact JSON string without whitespace.
+    ///
+    /// # Errors
+    /// Returns a `serde_json::Error` if serialization fails.
+    pub fn to_json_compact(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self)
+    }
+
     /// Serializes the resolved MON data into a YAML string.
     ///
+    /// Whole numbers are written as integers, e.g. `port: 8080`. Values copied from an anchor
+    /// are written out in full wherever they are used, without YAML anchors or aliases.
+    ///
     /// # Errors
     /// Returns a `serde_yaml::Error` if serialization fails.
     pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
-        serde_yaml::to_string(&self)
+        serde_yaml::to_string(&WholeNumbers(&self.to_value()))
+    }
+
+    /// Writes the resolved MON data as pretty-printed JSON to `writer`, without building the
+    /// whole string in memory first.
+    ///
+    /// # Errors
+    /// Returns a `serde_json::Error` if serialization or writing fails.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &self)
+    }
+
+    /// Writes the resolved MON data as YAML to `writer` like [`to_yaml`](Self::to_yaml),
+    /// without building the whole string in memory first.
+    ///
+    /// # Errors
+    /// Returns a `serde_yaml::Error` if serialization or writing fails.
+    pub fn write_yaml<W: Write>(&self, writer: W) -> Result<(), serde_yaml::Error> {
+        serde_yaml::to_writer(writer, &WholeNumbers(&self.to_value()))
+    }
+
+    /// Deserializes the resolved MON data directly into a typed value, without going through JSON.
+    ///
+    /// The returned value may borrow strings from this result.
+    ///
+    /// # Errors
+    /// Returns a [`DeserializeError`] if the data does not match the target type.
+    pub fn deserialize<'de, T>(&'de self) -> Result<T, DeserializeError>
+    where
+        T: Deserialize<'de>,
+    {
+        from_value(&self.document.root)
+    }
+
+    /// Returns the source text covered by `span`, e.g. the anchor definition found by
+    /// `get_definition_at`.
+    ///
+    /// Returns `None` if `span` lies outside the source or does not start and end on a
+    /// character boundary.
+    #[must_use]
+    pub fn text_at(&self, span: SourceSpan) -> Option<&str> {
+        let start = span.offset();
+        start
+            .checked_add(span.len())
+            .and_then(|end| self.source.get(start..end))
+    }
+
+    /// Replaces the value at a dotted `path` in the resolved document, e.g. `server.port`.
+    ///
+    /// Missing keys along the path are created as empty objects. Only the resolved
+    /// [`document`](Self::document) is changed; `unresolved_document` and `anchors` keep
+    /// reflecting the source. Keys that contain a `.` cannot be addressed.
+    ///
+    /// # Errors
+    /// Returns a [`PathError`] if the path is empty or runs through a value that is not an object.
+    pub fn set(&mut self, path: &str, value: MonValue) -> Result<(), PathError> {
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(PathError::InvalidPath {
+                path: path.to_string(),
+            });
+        }
+
+        let mut current = &mut self.document.root;
+        for (i, segment) in segments.iter().enumerate() {
+            let MonValueKind::Object(members) = &mut current.kind else {
+                return Err(PathError::NotAnObject {
+                    path: path.to_string(),
+                    segment: segments[..i].join("."),
+                });
+            };
+
+            let index = members
+                .iter()
+                .position(|member| matches!(member, Member::Pair(pair) if pair.key == *segment))
+                .unwrap_or_else(|| {
+                    members.push(Member::Pair(Pair {
+                        key: (*segment).to_string(),
+                        key_span: (0, 0).into(),
+                        value: MonValue {
+                            kind: MonValueKind::Object(Vec::new()),
+                            anchor: None,
+                            pos_start: 0,
+                            pos_end: 0,
+                        },
+                        validation: None,
+                        serialized_key: None,
+                    }));
+                    members.len() - 1
+                });
+            let Member::Pair(pair) = &mut members[index] else {
+                unreachable!("the index always points at a pair");
+            };
+            current = &mut pair.value;
+        }
+
+        *current = value;
+        Ok(())
+    }
+
+    /// Returns every value validated with `::` in the source, in document order, with the
+    /// path of its key, e.g. `servers[0].tls`, its type, and the definition of that type.
+    ///
+    /// The definition is that of the struct or enum the type names, or for a collection of a
+    /// single type like `[User...]`, its element type. It is `None` for built-in types. Types
+    /// named through a namespace, e.g. `schemas.User`, are looked up in that namespace's module.
+    #[must_use]
+    pub fn typed_fields(&self) -> Vec<(String, &TypeSpec, Option<&TypeDefinition>)> {
+        fn collect<'a>(value: &'a MonValue, path: &str, fields: &mut Vec<(String, &'a TypeSpec)>) {
+            match &value.kind {
+                MonValueKind::Object(members) => {
+                    for member in members {
+                        let Member::Pair(pair) = member else {
+                            continue;
+                        };
+                        let pair_path = if path.is_empty() {
+                            pair.key.clone()
+                        } else {
+                            format!("{path}.{}", pair.key)
+                        };
+                        if let Some(validation) = &pair.validation {
+                            fields.push((pair_path.clone(), validation));
+                        }
+                        collect(&pair.value, &pair_path, fields);
+                    }
+                }
+                MonValueKind::Array(elements) => {
+                    for (i, element) in elements.iter().enumerate() {
+                        collect(element, &format!("{path}[{i}]"), fields);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut fields = Vec::new();
+        collect(&self.unresolved_document.root, "", &mut fields);
+        fields
+            .into_iter()
+            .map(|(path, type_spec)| (path, type_spec, self.named_type_def(type_spec)))
+            .collect()
+    }
+
+    /// Looks up the struct or enum `type_spec` names, see [`typed_fields`](Self::typed_fields).
+    fn named_type_def(&self, type_spec: &TypeSpec) -> Option<&TypeDefinition> {
+        match type_spec {
+            TypeSpec::Simple(name, _) => match name.split_once('.') {
+                Some((namespace, local_name)) => {
+                    self.symbol_table.namespaces.get(namespace)?.get(local_name)
+                }
+                None => self.symbol_table.types.get(name),
+            },
+            TypeSpec::Spread(element, _) | TypeSpec::Repeat(element, _, _) => {
+                self.named_type_def(element)
+            }
+            TypeSpec::Collection(elements, _) => match elements.as_slice() {
+                [element] => self.named_type_def(element),
+                _ => None,
+            },
+        }
     }
 
     #[cfg(feature = "lsp")]
@@ -117,17 +353,17 @@ impl AnalysisResult {
     /// This is the core of "go to definition".
     #[must_use]
     pub fn get_definition_at(&self, position: usize) -> Option<SourceSpan> {
-        let node = find_node_at(&self.unresolved_document.root, position)?;
+        let node = lsp::find_node_at(&self.unresolved_document.root, position)?;
 
         match node {
-            FoundNode::Value(value) => match &value.kind {
+            lsp::FoundNode::Value(value) => match &value.kind {
                 MonValueKind::Alias(alias_name) => {
                     let anchor_def = self.anchors.get(alias_name)?;
                     Some(anchor_def.get_source_span())
                 }
                 _ => None,
             },
-            FoundNode::TypeSpec(type_spec) => match type_spec {
+            lsp::FoundNode::TypeSpec(type_spec) => match type_spec {
                 TypeSpec::Simple(name, _) => {
                     let type_def = self.symbol_table.types.get(name)?;
                     Some(type_def.def_type.get_span())
@@ -140,17 +376,58 @@ impl AnalysisResult {
     #[cfg(feature = "lsp")]
     /// Gets information about the type of the symbol at the given character position.
     /// This is the core of "hover" tooltips.
+    ///
+    /// A struct type is shown with its fields, e.g. `User { id: Number, role: String = "guest" }`.
+    /// For an array element or the value of a pair, its comments are added below, see [`MonDocument::element_docs`].
     #[must_use]
     pub fn get_type_info_at(&self, position: usize) -> Option<String> {
         let symbol_info = lsp::find_symbol_at(&self.unresolved_document.root, position)?;
 
         if let Some(validation) = symbol_info.validation {
-            return Some(validation.to_string());
+            return Some(self.describe_type_spec(validation));
         }
 
         match symbol_info.node {
-            lsp::FoundNode::Value(value) => Some(value.kind.to_string()),
-            lsp::FoundNode::TypeSpec(type_spec) => Some(type_spec.to_string()),
+            lsp::FoundNode::Value(value) => {
+                let info = value.kind.to_string();
+                match self.unresolved_document.element_docs.get(&value.pos_start) {
+                    Some(doc) => Some(format!("{info}\n\n{}", doc.text())),
+                    None => Some(info),
+                }
+            }
+            lsp::FoundNode::TypeSpec(type_spec) => Some(self.describe_type_spec(type_spec)),
+        }
+    }
+
+    #[cfg(feature = "lsp")]
+    /// Formats a type for hover, listing the fields of a struct defined in the symbol table.
+    fn describe_type_spec(&self, type_spec: &TypeSpec) -> String {
+        let TypeSpec::Simple(name, _) = type_spec else {
+            return type_spec.to_string();
+        };
+        let Some(TypeDef::Struct(struct_def)) =
+            self.symbol_table.types.get(name).map(|td| &td.def_type)
+        else {
+            return name.clone();
+        };
+
+        let mut fields: Vec<String> = struct_def
+            .fields
+            .iter()
+            .map(|field| match &field.default_value {
+                Some(default_value) => {
+                    format!("{}: {} = {default_value}", field.name, field.type_spec)
+                }
+                None => format!("{}: {}", field.name, field.type_spec),
+            })
+            .collect();
+        if struct_def.open {
+            fields.push("...".to_string());
+        }
+        if fields.is_empty() {
+            format!("{name} {{}}")
+        } else {
+            format!("{name} {{ {} }}", fields.join(", "))
         }
     }
     #[cfg(feature = "lsp")]
@@ -181,66 +458,28 @@ impl AnalysisResult {
             .collect();
         Some(usages)
     }
-}
-
-#[cfg(feature = "lsp")]
-#[derive(Debug, Clone, Copy)]
-/// An AST node found during a location-based query.
-enum FoundNode<'a> {
-    Value(&'a MonValue),
-    TypeSpec(&'a TypeSpec),
-}
-
-#[cfg(feature = "lsp")]
-/// Finds the most specific AST node that contains the given character position.
-fn find_node_at(value: &MonValue, position: usize) -> Option<FoundNode<'_>> {
-    if position < value.pos_start || position >= value.pos_end {
-        return None;
-    }
-
-    if let MonValueKind::Object(members) = &value.kind {
-        for member in members {
-            if let Member::Pair(pair) = member {
-                if let Some(validation) = &pair.validation {
-                    if let Some(found) = find_node_in_type_spec(validation, position) {
-                        return Some(found);
-                    }
-                }
-                if let Some(found) = find_node_at(&pair.value, position) {
-                    return Some(found);
-                }
-            }
-        }
-    }
 
-    if let MonValueKind::Array(elements) = &value.kind {
-        for element in elements {
-            if let Some(found) = find_node_at(element, position) {
-                return Some(found);
-            }
-        }
+    /// Returns the names of all anchors declared in this document, in document order.
+    ///
+    /// Anchors brought in through imports are not included; use [`Self::contains_anchor`]
+    /// to check whether an alias can be resolved.
+    #[must_use]
+    pub fn anchor_names(&self) -> Vec<&str> {
+        self.unresolved_document.root.anchor_names()
     }
 
-    Some(FoundNode::Value(value))
-}
-
-#[cfg(feature = "lsp")]
-/// Recursively finds the most specific `TypeSpec` node containing the given position.
-fn find_node_in_type_spec(type_spec: &TypeSpec, position: usize) -> Option<FoundNode<'_>> {
-    let span = type_spec.get_span();
-    if position < span.offset() || position >= span.offset() + span.len() {
-        return None;
+    /// Returns `true` if an anchor with the given name is visible to this document,
+    /// either declared in it or imported.
+    #[must_use]
+    pub fn contains_anchor(&self, name: &str) -> bool {
+        self.anchors.contains_key(name)
     }
 
-    if let TypeSpec::Collection(children, _) = type_spec {
-        for child in children {
-            if let Some(found) = find_node_in_type_spec(child, position) {
-                return Some(found);
-            }
-        }
+    /// Returns every alias and spread in this document, with the anchor name it refers to.
+    #[must_use]
+    pub fn alias_usages(&self) -> Vec<(&str, SourceSpan)> {
+        self.unresolved_document.root.alias_usages()
     }
-
-    Some(FoundNode::TypeSpec(type_spec))
 }
 
 /// Analyzes a MON source string, parsing, resolving, and validating it.
@@ -256,117 +495,1959 @@ fn find_node_in_type_spec(type_spec: &TypeSpec, position: usize) -> Option<Found
 ///
 /// # Errors
 ///
+/// Returns a [`MonError`] if parsing, resolution, or validation fails, or if `file_name` is
+/// relative and the current directory cannot be determined.
+pub fn analyze(source: &str, file_name: &str) -> Result<AnalysisResult, MonError> {
+    analyze_with_options(source, file_name, ResolveOptions::default())
+}
+
+/// Analyzes a MON source string like [`analyze`], with the given [`ResolveOptions`].
+///
+/// The document and its imported modules are tokenized with [`ResolveOptions::lexer`], e.g.
+/// to read `yes` and `no` as booleans.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze`].
+///
+/// # Example
+///
+/// ```rust
+/// use mon_core::api::analyze_with_options;
+/// use mon_core::lexer::LexerOptions;
+/// use mon_core::resolver::ResolveOptions;
+///
+/// let options = ResolveOptions::default().with_lexer_options(LexerOptions::default().with_yes_no());
+/// let result = analyze_with_options("{ debug: yes }", "app.mon", options).unwrap();
+/// assert_eq!(result.to_json().unwrap(), "{\n  \"debug\": true\n}");
+/// ```
+pub fn analyze_with_options(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+) -> Result<AnalysisResult, MonError> {
+    analyze_at(source, file_name, absolute_file_path(file_name)?, options)
+}
+
+/// Analyzes a MON source string like [`analyze`], but resolves a relative `file_name`, and
+/// with it the document's relative imports, against `base_dir` instead of the current
+/// directory.
+///
+/// `base_dir` should be absolute. This never looks at the current directory, e.g. for
+/// sandboxed environments that have none.
+///
+/// # Arguments
+///
+/// * `source` - The MON source code as a string.
+/// * `file_name` - The name of the file being analyzed (used for error reporting and imports).
+/// * `base_dir` - The directory a relative `file_name` is in.
+///
+/// # Errors
+///
 /// Returns a [`MonError`] if parsing, resolution, or validation fails.
+pub fn analyze_in(
+    source: &str,
+    file_name: &str,
+    base_dir: &Path,
+) -> Result<AnalysisResult, MonError> {
+    analyze_at(
+        source,
+        file_name,
+        base_dir.join(file_name),
+        ResolveOptions::default(),
+    )
+}
+
+/// Checks that a MON source string parses, resolves, and validates, like [`analyze`], without
+/// building an [`AnalysisResult`].
 ///
-/// # Panics
+/// This skips keeping a copy of the unresolved document, e.g. for checking many files in CI.
 ///
-/// Panics if the current directory cannot be determined when `file_name` is relative.
-pub fn analyze(source: &str, file_name: &str) -> Result<AnalysisResult, MonError> {
-    let mut parser = Parser::new_with_name(source, file_name.to_string())?;
+/// # Errors
+///
+/// Returns the [`MonError`] that [`analyze`] would return.
+pub fn validate_only(source: &str, file_name: &str) -> Result<(), MonError> {
+    validate_only_with_options(source, file_name, ResolveOptions::default())
+}
+
+/// Checks a MON source string like [`validate_only`], with the given [`ResolveOptions`].
+///
+/// # Errors
+///
+/// Returns the [`MonError`] that [`analyze_with_options`] would return.
+pub fn validate_only_with_options(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+) -> Result<(), MonError> {
+    let path = absolute_file_path(file_name)?;
+    let document = Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())?
+        .parse_document()?;
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    resolver.resolve(document, source, path, None)?;
+    Ok(())
+}
+
+/// Returns whether a MON source string parses, resolves, and validates, see [`validate_only`].
+#[must_use]
+pub fn is_valid(source: &str, file_name: &str) -> bool {
+    validate_only(source, file_name).is_ok()
+}
+
+/// Analyzes `source` as the document at the absolute `path`, see [`analyze`].
+fn analyze_at(
+    source: &str,
+    file_name: &str,
+    path: PathBuf,
+    options: ResolveOptions,
+) -> Result<AnalysisResult, MonError> {
+    let mut parser =
+        Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())?;
+    if cfg!(feature = "lsp") {
+        // Hover shows the comments of array elements
+        parser = parser.with_comments();
+    }
     let document = parser.parse_document()?;
-    let unresolved_document = document.clone();
 
     let mut resolver = Resolver::new();
-    let mut path = PathBuf::from(file_name);
-    if path.is_relative() {
-        path = std::env::current_dir().unwrap().join(path);
+    resolver.set_options(options);
+    resolve_with(resolver, document, source, path)
+}
+
+/// Analyzes a MON source string like [`analyze`], and measures how long each phase took, e.g.
+/// to find out why a file is slow to analyze in an editor.
+///
+/// Timing is only done here, [`analyze`] and the other entry points do not measure anything.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze`].
+pub fn analyze_with_metrics(
+    source: &str,
+    file_name: &str,
+) -> Result<(AnalysisResult, AnalyzeMetrics), MonError> {
+    analyze_with_metrics_and_options(source, file_name, ResolveOptions::default())
+}
+
+/// Analyzes and measures a MON source string like [`analyze_with_metrics`], with the given
+/// [`ResolveOptions`].
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze_with_options`].
+pub fn analyze_with_metrics_and_options(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+) -> Result<(AnalysisResult, AnalyzeMetrics), MonError> {
+    let path = absolute_file_path(file_name)?;
+    let lex_start = Instant::now();
+    let mut parser =
+        Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())?;
+    let lex_time = lex_start.elapsed();
+    if cfg!(feature = "lsp") {
+        parser = parser.with_comments();
     }
+    let parse_start = Instant::now();
+    let document = parser.parse_document()?;
+    let parse_time = parse_start.elapsed();
 
+    let unresolved_document = document.clone();
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    resolver.metrics = Some(AnalyzeMetrics::default());
+    let resolve_start = Instant::now();
     let resolved_doc = resolver.resolve(document, source, path, None)?;
+    let resolve_elapsed = resolve_start.elapsed();
 
-    Ok(AnalysisResult {
+    let mut metrics = resolver.metrics.take().unwrap_or_default();
+    // The resolver lexes, parses and validates modules itself, which is counted there
+    metrics.resolve_time = resolve_elapsed
+        .saturating_sub(metrics.lex_time + metrics.parse_time + metrics.validate_time);
+    metrics.lex_time += lex_time;
+    metrics.parse_time += parse_time;
+
+    let result = AnalysisResult {
         document: resolved_doc,
         unresolved_document,
         symbol_table: resolver.symbol_table,
         anchors: resolver.anchors,
-    })
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: source.to_string(),
+    };
+    Ok((result, metrics))
 }
 
-impl Display for TypeSpec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TypeSpec::Simple(name, _) => write!(f, "{name}"),
-            TypeSpec::Collection(types, _) => {
-                write!(f, "[")?;
-                for (i, t) in types.iter().enumerate() {
-                    write!(f, "{t}")?;
-                    if i < types.len() - 1 {
-                        write!(f, ", ")?;
-                    }
-                }
-                write!(f, "]")
-            }
-            TypeSpec::Spread(t, _) => write!(f, "{t}..."),
-        }
-    }
+/// Analyzes a MON source string like [`analyze`], but reports every validation error instead
+/// of stopping at the first, e.g. to show all diagnostics of a file in an editor at once.
+///
+/// The result is `None` if any error occurred. Parsing and resolution errors other than
+/// validation still stop the analysis, and are returned as the only error. See
+/// [`Resolver::resolve_collect`] for which validation errors are found.
+///
+/// # Arguments
+///
+/// * `source` - The MON source code as a string.
+/// * `file_name` - The name of the file being analyzed (used for error reporting and imports).
+#[must_use]
+pub fn analyze_all(source: &str, file_name: &str) -> (Option<AnalysisResult>, Vec<MonError>) {
+    analyze_all_with_options(source, file_name, ResolveOptions::default())
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::analyze;
-
-    #[test]
-    fn test_simple_parse_to_json() {
-        let source = r#"{
-            name: "My App",
-            version: 1.0,
-            is_enabled: true,
-            features: ["a", "b", "c"],
-            config: {
-                host: "localhost",
-                port: 8080.0,
-            }
-        }"#;
-
-        let expected_json = serde_json::json!({
-            "name": "My App",
-            "version": 1.0,
-            "is_enabled": true,
-            "features": ["a", "b", "c"],
-            "config": {
-                "host": "localhost",
-                "port": 8080.0,
+/// Analyzes a MON source string like [`analyze_all`], with the given [`ResolveOptions`].
+#[must_use]
+pub fn analyze_all_with_options(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+) -> (Option<AnalysisResult>, Vec<MonError>) {
+    let parsed = Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())
+        .and_then(|mut parser| {
+            if cfg!(feature = "lsp") {
+                parser = parser.with_comments();
             }
+            parser.parse_document()
         });
+    let document = match parsed {
+        Ok(document) => document,
+        Err(err) => return (None, vec![err]),
+    };
+    let unresolved_document = document.clone();
+    let path = match absolute_file_path(file_name) {
+        Ok(path) => path,
+        Err(err) => return (None, vec![err.into()]),
+    };
 
-        let analysis_result = analyze(source, "test.mon").unwrap();
-        let result = analysis_result.to_json().unwrap();
-        let result_json: serde_json::Value = serde_json::from_str(&result).unwrap();
-
-        assert_eq!(result_json, expected_json);
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    match resolver.resolve_collect(document, source, path) {
+        Ok((resolved_doc, errors)) if errors.is_empty() => (
+            Some(AnalysisResult {
+                document: resolved_doc,
+                unresolved_document,
+                symbol_table: resolver.symbol_table,
+                anchors: resolver.anchors,
+                dependencies: resolver.dependencies,
+                warnings: resolver.warnings,
+                source: source.to_string(),
+            }),
+            Vec::new(),
+        ),
+        Ok((_, errors)) => (
+            None,
+            errors
+                .into_iter()
+                .map(|err| ResolverError::Validation(err).into())
+                .collect(),
+        ),
+        Err(err) => (None, vec![err.into()]),
     }
+}
 
-    #[test]
-    fn test_analyze_semantic_info() {
-        let source = r#"{
-            MyType: #struct { field(String) },
-            &my_anchor: { a: 1 },
-            value: *my_anchor,
-        }"#;
-
-        let analysis_result = analyze(source, "test.mon").unwrap();
-
-        // Check symbol table
-        assert!(analysis_result.symbol_table.types.contains_key("MyType"));
+/// Analyzes a MON source string like [`analyze`], and validates its whole root object against
+/// the type `root_type`, as if the document were annotated with `:: root_type`.
+///
+/// The type can be defined in the document itself or in a module it imports. Type
+/// definitions in the root are not checked as fields, so a document can carry its own schema:
+/// `{ Config: #struct { port(Number) }, port: 8080 }`. Bare words in the root can name variants
+/// of enum fields of `root_type`, see [`validate_against`] for a schema in a separate file.
+///
+/// # Arguments
+///
+/// * `source` - The MON source code as a string.
+/// * `file_name` - The name of the file being analyzed (used for error reporting and imports).
+/// * `root_type` - The name of the type the root must match.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if parsing, resolution, or validation fails, or if `root_type` is
+/// not defined.
+pub fn analyze_typed(
+    source: &str,
+    file_name: &str,
+    root_type: &str,
+) -> Result<AnalysisResult, MonError> {
+    analyze_typed_with_options(source, file_name, root_type, ResolveOptions::default())
+}
 
-        // Check anchors
-        assert!(analysis_result.anchors.contains_key("my_anchor"));
+/// Analyzes a MON source string against `root_type` like [`analyze_typed`], with the given
+/// [`ResolveOptions`].
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze_typed`].
+pub fn analyze_typed_with_options(
+    source: &str,
+    file_name: &str,
+    root_type: &str,
+    options: ResolveOptions,
+) -> Result<AnalysisResult, MonError> {
+    let mut parser =
+        Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())?;
+    if cfg!(feature = "lsp") {
+        parser = parser.with_comments();
     }
+    let document = parser.parse_document()?;
+    let unresolved_document = document.clone();
 
-    #[test]
-    fn test_simple_parse_to_yaml() {
-        let source = r#"
-{
-        name: "My App",
-        version: 1.0,
-    is_enabled: true,
-}"#;
-
-        let expected_yaml = "is_enabled: true\nname: My App\nversion: 1.0\n";
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    let path = absolute_file_path(file_name)?;
+    let resolved_doc = resolver.resolve_as(document, source, path, root_type)?;
 
-        let analysis_result = analyze(source, "test.mon").unwrap();
-        let result = analysis_result.to_yaml().unwrap();
+    Ok(AnalysisResult {
+        document: resolved_doc,
+        unresolved_document,
+        symbol_table: resolver.symbol_table,
+        anchors: resolver.anchors,
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: source.to_string(),
+    })
+}
+
+/// Analyzes the MON block fenced by `fence` inside a larger document, e.g. a ```` ```mon ````
+/// code block in a Markdown file. See [`extract_mon`] for how the block is found.
+///
+/// Error spans point into `text`, so reports show the location in the host document.
+///
+/// # Arguments
+///
+/// * `text` - The host document.
+/// * `file_name` - The name of the host document (used for error reporting and imports).
+/// * `fence` - The line that opens the MON block, e.g. ```` ```mon ```` or `--- data`.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if parsing, resolution, or validation of the block fails.
+pub fn analyze_fenced(
+    text: &str,
+    file_name: &str,
+    fence: &str,
+) -> Result<Option<AnalysisResult>, MonError> {
+    match extract_mon(text, fence) {
+        Some(source) => analyze(&source, file_name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Resolves and validates an already parsed [`MonDocument`], skipping the parsing stage.
+///
+/// This is useful when a document is constructed or transformed programmatically and
+/// should be validated in memory without serializing it back to text first.
+///
+/// # Arguments
+///
+/// * `doc` - The [`MonDocument`] to resolve.
+/// * `source` - The source text the document's spans refer to (used for error reporting).
+/// * `path` - The absolute path of the document, used for resolving relative imports.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if resolution or validation fails.
+pub fn resolve_document(
+    doc: MonDocument,
+    source: &str,
+    path: PathBuf,
+) -> Result<AnalysisResult, MonError> {
+    resolve_with(Resolver::new(), doc, source, path)
+}
+
+/// Resolves `doc` with `resolver` and builds its [`AnalysisResult`], see [`resolve_document`].
+fn resolve_with(
+    mut resolver: Resolver,
+    doc: MonDocument,
+    source: &str,
+    path: PathBuf,
+) -> Result<AnalysisResult, MonError> {
+    let unresolved_document = doc.clone();
+    let resolved_doc = resolver.resolve(doc, source, path, None)?;
+
+    Ok(AnalysisResult {
+        document: resolved_doc,
+        unresolved_document,
+        symbol_table: resolver.symbol_table,
+        anchors: resolver.anchors,
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: source.to_string(),
+    })
+}
+
+/// Analyzes a MON source string like [`analyze`], but streams the elements of root-level
+/// arrays to `on_element` instead of keeping them in the result.
+///
+/// Each element is resolved before it is passed on, and validated against the element type
+/// if its key is annotated with `[T...]` or `[T; N]`. This bounds memory when processing a
+/// large list of records one at a time, e.g. `{ records: [ ... ] }`.
+///
+/// Elements are resolved while the document is still being parsed, so they can only use
+/// anchors declared before the array. Length constraints on streamed arrays are not checked,
+/// and arrays with an anchor (`&items: [...]`) are not streamed. The returned
+/// [`AnalysisResult`] holds the rest of the document, with the streamed arrays empty.
+///
+/// # Arguments
+///
+/// * `source` - The MON source code as a string.
+/// * `file_name` - The name of the file being analyzed (used for error reporting).
+/// * `on_element` - Called with the key of the array and each resolved element, in order.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if parsing, resolution, or validation fails, or if `on_element`
+/// returns an error.
+pub fn analyze_streaming<F>(
+    source: &str,
+    file_name: &str,
+    on_element: F,
+) -> Result<AnalysisResult, MonError>
+where
+    F: FnMut(&str, MonValue) -> Result<(), MonError>,
+{
+    analyze_streaming_with_options(source, file_name, ResolveOptions::default(), on_element)
+}
+
+/// Analyzes a MON source string and streams its root-level arrays like [`analyze_streaming`],
+/// with the given [`ResolveOptions`].
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze_streaming`].
+pub fn analyze_streaming_with_options<F>(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+    mut on_element: F,
+) -> Result<AnalysisResult, MonError>
+where
+    F: FnMut(&str, MonValue) -> Result<(), MonError>,
+{
+    let path = absolute_file_path(file_name)?;
+    let lexer_options = options.lexer.clone();
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    let mut streamed_keys = Vec::new();
+
+    let document = {
+        let sink = ResolvingSink {
+            resolver: &mut resolver,
+            source,
+            file_path: &path,
+            imports: Vec::new(),
+            streamed_keys: &mut streamed_keys,
+            on_element: &mut on_element,
+        };
+        let mut parser = Parser::new_with_options(source, file_name.to_string(), lexer_options)?;
+        parser.parse_document_streaming(sink)?
+    };
+
+    // The elements were validated as they were streamed, and the emptied arrays would not
+    // pass length checks, so their annotations are not validated again.
+    let mut to_resolve = document.clone();
+    if let MonValueKind::Object(members) = &mut to_resolve.root.kind {
+        for member in members {
+            if let Member::Pair(pair) = member {
+                if streamed_keys.iter().any(|(key, _)| *key == pair.key) {
+                    pair.validation = None;
+                }
+            }
+        }
+    }
+    let resolved_doc = resolver.resolve(to_resolve, source, path, None)?;
+
+    Ok(AnalysisResult {
+        document: resolved_doc,
+        unresolved_document: document,
+        symbol_table: resolver.symbol_table,
+        anchors: resolver.anchors,
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: source.to_string(),
+    })
+}
+
+/// Resolves streamed array elements with the imports and anchors seen so far.
+struct ResolvingSink<'r, F> {
+    resolver: &'r mut Resolver,
+    source: &'r str,
+    file_path: &'r PathBuf,
+    imports: Vec<ImportStatement>,
+    /// The keys of the arrays streamed so far, with the number of elements of each.
+    streamed_keys: &'r mut Vec<(String, usize)>,
+    on_element: &'r mut F,
+}
+
+impl<F> DocumentSink for ResolvingSink<'_, F>
+where
+    F: FnMut(&str, MonValue) -> Result<(), MonError>,
+{
+    fn on_imports(&mut self, imports: &[ImportStatement]) -> Result<(), MonError> {
+        self.imports = imports.to_vec();
+        self.resolver
+            .resolve_imports(imports, self.source, self.file_path)?;
+        Ok(())
+    }
+
+    fn on_root_member(&mut self, member: &Member) -> Result<(), MonError> {
+        if let Member::TypeDefinition(_) | Member::Guarded(..) = member {
+            let Some(member) = unwrap_guards(member.clone(), &self.resolver.options().env) else {
+                return Ok(());
+            };
+            // Streamed elements can only use structs that extend ones defined before them
+            let mut members = [member];
+            self.resolver.add_base_fields(
+                &mut members,
+                &self.imports,
+                self.file_path,
+                self.source,
+            )?;
+            self.resolver
+                .collect_member_symbols(&members[0], self.file_path, self.source);
+        } else {
+            self.resolver
+                .collect_member_symbols(member, self.file_path, self.source);
+        }
+        Ok(())
+    }
+
+    fn on_element(
+        &mut self,
+        key: &str,
+        validation: Option<&TypeSpec>,
+        element: MonValue,
+    ) -> Result<(), MonError> {
+        let index = match self.streamed_keys.iter_mut().find(|(k, _)| k == key) {
+            Some((_, count)) => {
+                *count += 1;
+                *count - 1
+            }
+            None => {
+                self.streamed_keys.push((key.to_string(), 1));
+                0
+            }
+        };
+        let element = self.resolver.resolve_streamed_element(
+            element,
+            validation,
+            key,
+            index,
+            &self.imports,
+            self.file_path,
+            self.source,
+        )?;
+        (self.on_element)(key, element)
+    }
+}
+
+/// Analyzes a multi-document MON source string, where documents are separated by `---`.
+///
+/// Each document is resolved and validated independently, with its own symbol table and
+/// anchors, but imported modules are only loaded once and shared between documents.
+///
+/// # Arguments
+///
+/// * `source` - The MON source code as a string.
+/// * `file_name` - The name of the file being analyzed (used for error reporting).
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if parsing, resolution, or validation of any document fails.
+pub fn analyze_multi(source: &str, file_name: &str) -> Result<Vec<AnalysisResult>, MonError> {
+    analyze_multi_with_options(source, file_name, ResolveOptions::default())
+}
+
+/// Analyzes a multi-document MON source string like [`analyze_multi`], with the given
+/// [`ResolveOptions`] for every document.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze_multi`].
+pub fn analyze_multi_with_options(
+    source: &str,
+    file_name: &str,
+    options: ResolveOptions,
+) -> Result<Vec<AnalysisResult>, MonError> {
+    let mut parser =
+        Parser::new_with_options(source, file_name.to_string(), options.lexer.clone())?;
+    let documents = parser.parse_documents()?;
+
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    let path = absolute_file_path(file_name)?;
+
+    let mut results = Vec::with_capacity(documents.len());
+    for document in documents {
+        let unresolved_document = document.clone();
+        let resolved_doc = resolver.resolve(document, source, path.clone(), None)?;
+        let (symbol_table, anchors) = resolver.take_symbols();
+
+        results.push(AnalysisResult {
+            document: resolved_doc,
+            unresolved_document,
+            symbol_table,
+            anchors,
+            dependencies: std::mem::take(&mut resolver.dependencies),
+            warnings: std::mem::take(&mut resolver.warnings),
+            source: source.to_string(),
+        });
+    }
+    Ok(results)
+}
+
+/// Validates a data document against the types defined in a separate schema document.
+///
+/// The schema's root object holds the type definitions, e.g. `{ Config: #struct { ... } }`,
+/// and the data document needs no inline types. The data's root is validated as if it were
+/// annotated with `:: root_type`. Only the schema's types are shared with the data, its
+/// anchors are not.
+///
+/// # Arguments
+///
+/// * `data_source` - The MON source of the data document.
+/// * `data_file_name` - The name of the data file (used for error reporting).
+/// * `schema_source` - The MON source of the schema document.
+/// * `schema_file_name` - The name of the schema file (used for error reporting and imports).
+/// * `root_type` - The name of the schema type the data's root must match.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if either document fails to parse or resolve, if `root_type` is
+/// not defined, or if the data does not match it.
+pub fn validate_against(
+    data_source: &str,
+    data_file_name: &str,
+    schema_source: &str,
+    schema_file_name: &str,
+    root_type: &str,
+) -> Result<AnalysisResult, MonError> {
+    validate_against_with_options(
+        data_source,
+        data_file_name,
+        schema_source,
+        schema_file_name,
+        root_type,
+        ResolveOptions::default(),
+    )
+}
+
+/// Validates a data document against a schema document like [`validate_against`], with the
+/// given [`ResolveOptions`] for both documents.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`validate_against`].
+pub fn validate_against_with_options(
+    data_source: &str,
+    data_file_name: &str,
+    schema_source: &str,
+    schema_file_name: &str,
+    root_type: &str,
+    options: ResolveOptions,
+) -> Result<AnalysisResult, MonError> {
+    let lexer_options = options.lexer.clone();
+    let mut schema_parser = Parser::new_with_options(
+        schema_source,
+        schema_file_name.to_string(),
+        lexer_options.clone(),
+    )?;
+    let schema = schema_parser.parse_document()?;
+
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    resolver.resolve(
+        schema,
+        schema_source,
+        absolute_file_path(schema_file_name)?,
+        None,
+    )?;
+    resolver.anchors.clear();
+
+    let mut parser =
+        Parser::new_with_options(data_source, data_file_name.to_string(), lexer_options)?;
+    let document = parser.parse_document()?;
+    let unresolved_document = document.clone();
+
+    let path = absolute_file_path(data_file_name)?;
+    let resolved_doc = resolver.resolve_as(document, data_source, path, root_type)?;
+
+    Ok(AnalysisResult {
+        document: resolved_doc,
+        unresolved_document,
+        symbol_table: resolver.symbol_table,
+        anchors: resolver.anchors,
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: data_source.to_string(),
+    })
+}
+
+/// Analyzes the MON files at `paths` in order and deep-merges their data, e.g. a base
+/// configuration followed by environment overrides like `["base.mon", "prod.mon"]`.
+///
+/// Later files override earlier ones with the rules of [`Value::merge`]: objects are merged
+/// key by key, everything else is replaced. Types and anchors of earlier files can be used by
+/// later ones without importing them. Each file is validated on its own, the merged data is
+/// not validated again.
+///
+/// Spans in the merged document point into different files, and the `unresolved_document`
+/// and `source` of the result are the last file's. An empty `paths` gives an empty object.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] if a file cannot be read, see [`ResolverError::LayerUnreadable`], or
+/// if parsing, resolution, or validation of a file fails.
+pub fn analyze_layers(paths: &[&str]) -> Result<AnalysisResult, MonError> {
+    analyze_layers_with_options(paths, ResolveOptions::default())
+}
+
+/// Analyzes and merges the MON files at `paths` like [`analyze_layers`], with the given
+/// [`ResolveOptions`] for every file.
+///
+/// # Errors
+///
+/// Returns a [`MonError`] like [`analyze_layers`].
+pub fn analyze_layers_with_options(
+    paths: &[&str],
+    options: ResolveOptions,
+) -> Result<AnalysisResult, MonError> {
+    let lexer_options = options.lexer.clone();
+    let mut resolver = Resolver::new();
+    resolver.set_options(options);
+    let mut merged: Option<MonDocument> = None;
+    let mut unresolved_document = None;
+    let mut last_source = String::new();
+    for path in paths {
+        let file_path = absolute_file_path(path)?;
+        let source =
+            std::fs::read_to_string(&file_path).map_err(|err| ResolverError::LayerUnreadable {
+                path: (*path).to_string(),
+                reason: err.to_string(),
+            })?;
+        let document = Parser::new_with_options(
+            &source,
+            file_path.to_string_lossy().to_string(),
+            lexer_options.clone(),
+        )?
+        .parse_document()?;
+        unresolved_document = Some(document.clone());
+
+        let resolved_doc = resolver.resolve(document, &source, file_path, None)?;
+        merged = Some(match merged {
+            Some(base) => merge_documents(base, resolved_doc),
+            None => resolved_doc,
+        });
+        last_source = source;
+    }
+
+    let document = merged.unwrap_or_else(|| MonDocument {
+        root: MonValue {
+            kind: MonValueKind::Object(Vec::new()),
+            anchor: None,
+            pos_start: 0,
+            pos_end: 0,
+        },
+        imports: Vec::new(),
+        metadata: HashMap::new(),
+        element_docs: HashMap::new(),
+        number_literals: HashMap::new(),
+    });
+    Ok(AnalysisResult {
+        unresolved_document: unresolved_document.unwrap_or_else(|| document.clone()),
+        document,
+        symbol_table: resolver.symbol_table,
+        anchors: resolver.anchors,
+        dependencies: resolver.dependencies,
+        warnings: resolver.warnings,
+        source: last_source,
+    })
+}
+
+/// Deep-merges the resolved document `overlay` on top of `base`, see [`analyze_layers`].
+fn merge_documents(mut base: MonDocument, overlay: MonDocument) -> MonDocument {
+    base.root = merge_values(base.root, overlay.root);
+    base.imports.extend(overlay.imports);
+    base.metadata.extend(overlay.metadata);
+    base.element_docs = overlay.element_docs;
+    base.number_literals = overlay.number_literals;
+    base
+}
+
+/// Merges two resolved values like [`Value::merge`], keeping the type definitions of both.
+fn merge_values(base: MonValue, overlay: MonValue) -> MonValue {
+    let kind = match (base.kind, overlay.kind) {
+        (MonValueKind::Object(mut members), MonValueKind::Object(overlay_members)) => {
+            for overlay_member in overlay_members {
+                let Member::Pair(overlay_pair) = overlay_member else {
+                    members.push(overlay_member);
+                    continue;
+                };
+                let existing = members.iter_mut().find_map(|member| match member {
+                    Member::Pair(pair) if pair.key == overlay_pair.key => Some(pair),
+                    _ => None,
+                });
+                match existing {
+                    Some(pair) => {
+                        pair.value = merge_values(pair.value.clone(), overlay_pair.value);
+                        if overlay_pair.validation.is_some() {
+                            pair.validation = overlay_pair.validation;
+                        }
+                    }
+                    None => members.push(Member::Pair(overlay_pair)),
+                }
+            }
+            MonValueKind::Object(members)
+        }
+        (_, kind) => kind,
+    };
+    MonValue {
+        kind,
+        anchor: overlay.anchor.or(base.anchor),
+        pos_start: overlay.pos_start,
+        pos_end: overlay.pos_end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::SourceSpan;
+
+    use crate::error::MonError;
+    use crate::lexer::LexerOptions;
+    use crate::resolver::ResolveOptions;
+    use crate::{
+        analyze, analyze_all, analyze_all_with_options, analyze_fenced, analyze_in, analyze_layers,
+        analyze_layers_with_options, analyze_multi_with_options, analyze_streaming_with_options,
+        analyze_typed, analyze_typed_with_options, analyze_with_metrics_and_options,
+        analyze_with_options, validate_against_with_options, validate_only,
+        validate_only_with_options,
+    };
+
+    #[test]
+    fn test_simple_parse_to_json() {
+        let source = r#"{
+            name: "My App",
+            version: 1.0,
+            is_enabled: true,
+            features: ["a", "b", "c"],
+            config: {
+                host: "localhost",
+                port: 8080.0,
+            }
+        }"#;
+
+        let expected_json = serde_json::json!({
+            "name": "My App",
+            "version": 1.0,
+            "is_enabled": true,
+            "features": ["a", "b", "c"],
+            "config": {
+                "host": "localhost",
+                "port": 8080.0,
+            }
+        });
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+        let result = analysis_result.to_json().unwrap();
+        let result_json: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(result_json, expected_json);
+    }
+
+    #[test]
+    fn test_analyze_semantic_info() {
+        let source = r#"{
+            MyType: #struct { field(String) },
+            &my_anchor: { a: 1 },
+            value: *my_anchor,
+        }"#;
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+
+        // Check symbol table
+        assert!(analysis_result.symbol_table.types.contains_key("MyType"));
+
+        // Check anchors
+        assert!(analysis_result.anchors.contains_key("my_anchor"));
+    }
+
+    #[test]
+    fn test_analyze_multi_documents_are_independent() {
+        let source = "{ &shared: 1, first: *shared }\n---\n{ second: 2 }";
+        let results = crate::analyze_multi(source, "test.mon").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].anchors.contains_key("shared"));
+        assert!(results[1].anchors.is_empty());
+
+        // Anchors do not leak from one document into the next.
+        let source = "{ &shared: 1 }\n---\n{ second: *shared }";
+        assert!(crate::analyze_multi(source, "test.mon").is_err());
+    }
+
+    #[test]
+    fn test_analyze_multi_cached_imports_follow_their_spec() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("shared.mon"),
+            "{ User: #struct { name(String) }, &port: 8080, &host: \"localhost\" }",
+        )
+        .unwrap();
+        let main_path = dir.path().join("main.mon");
+        let main_path = main_path.to_string_lossy();
+
+        // The second document finds the module cached, and gets only what it imports
+        let source = r#"import { User, &port } from "./shared.mon"
+{ admin :: User = { name: "root" }, port: *port }
+---
+import { User as Member, &port as server_port } from "./shared.mon"
+import shared from "./shared.mon"
+{ admin :: Member = { name: "root" }, port: *server_port, server: { ...*shared } }"#;
+        let results = crate::analyze_multi(source, &main_path).unwrap();
+        let second = serde_json::to_value(results[1].to_value()).unwrap();
+        assert_eq!(second["port"], 8080.0);
+        assert_eq!(second["server"]["host"], "localhost");
+        assert!(!results[1].anchors.contains_key("port"));
+        assert!(!results[1].anchors.contains_key("host"));
+
+        let source = r#"import { &port } from "./shared.mon"
+{ port: *port }
+---
+import { &port } from "./shared.mon"
+{ host: *host }"#;
+        assert!(crate::analyze_multi(source, &main_path).is_err());
+    }
+
+    #[test]
+    fn test_resolve_document() {
+        let source = "{ &port: 8080, server: { port: *port } }";
+        let document = crate::parser::Parser::new(source)
+            .unwrap()
+            .parse_document()
+            .unwrap();
+
+        let result =
+            crate::api::resolve_document(document.clone(), source, "/tmp/test.mon".into()).unwrap();
+
+        assert_eq!(result.unresolved_document, document);
+        assert!(result.anchors.contains_key("port"));
+        assert!(result.to_json().unwrap().contains("\"port\": 8080.0"));
+    }
+
+    #[test]
+    fn test_yaml_inlines_anchors_and_writes_integers() {
+        let source = r#"{
+            &defaults: { port: 8080, ratio: 0.5, retries: -3 },
+            primary: *defaults,
+            replica: { ...*defaults, port: 8081 },
+            big: 12300000000,
+        }"#;
+        let result = analyze(source, "yaml.mon").unwrap();
+        let yaml = result.to_yaml().unwrap();
+        assert_eq!(
+            yaml,
+            "big: 12300000000\n\
+             defaults:\n  port: 8080\n  ratio: 0.5\n  retries: -3\n\
+             primary:\n  port: 8080\n  ratio: 0.5\n  retries: -3\n\
+             replica:\n  port: 8081\n  ratio: 0.5\n  retries: -3\n"
+        );
+        assert!(!yaml.contains('&') && !yaml.contains('*'));
+
+        let mut written = Vec::new();
+        result.write_yaml(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_simple_parse_to_yaml() {
+        let source = r#"
+{
+        name: "My App",
+        version: 1.0,
+    is_enabled: true,
+}"#;
+
+        let expected_yaml = "is_enabled: true\nname: My App\nversion: 1\n";
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+        let result = analysis_result.to_yaml().unwrap();
 
         assert_eq!(result, expected_yaml);
     }
+
+    #[test]
+    fn test_error_code_and_category() {
+        use crate::error::ErrorCategory;
+        use miette::Diagnostic;
+
+        let cases = [
+            (
+                "{ a: 1 b: 2 }",
+                "parser::unexpected_token",
+                ErrorCategory::Parsing,
+            ),
+            (
+                "{ a: \"unclosed }",
+                "lexer::invalid_token",
+                ErrorCategory::Lexing,
+            ),
+            (
+                "{ a: *missing }",
+                "resolver::anchor_not_found",
+                ErrorCategory::Resolution,
+            ),
+            (
+                "import * as m from \"./does_not_exist.mon\"\n{}",
+                "resolver::module_not_found",
+                ErrorCategory::Io,
+            ),
+            (
+                "{ T: #struct { a(Number) }, x :: T = { a: \"no\" } }",
+                "validation::type_mismatch",
+                ErrorCategory::Validation,
+            ),
+        ];
+
+        for (source, code, category) in cases {
+            let err = analyze(source, "test.mon").err().unwrap();
+            assert_eq!(err.code(), code, "source: {source}");
+            assert_eq!(err.category(), category, "source: {source}");
+            // The code must agree with the one miette renders.
+            assert_eq!(Diagnostic::code(&err).unwrap().to_string(), code);
+        }
+    }
+
+    #[test]
+    fn test_error_is_recoverable() {
+        for (source, recoverable) in [
+            ("{ a: ~ }", true),
+            ("{ a: \"unclosed }", false),
+            ("{ a: *missing }", true),
+            ("{ T: #struct { a(Number) }, x :: T = { a: \"no\" } }", true),
+            ("{ T: #struct { a(Number)? = 1 } }", true),
+            ("{ a: { b: 1 }", false),
+            ("{ a: 1 } }", false),
+            ("{ a: [1, 2 }", false),
+        ] {
+            let err = analyze(source, "test.mon").err().unwrap();
+            assert_eq!(
+                err.is_recoverable(),
+                recoverable,
+                "source: {source}, {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_against_schema() {
+        use crate::api::validate_against;
+        use crate::error::{ResolverError, ValidationError};
+
+        let schema = r#"{
+            &port: 8080,
+            Server: #struct { host(String), port(Number) = 80 },
+            Config: #struct { name(String), server(Server) },
+        }"#;
+
+        let data = r#"{ name: "app", server: { host: "localhost" } }"#;
+        let result = validate_against(data, "data.mon", schema, "schema.mon", "Config").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(json["server"]["port"], 80.0);
+
+        let data = r#"{ name: "app", server: { host: 1 } }"#;
+        let err = validate_against(data, "data.mon", schema, "schema.mon", "Config")
+            .err()
+            .unwrap();
+        match err {
+            MonError::Resolver(err) => match *err {
+                ResolverError::Validation(ValidationError::TypeMismatch { path, .. }) => {
+                    assert_eq!(path, "server.host");
+                }
+                err => panic!("Expected TypeMismatch error, but got {err:?}"),
+            },
+            err => panic!("Expected a resolver error, but got {err:?}"),
+        }
+
+        // The schema's anchors are not visible to the data.
+        let data = r#"{ name: "app", server: { host: "localhost", port: *port } }"#;
+        let err = validate_against(data, "data.mon", schema, "schema.mon", "Config")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "resolver::anchor_not_found");
+
+        // Bare words name variants of enum fields of the root type.
+        let schema_with_enum = r#"{ Level: #enum { Debug, Info }, Log: #struct { level(Level) } }"#;
+        validate_against(
+            "{ level: Debug }",
+            "data.mon",
+            schema_with_enum,
+            "schema.mon",
+            "Log",
+        )
+        .unwrap();
+
+        let err = validate_against("{}", "data.mon", schema, "schema.mon", "Missing")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "validation::undefined_type");
+    }
+
+    #[test]
+    fn test_analyze_fenced() {
+        let markdown = "# Config\n\n```mon\n{ port: 80 }\n```\n";
+        let result = analyze_fenced(markdown, "README.md", "```mon")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.to_json_compact().unwrap(), r#"{"port":80.0}"#);
+
+        assert!(analyze_fenced("no MON here", "README.md", "```mon")
+            .unwrap()
+            .is_none());
+
+        // Errors point at the host document
+        let markdown = "# Config\n\n```mon\n{\n  port: *missing\n}\n```\n";
+        let err = analyze_fenced(markdown, "README.md", "```mon")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.primary_span().unwrap().offset(),
+            markdown.find("*missing").unwrap()
+        );
+        assert_eq!(err.location(), Some((5, 9)));
+    }
+
+    #[test]
+    fn test_struct_field_aliases() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "camelCase")]
+        struct User {
+            user_name: String,
+            last_login: f64,
+            id: f64,
+        }
+
+        let source = r#"{
+            User: #struct {
+                user_name(String) as "userName",
+                last_login(Number) as "lastLogin" = 0,
+                id(Number),
+            },
+            user :: User = { user_name: "ann", id: 1 },
+            plain: { user_name: "bob" },
+        }"#;
+        let result = analyze(source, "test.mon").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(
+            value["user"],
+            serde_json::json!({ "userName": "ann", "lastLogin": 0.0, "id": 1.0 })
+        );
+        // Only pairs validated as the struct are renamed
+        assert_eq!(value["plain"], serde_json::json!({ "user_name": "bob" }));
+
+        #[derive(serde::Deserialize)]
+        struct Root {
+            user: User,
+        }
+        let root: Root = result.deserialize().unwrap();
+        assert_eq!(
+            root.user,
+            User {
+                user_name: "ann".to_string(),
+                last_login: 0.0,
+                id: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_json_and_yaml() {
+        let result = analyze(r#"{ b: [1, 2], a: { c: "x" } }"#, "test.mon").unwrap();
+
+        let mut json = Vec::new();
+        result.write_json(&mut json).unwrap();
+        assert_eq!(String::from_utf8(json).unwrap(), result.to_json().unwrap());
+
+        let mut yaml = Vec::new();
+        result.write_yaml(&mut yaml).unwrap();
+        assert_eq!(String::from_utf8(yaml).unwrap(), result.to_yaml().unwrap());
+    }
+
+    #[test]
+    fn test_analyze_all_reports_every_validation_error() {
+        let source = r#"{
+            Point: #struct { x(Number), y(Number) },
+            User: #struct { name(String), tags([String...]) },
+
+            a :: Point = { x: "1", y: "2" },
+            b :: Point = { x: 1 },
+            c :: User = { name: "ann", tags: ["ok", 1, false], extra: 1 },
+            d :: Point = { x: 1, y: 2 },
+        }"#;
+
+        let (result, errors) = analyze_all(source, "test.mon");
+        assert!(result.is_none());
+        let mut codes: Vec<_> = errors.iter().map(MonError::code).collect();
+        codes.sort_unstable();
+        assert_eq!(
+            codes,
+            [
+                "validation::missing_field",
+                "validation::type_mismatch",
+                "validation::type_mismatch",
+                "validation::type_mismatch",
+                "validation::type_mismatch",
+                "validation::unexpected_field",
+            ]
+        );
+        // `analyze` stops at the first of them
+        let first = analyze(source, "test.mon").err().unwrap();
+        assert!(errors
+            .iter()
+            .any(|e| e.primary_span() == first.primary_span()));
+
+        let (result, errors) = analyze_all("{ p :: Number = 1 }", "test.mon");
+        assert!(result.is_some() && errors.is_empty());
+
+        // Errors other than validation errors stop the analysis
+        let (result, errors) = analyze_all("{ a: *missing, b :: Number = \"x\" }", "test.mon");
+        assert!(result.is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), "resolver::anchor_not_found");
+    }
+
+    #[test]
+    fn test_validate_only() {
+        use crate::{is_valid, validate_only};
+
+        let valid = "{ User: #struct { id(Number) }, user :: User = { id: 1 } }";
+        let invalid = r#"{ User: #struct { id(Number) }, user :: User = { id: "one" } }"#;
+        assert!(is_valid(valid, "valid.mon"));
+        assert!(!is_valid(invalid, "invalid.mon"));
+        assert!(!is_valid("{ a: }", "broken.mon"));
+
+        // The same error as from a full analysis
+        let err = validate_only(invalid, "invalid.mon").unwrap_err();
+        assert_eq!(
+            err.code(),
+            analyze(invalid, "invalid.mon").err().unwrap().code()
+        );
+        assert_eq!(err.code(), "validation::type_mismatch");
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        use crate::ast::TypeDef;
+
+        let source = r#"{
+            Status: #enum { Active, Inactive },
+            User: #struct { name(String), status(Status) },
+            admin :: User = { name: "root", status :: Status = Active },
+            servers: [{ port :: Number = 80 }],
+            users :: [User...] = [],
+        }"#;
+        let result = analyze(source, "typed.mon").unwrap();
+        let fields: Vec<(String, String, Option<&str>)> = result
+            .typed_fields()
+            .into_iter()
+            .map(|(path, type_spec, type_def)| {
+                (
+                    path,
+                    type_spec.to_string(),
+                    type_def.map(|type_def| type_def.name.as_str()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("admin".to_string(), "User".to_string(), Some("User")),
+                (
+                    "admin.status".to_string(),
+                    "Status".to_string(),
+                    Some("Status")
+                ),
+                ("servers[0].port".to_string(), "Number".to_string(), None),
+                ("users".to_string(), "[User...]".to_string(), Some("User")),
+            ]
+        );
+        let (_, _, Some(user)) = &result.typed_fields()[0] else {
+            panic!("Expected the definition of User");
+        };
+        assert!(matches!(&user.def_type, TypeDef::Struct(s) if s.fields.len() == 2));
+    }
+
+    #[test]
+    fn test_typed_fields_through_a_namespace() {
+        use crate::ast::TypeDef;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("net.mon"),
+            "{ Port: #struct { number(Number), protocol(String) } }",
+        )
+        .unwrap();
+        let main_path = dir.path().join("main.mon");
+        // The local `Port` must not shadow `net.Port`
+        let source = r#"import * as net from "./net.mon"
+            {
+                Port: #struct { name(String) },
+                http :: net.Port = { number: 80, protocol: "tcp" },
+            }"#;
+        let result = analyze(source, &main_path.to_string_lossy()).unwrap();
+        let typed_fields = result.typed_fields();
+        let [(path, _, Some(port))] = typed_fields.as_slice() else {
+            panic!("Expected one typed field with a definition, got {typed_fields:?}");
+        };
+        assert_eq!(path, "http");
+        let TypeDef::Struct(port) = &port.def_type else {
+            panic!("Expected net.Port to be a struct");
+        };
+        let field_names: Vec<&str> = port.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(field_names, vec!["number", "protocol"]);
+    }
+
+    #[test]
+    fn test_analyze_with_metrics() {
+        use crate::api::analyze_with_metrics;
+        use std::time::Instant;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("schemas.mon"),
+            "{ Port: #struct { number(Number) } }",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("shared.mon"), "{ &port: 8080 }").unwrap();
+        let main_path = dir.path().join("main.mon");
+        let source = r#"import { Port } from "./schemas.mon"
+            import { &port } from "./shared.mon"
+            { port :: Port = { number: *port } }"#;
+
+        let started = Instant::now();
+        let (result, metrics) = analyze_with_metrics(source, &main_path.to_string_lossy()).unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(metrics.files_read, 2);
+        // The phases do not overlap, so together they take no longer than the whole analysis
+        assert!(
+            metrics.lex_time + metrics.parse_time + metrics.resolve_time + metrics.validate_time
+                <= elapsed
+        );
+        // The result is the same as without metrics
+        let plain = analyze(source, &main_path.to_string_lossy()).unwrap();
+        assert_eq!(result.to_value(), plain.to_value());
+    }
+
+    #[test]
+    fn test_analyze_with_metrics_reads_cached_modules_once() {
+        use crate::api::analyze_with_metrics;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let schemas = dir.path().join("schemas");
+        std::fs::create_dir(&schemas).unwrap();
+        std::fs::write(
+            schemas.join("port.mon"),
+            "{ Port: #struct { number(Number) } }",
+        )
+        .unwrap();
+        std::fs::write(
+            schemas.join("host.mon"),
+            "{ Host: #struct { name(String) } }",
+        )
+        .unwrap();
+        let main_path = dir.path().join("main.mon");
+        let source = r#"import * as schemas from "./schemas/"
+            import { Port } from "./schemas/"
+            import { Host } from "./schemas/host.mon"
+            { port :: Port = { number: 80 }, host :: schemas.Host = { name: "a" } }"#;
+
+        let (_, metrics) = analyze_with_metrics(source, &main_path.to_string_lossy()).unwrap();
+        assert_eq!(metrics.files_read, 2);
+    }
+
+    #[test]
+    fn test_analyze_in_resolves_imports_against_base_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("shared.mon"), "{ &port: 8080 }").unwrap();
+        let source = r#"import { &port } from "./shared.mon"
+            { port: *port }"#;
+
+        let result = analyze_in(source, "main.mon", dir.path()).unwrap();
+        assert_eq!(result.imported_files(), vec![dir.path().join("shared.mon")]);
+        assert!(result.to_json().unwrap().contains("8080"));
+
+        // Without the base directory, the import is looked up in the current directory
+        let err = analyze(source, "main.mon").err().unwrap();
+        assert_eq!(err.code(), "resolver::module_not_found");
+    }
+
+    #[test]
+    fn test_analyze_with_lexer_options() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("flags.mon"),
+            "{ Flags: #struct { cache(Boolean) = no }, &verbose: enabled }",
+        )
+        .unwrap();
+        let main_path = dir.path().join("main.mon");
+        let source = r#"import { Flags, &verbose } from "./flags.mon"
+            { flags :: Flags = { }, debug: yes, verbose: *verbose }"#;
+        let options = ResolveOptions::default().with_lexer_options(
+            LexerOptions::default()
+                .with_yes_no()
+                .with_enabled_disabled(),
+        );
+
+        let result = analyze_with_options(source, &main_path.to_string_lossy(), options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(value["flags"]["cache"], false);
+        assert_eq!(value["debug"], true);
+        assert_eq!(value["verbose"], true);
+
+        // Without the options, `yes` is a bare word
+        assert!(analyze(source, &main_path.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_entry_points_with_options() {
+        let source = "{ Config: #struct { debug(Boolean) }, debug: yes }";
+        let options =
+            || ResolveOptions::default().with_lexer_options(LexerOptions::default().with_yes_no());
+
+        assert!(validate_only(source, "config.mon").is_err());
+        validate_only_with_options(source, "config.mon", options()).unwrap();
+        analyze_typed_with_options(source, "config.mon", "Config", options()).unwrap();
+        let (result, _) =
+            analyze_with_metrics_and_options(source, "config.mon", options()).unwrap();
+        assert_eq!(result.to_json().unwrap(), "{\n  \"debug\": true\n}");
+
+        let (result, errors) = analyze_all_with_options(source, "config.mon", options());
+        assert!(errors.is_empty());
+        assert!(result.is_some());
+
+        let results =
+            analyze_multi_with_options("{ a: yes }\n---\n{ b: no }", "multi.mon", options())
+                .unwrap();
+        assert_eq!(results.len(), 2);
+
+        let mut streamed = Vec::new();
+        analyze_streaming_with_options(
+            "{ items: [yes, no] }",
+            "items.mon",
+            options(),
+            |_, value| {
+                streamed.push(value);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(streamed.len(), 2);
+
+        let schema = "{ Config: #struct { debug(Boolean) = no } }";
+        let result = validate_against_with_options(
+            "{ }",
+            "data.mon",
+            schema,
+            "schema.mon",
+            "Config",
+            options(),
+        )
+        .unwrap();
+        assert_eq!(result.to_json().unwrap(), "{\n  \"debug\": false\n}");
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let layer = dir.path().join("base.mon");
+        std::fs::write(&layer, "{ debug: yes }").unwrap();
+        let result = analyze_layers_with_options(&[layer.to_str().unwrap()], options()).unwrap();
+        assert_eq!(result.to_json().unwrap(), "{\n  \"debug\": true\n}");
+    }
+
+    #[test]
+    fn test_analyze_typed() {
+        let source = r#"{
+            Mode: #enum { Dev, Prod },
+            Config: #struct { port(Number), mode(Mode), debug(Boolean) = false },
+            port: 8080,
+            mode: Prod,
+        }"#;
+        let result = analyze_typed(source, "config.mon", "Config").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(value["port"], 8080.0);
+        assert_eq!(value["debug"], false);
+
+        let err = analyze_typed(
+            r#"{ Config: #struct { port(Number) }, host: "x" }"#,
+            "config.mon",
+            "Config",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.code(), "validation::missing_field");
+
+        let err = analyze_typed("{ port: 1 }", "config.mon", "Config")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "validation::undefined_type");
+    }
+
+    #[test]
+    fn test_bytes_serialization() {
+        use crate::serialization::{BytesEncoding, Value};
+
+        let result = analyze(r#"{ key :: Bytes = "0x6869" }"#, "bytes.mon").unwrap();
+        let key = |value: Value| match value {
+            Value::Object(mut map) => map.remove("key").unwrap(),
+            other => panic!("Expected an object, got {other:?}"),
+        };
+        assert_eq!(key(result.to_value()), Value::String("aGk=".to_string()));
+        assert_eq!(
+            key(result.to_value_with(BytesEncoding::Array)),
+            Value::Array(vec![Value::Number(104.0), Value::Number(105.0)])
+        );
+
+        #[derive(serde::Deserialize)]
+        struct Keys {
+            key: Vec<u8>,
+        }
+        assert_eq!(result.deserialize::<Keys>().unwrap().key, b"hi");
+    }
+
+    #[test]
+    fn test_analyze_layers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path().join("base.mon");
+        let prod = dir.path().join("prod.mon");
+        std::fs::write(
+            &base,
+            r#"{
+                Server: #struct { host(String), port(Number) },
+                &defaults: { retries: 3 },
+                server :: Server = { host: "localhost", port: 8080 },
+                log: { level: "debug", file: "app.log" },
+                features: ["a", "b"],
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &prod,
+            r#"{
+                server :: Server = { host: "example.com", port: 443 },
+                log: { level: "warn" },
+                features: ["c"],
+                client: { ...*defaults, timeout: 5 },
+            }"#,
+        )
+        .unwrap();
+        let base = base.to_str().unwrap();
+        let prod = prod.to_str().unwrap();
+
+        let result = analyze_layers(&[base, prod]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(value["server"]["host"], "example.com");
+        assert_eq!(value["log"]["level"], "warn");
+        assert_eq!(value["log"]["file"], "app.log");
+        assert_eq!(value["features"], serde_json::json!(["c"]));
+        assert_eq!(value["client"]["retries"], 3.0);
+
+        // Each layer is validated against the types of all layers before it
+        let bad = dir.path().join("bad.mon");
+        std::fs::write(&bad, r#"{ server :: Server = { host: 1, port: 1 } }"#).unwrap();
+        let err = analyze_layers(&[base, bad.to_str().unwrap()])
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "validation::type_mismatch");
+
+        let missing = dir.path().join("missing.mon");
+        let err = analyze_layers(&[base, missing.to_str().unwrap()])
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "resolver::layer_unreadable");
+        assert!(miette::Diagnostic::labels(&err).is_none());
+    }
+
+    #[test]
+    fn test_to_canonical_json() {
+        let canonical = |source: &str| analyze(source, "test.mon").unwrap().to_canonical_json();
+        let json = canonical(
+            r#"{ z: -0.0, a: { list: [1, 0.5, 1e300], empty: [], none: {} }, s: "q\"\u0007é" }"#,
+        );
+        assert_eq!(
+            json,
+            r#"{
+  "a": {
+    "empty": [],
+    "list": [
+      1,
+      0.5,
+      1e300
+    ],
+    "none": {}
+  },
+  "s": "q\"\u0007é",
+  "z": 0
+}
+"#
+        );
+        // Only the data matters, not how the source is formatted or ordered
+        assert_eq!(
+            json,
+            canonical(
+                r#"{
+                    s: "q\"\u0007é",
+                    a: { none: {}, empty: [], list: [1.0, 0.5, 1e300] },
+                    z: 0,
+                }"#
+            )
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["s"], "q\"\u{7}é");
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let hash = |source: &str| analyze(source, "test.mon").unwrap().content_hash();
+
+        let base = hash(r#"{ name: "app", ports: [80, 443], tls: { enabled: true } }"#);
+        // Formatting, comments and key order do not matter
+        assert_eq!(
+            base,
+            hash(
+                r#"{
+                    // the ports
+                    tls: { enabled: on },
+                    name: "app",
+                    ports: [80, 443.0],
+                }"#
+            )
+        );
+        assert_ne!(
+            base,
+            hash(r#"{ name: "app", ports: [443, 80], tls: { enabled: true } }"#)
+        );
+        assert_ne!(
+            base,
+            hash(r#"{ name: "ap", ports: [80, 443], tls: { enabled: true } }"#)
+        );
+        assert_ne!(
+            base,
+            hash(r#"{ name: "app", ports: [80, 443], tls: { enabled: 1 } }"#)
+        );
+        assert_ne!(hash(r#"{ a: ["x", "y"] }"#), hash(r#"{ a: ["xy"] }"#));
+    }
+
+    #[test]
+    fn test_to_json_compact() {
+        let result = analyze(r#"{ b: [1, 2], a: { c: "x" } }"#, "test.mon").unwrap();
+        assert_eq!(
+            result.to_json_compact().unwrap(),
+            r#"{"a":{"c":"x"},"b":[1.0,2.0]}"#
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let source = "{\r\n    a: 1,\r\n    b: *missing,\r\n}";
+        let err = analyze(source, "test.mon").err().unwrap();
+        assert_eq!(err.location(), Some((3, 8)));
+
+        let err = analyze("{ a: 1 b: 2 }", "test.mon").err().unwrap();
+        assert_eq!(err.location(), Some((1, 8)));
+    }
+
+    #[test]
+    fn test_error_primary_span() {
+        let mut errors: Vec<MonError> = [
+            "{ T: #struct { a(Number) }, x :: T = { a: \"no\" } }",
+            "{ a: *missing }",
+            "{ a: 1 b: 2 }",
+        ]
+        .iter()
+        .map(|source| analyze(source, "test.mon").err().unwrap())
+        .collect();
+
+        assert_eq!(errors[1].primary_span(), Some((5, 8).into()));
+
+        errors.sort_by_key(|err| err.primary_span().map(|span| span.offset()));
+        let codes: Vec<&str> = errors.iter().map(MonError::code).collect();
+        assert_eq!(
+            codes,
+            [
+                "resolver::anchor_not_found",
+                "parser::unexpected_token",
+                "validation::type_mismatch"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_error() {
+        use crate::error::{render, render_plain};
+
+        let err = analyze("{ a: *missing }", "test.mon").err().unwrap();
+
+        let colored = render(&err, true);
+        assert!(colored.contains("resolver::anchor_not_found"));
+        assert!(colored.contains('\u{1b}'));
+
+        let uncolored = render(&err, false);
+        assert!(uncolored.contains("test.mon"));
+        assert!(!uncolored.contains('\u{1b}'));
+
+        let plain = render_plain(&err);
+        assert!(plain.contains("resolver::anchor_not_found"));
+        assert!(!plain.contains('\u{1b}'));
+        assert!(!plain.contains('╭'));
+    }
+
+    #[test]
+    fn test_redundant_spread_warning() {
+        let source = "{ &base: { a: 1, b: 2 }, c: { ...*base, a: 3, b: 4 } }";
+        let result = analyze(source, "test.mon").unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        let warning = &result.warnings[0];
+        assert_eq!(warning.code(), "resolver::redundant_spread");
+        let span = warning.primary_span().unwrap();
+        assert_eq!(
+            &source[span.offset()..span.offset() + span.len()],
+            "...*base"
+        );
+
+        // A spread that still contributes a key is fine.
+        let result = analyze(
+            "{ &base: { a: 1, b: 2 }, c: { ...*base, a: 3 } }",
+            "test.mon",
+        )
+        .unwrap();
+        assert!(result.warnings.is_empty());
+
+        // So is spreading an empty object.
+        let result = analyze("{ &base: {}, c: { ...*base, a: 3 } }", "test.mon").unwrap();
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_type_shadowed_warning() {
+        use crate::error::MonWarning;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("accounts.mon"),
+            "{ User: #struct { id(Number) } }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("people.mon"),
+            "{ User: #struct { name(String) } }",
+        )
+        .unwrap();
+        let shadowed = |source: &str| {
+            let result = analyze_in(source, "main.mon", dir.path()).unwrap();
+            result
+                .warnings
+                .iter()
+                .map(|warning| match warning {
+                    MonWarning::TypeShadowed {
+                        name,
+                        original_file,
+                        shadowing_span,
+                        ..
+                    } => {
+                        let file = std::path::Path::new(original_file).file_name().unwrap();
+                        let text = result.text_at(*shadowing_span).unwrap().to_string();
+                        (name.clone(), file.to_string_lossy().to_string(), text)
+                    }
+                    other => panic!("Unexpected warning {other:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // A local type replaces an imported one
+        assert_eq!(
+            shadowed(
+                r#"import { User } from "./accounts.mon"
+                { User: #struct { email(String) } }"#
+            ),
+            [(
+                "User".to_string(),
+                "accounts.mon".to_string(),
+                "User".to_string()
+            )]
+        );
+        // Two imports bring in a `User`
+        assert_eq!(
+            shadowed(
+                r#"import { User } from "./accounts.mon"
+                import { User } from "./people.mon"
+                {}"#
+            ),
+            [(
+                "User".to_string(),
+                "accounts.mon".to_string(),
+                r#"import { User } from "./people.mon""#.to_string()
+            )]
+        );
+
+        // Renaming an import, namespaces and importing the same type twice are fine
+        assert!(shadowed(
+            r#"import { User as Account } from "./accounts.mon"
+            import * as people from "./people.mon"
+            import { User } from "./people.mon"
+            import { User } from "./people.mon"
+            {}"#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_set_value_by_path() {
+        use crate::ast::{MonValue, MonValueKind};
+        use crate::error::PathError;
+
+        let number = |n| MonValue {
+            kind: MonValueKind::Number(n),
+            anchor: None,
+            pos_start: 0,
+            pos_end: 0,
+        };
+
+        let mut result = analyze("{ server: { port: 80, host: \"a\" } }", "test.mon").unwrap();
+        result.set("server.port", number(8080.0)).unwrap();
+        result.set("limits.max.connections", number(10.0)).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "server": { "port": 8080.0, "host": "a" },
+                "limits": { "max": { "connections": 10.0 } },
+            })
+        );
+
+        assert_eq!(
+            result.set("server.host.name", number(1.0)),
+            Err(PathError::NotAnObject {
+                path: "server.host.name".to_string(),
+                segment: "server.host".to_string(),
+            })
+        );
+        assert!(matches!(
+            result.set("server..port", number(1.0)),
+            Err(PathError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merged_with() {
+        let base = analyze(
+            "{ server: { host: \"localhost\", port: 80 }, debug: false }",
+            "base.mon",
+        )
+        .unwrap();
+        let overrides = analyze("{ server: { port: 443 }, debug: true }", "prod.mon").unwrap();
+
+        let merged = serde_json::to_value(base.merged_with(&overrides)).unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({
+                "server": { "host": "localhost", "port": 443.0 },
+                "debug": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_analyze_streaming() {
+        use crate::serialization::{to_value, Value};
+
+        let source = r#"{
+            Record: #struct { id(Number), name(String) },
+            &defaults: { name: "unknown" },
+            title: "batch",
+            records :: [Record...] = [
+                { id: 1, name: "a" },
+                { id: 2, ...*defaults },
+            ],
+            &kept: [1, 2],
+        }"#;
+
+        let mut streamed = Vec::new();
+        let result = crate::analyze_streaming(source, "test.mon", |key, element| {
+            streamed.push((key.to_string(), to_value(&element)));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(streamed.len(), 2);
+        assert_eq!(streamed[1].0, "records");
+        let Value::Object(second) = &streamed[1].1 else {
+            panic!("Expected an object");
+        };
+        assert_eq!(second["name"], Value::String("unknown".to_string()));
+
+        let json: serde_json::Value = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        assert_eq!(json["records"], serde_json::json!([]));
+        assert_eq!(json["title"], "batch");
+        assert_eq!(json["kept"], serde_json::json!([1.0, 2.0]));
+    }
+
+    #[test]
+    fn test_analyze_streaming_validates_elements() {
+        use crate::error::{ResolverError, ValidationError};
+
+        let source = r#"{
+            Record: #struct { id(Number) },
+            records :: [Record...] = [{ id: 1 }, { id: "two" }],
+        }"#;
+
+        let mut count = 0;
+        let err = crate::analyze_streaming(source, "test.mon", |_, _| {
+            count += 1;
+            Ok(())
+        })
+        .err()
+        .unwrap();
+
+        assert_eq!(count, 1);
+        match err {
+            MonError::Resolver(err) => match *err {
+                ResolverError::Validation(ValidationError::TypeMismatch { path, .. }) => {
+                    assert_eq!(path, "records[1].id");
+                }
+                err => panic!("Expected TypeMismatch error, but got {err:?}"),
+            },
+            err => panic!("Expected a resolver error, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_anchor_names_and_alias_usages() {
+        let source = r"
+        {
+            &base: { a: 1 },
+            &tags: [1, 2],
+            unused: &inline { b: 2 },
+            value: *base,
+            list: [...*tags, 3],
+        }
+    ";
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+
+        assert_eq!(
+            analysis_result.anchor_names(),
+            vec!["base", "tags", "inline"]
+        );
+        assert!(analysis_result.contains_anchor("inline"));
+        assert!(!analysis_result.contains_anchor("missing"));
+
+        let usages = analysis_result.alias_usages();
+        assert_eq!(usages.len(), 2);
+        assert!(usages.contains(&(
+            "base",
+            SourceSpan::new(source.find("*base").unwrap().into(), 5)
+        )));
+        assert!(usages.contains(&(
+            "tags",
+            SourceSpan::new(source.find("...*tags").unwrap().into(), 8)
+        )));
+    }
 }
@@ -37,8 +37,10 @@
 //!
 //! Developers typically do not need to construct these AST nodes manually. Instead, they are
 //! generated by the `Parser` and consumed by other parts of the library or by language tools.
+use crate::utils::{encode_base64, source_span};
 use log::error;
 use miette::SourceSpan;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 use std::panic::Location;
 
@@ -47,6 +49,44 @@ use std::panic::Location;
 pub struct MonDocument {
     pub root: MonValue,
     pub imports: Vec<ImportStatement>,
+    /// Annotations from `//! key: value` comments at the top of the document, before any
+    /// import or the root object, e.g. `//! schema-version: 2`.
+    pub metadata: HashMap<String, String>,
+    /// Comments attached to array elements and object members, keyed by the element's `pos_start`
+    /// (for a pair, the `pos_start` of its value).
+    ///
+    /// Only filled by a parser created with [`Parser::with_comments`](crate::parser::Parser::with_comments).
+    pub element_docs: HashMap<usize, ElementDoc>,
+    /// The source text of number literals, keyed by the number's `pos_start`, e.g. `1.23e10`
+    /// for the value `12300000000`. Formatting the document writes a number as it was written.
+    ///
+    /// Only filled by a parser created with [`Parser::with_number_literals`](crate::parser::Parser::with_number_literals).
+    pub number_literals: HashMap<usize, String>,
+}
+
+/// The comments written around an array element or object member.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ElementDoc {
+    /// The comment lines on their own lines directly before the element or member, joined by `\n`.
+    pub leading_doc: Option<String>,
+    /// The comment after the element or member (and its comma) on the same line.
+    pub trailing_doc: Option<String>,
+    /// The comment lines after the last element or member, before the closing bracket, joined
+    /// by `\n`.
+    pub closing_doc: Option<String>,
+}
+
+impl ElementDoc {
+    /// Returns the leading and trailing comment, joined by a newline.
+    #[must_use]
+    pub fn text(&self) -> String {
+        [&self.leading_doc, &self.trailing_doc]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 /// Represents a value in a MON document, such as a `string`, `number`, `object`, or `array`...
@@ -63,7 +103,142 @@ impl MonValue {
     /// Returns the source span of the value, which can be used for error reporting.
     #[must_use]
     pub fn get_source_span(&self) -> SourceSpan {
-        SourceSpan::new(self.pos_start.into(), self.pos_end - self.pos_start)
+        self.pos_span()
+    }
+
+    /// Returns the span from `pos_start` to `pos_end`. Values built by hand may carry an
+    /// inverted range; that gives an empty span rather than an underflowing length.
+    #[must_use]
+    pub fn pos_span(&self) -> SourceSpan {
+        source_span(self.pos_start, self.pos_end)
+    }
+
+    /// Calls `f` with this value and then, recursively, with every value inside it: the values
+    /// of an object's pairs and the elements of an array, in order.
+    ///
+    /// Children are visited after `f` has seen their parent, so `f` may replace them.
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut MonValue)) {
+        f(self);
+        match &mut self.kind {
+            MonValueKind::Object(members) => {
+                for member in members {
+                    if let Member::Pair(pair) = member {
+                        pair.value.walk_mut(f);
+                    }
+                }
+            }
+            MonValueKind::Array(elements) => {
+                for element in elements {
+                    element.walk_mut(f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the names of all anchors declared in this value and the values inside it, in
+    /// document order.
+    #[must_use]
+    pub fn anchor_names(&self) -> Vec<&str> {
+        fn collect<'a>(value: &'a MonValue, anchors: &mut Vec<&'a str>) {
+            if let Some(anchor) = &value.anchor {
+                anchors.push(anchor);
+            }
+            match &value.kind {
+                MonValueKind::Object(members) => {
+                    for member in members {
+                        if let Member::Pair(pair) = member.unguarded() {
+                            collect(&pair.value, anchors);
+                        }
+                    }
+                }
+                MonValueKind::Array(elements) => {
+                    for element in elements {
+                        collect(element, anchors);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut anchors = Vec::new();
+        collect(self, &mut anchors);
+        anchors
+    }
+
+    /// Returns every alias (`*name`) and spread (`...*name`) in this value and the values
+    /// inside it, with the anchor name it refers to, in document order.
+    #[must_use]
+    pub fn alias_usages(&self) -> Vec<(&str, SourceSpan)> {
+        fn collect<'a>(value: &'a MonValue, usages: &mut Vec<(&'a str, SourceSpan)>) {
+            match &value.kind {
+                MonValueKind::Alias(name) | MonValueKind::ArraySpread(name) => {
+                    usages.push((name, value.get_source_span()));
+                }
+                MonValueKind::Object(members) => {
+                    for member in members {
+                        match member.unguarded() {
+                            Member::Pair(pair) => collect(&pair.value, usages),
+                            Member::Spread(name, span) => usages.push((name, *span)),
+                            _ => {}
+                        }
+                    }
+                }
+                MonValueKind::Array(elements) => {
+                    for element in elements {
+                        collect(element, usages);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut usages = Vec::new();
+        collect(self, &mut usages);
+        usages
+    }
+
+    /// Compares two values by their data only, unlike `==`, which also compares source
+    /// positions and anchors.
+    ///
+    /// Anchors and positions are ignored at every level. Objects are compared like they are
+    /// serialized: by their pairs, in any order, while type definitions and other members are
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mon_core::parser::Parser;
+    ///
+    /// let parse = |source: &str| Parser::new(source).unwrap().parse_document().unwrap().root;
+    /// let a = parse("{ &x: 5, y: [1, 2] }");
+    /// let b = parse("{ y: [1, 2], x: 5 }");
+    /// assert_ne!(a, b);
+    /// assert!(a.structurally_eq(&b));
+    /// ```
+    #[must_use]
+    pub fn structurally_eq(&self, other: &MonValue) -> bool {
+        match (&self.kind, &other.kind) {
+            (MonValueKind::Object(a), MonValueKind::Object(b)) => {
+                fn pairs(members: &[Member]) -> Vec<&Pair> {
+                    members
+                        .iter()
+                        .filter_map(|member| match member {
+                            Member::Pair(pair) => Some(pair),
+                            _ => None,
+                        })
+                        .collect()
+                }
+                let (a, b) = (pairs(a), pairs(b));
+                a.len() == b.len()
+                    && a.iter().all(|a| {
+                        b.iter()
+                            .any(|b| a.key == b.key && a.value.structurally_eq(&b.value))
+                    })
+            }
+            (MonValueKind::Array(a), MonValueKind::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.structurally_eq(b))
+            }
+            (a, b) => a == b,
+        }
     }
 }
 
@@ -91,6 +266,114 @@ pub enum MonValueKind {
     },
     /// A spread of an array.
     ArraySpread(String),
+    /// An unquoted word, e.g. `Active`. The resolver turns it into an
+    /// [`EnumValue`](MonValueKind::EnumValue) where an enum is expected, and rejects it elsewhere.
+    BareWord(String),
+    /// A number with a unit suffix, e.g. `30s` or `10MB`. See [`QuantityKind`] for the units.
+    Quantity { value: f64, unit: String },
+    /// Binary data, decoded by the resolver from a string validated as the `Bytes` type.
+    Bytes(Vec<u8>),
+}
+
+impl MonValueKind {
+    /// Returns the string if this is a [`MonValueKind::String`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mon_core::parser::Parser;
+    ///
+    /// let document = Parser::new(r#"{ name: "mon" }"#).unwrap().parse_document().unwrap();
+    /// let members = document.root.kind.as_object().unwrap();
+    /// let mon_core::ast::Member::Pair(pair) = &members[0] else { unreachable!() };
+    /// assert_eq!(pair.value.kind.as_str(), Some("mon"));
+    /// assert_eq!(pair.value.kind.as_number(), None);
+    /// ```
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MonValueKind::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if this is a [`MonValueKind::Number`].
+    #[must_use]
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            MonValueKind::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if this is a [`MonValueKind::Boolean`].
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            MonValueKind::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the members if this is a [`MonValueKind::Object`].
+    #[must_use]
+    pub fn as_object(&self) -> Option<&[Member]> {
+        match self {
+            MonValueKind::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is a [`MonValueKind::Array`].
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[MonValue]> {
+        match self {
+            MonValueKind::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is [`MonValueKind::Null`].
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, MonValueKind::Null)
+    }
+}
+
+/// What the unit suffix of a [`MonValueKind::Quantity`] measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantityKind {
+    /// A length of time: `ms`, `s`, `m`, `h` or `d`. The base unit is the second.
+    Duration,
+    /// An amount of data: `B`, `KB`, `MB`, `GB`, `TB`, `KiB`, `MiB`, `GiB` or `TiB`.
+    /// The base unit is the byte.
+    ByteSize,
+}
+
+impl QuantityKind {
+    /// Looks up a unit suffix, returning what it measures and its size in the base unit.
+    #[must_use]
+    pub fn of_unit(unit: &str) -> Option<(QuantityKind, f64)> {
+        const KIB: f64 = 1024.0;
+        let entry = match unit {
+            "ms" => (QuantityKind::Duration, 0.001),
+            "s" => (QuantityKind::Duration, 1.0),
+            "m" => (QuantityKind::Duration, 60.0),
+            "h" => (QuantityKind::Duration, 3_600.0),
+            "d" => (QuantityKind::Duration, 86_400.0),
+            "B" => (QuantityKind::ByteSize, 1.0),
+            "KB" => (QuantityKind::ByteSize, 1e3),
+            "MB" => (QuantityKind::ByteSize, 1e6),
+            "GB" => (QuantityKind::ByteSize, 1e9),
+            "TB" => (QuantityKind::ByteSize, 1e12),
+            "KiB" => (QuantityKind::ByteSize, KIB),
+            "MiB" => (QuantityKind::ByteSize, KIB * KIB),
+            "GiB" => (QuantityKind::ByteSize, KIB * KIB * KIB),
+            "TiB" => (QuantityKind::ByteSize, KIB * KIB * KIB * KIB),
+            _ => return None,
+        };
+        Some(entry)
+    }
 }
 
 /// Represents a member of a MON object.
@@ -98,12 +381,92 @@ pub enum MonValueKind {
 pub enum Member {
     /// A key-value pair.
     Pair(Pair),
-    /// A spread of another object's members.
-    Spread(String),
+    /// A spread of another object's members, with the span of the whole `...*name`.
+    Spread(String, SourceSpan),
     /// An import statement.
     Import(ImportStatement),
     /// A type definition (`#struct` or `#enum`).
     TypeDefinition(TypeDefinition),
+    /// A member that only applies when its guard holds, e.g. `[@if(ENV == "prod")] replicas: 3`.
+    /// The resolver replaces it by the member, or drops it.
+    Guarded(Guard, Box<Member>),
+}
+
+impl Member {
+    /// Returns the member itself, or for a guarded member, the member its guards apply to.
+    #[must_use]
+    pub fn unguarded(&self) -> &Member {
+        match self {
+            Member::Guarded(_, member) => member.unguarded(),
+            member => member,
+        }
+    }
+
+    /// The position its comments are stored at in [`MonDocument::element_docs`].
+    pub(crate) fn doc_key(&self) -> Option<usize> {
+        match self {
+            Member::Pair(pair) => Some(pair.value.pos_start),
+            Member::Spread(_, span) => Some(span.offset()),
+            Member::TypeDefinition(type_def) => Some(type_def.pos_start),
+            Member::Import(_) => None,
+            Member::Guarded(_, member) => member.doc_key(),
+        }
+    }
+}
+
+/// The condition of a `[@if(...)]` guard before an object member, on a variable of the
+/// environment the document is resolved in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Guard {
+    /// The name of the variable, e.g. `ENV`.
+    pub variable: String,
+    pub condition: GuardCondition,
+    /// The starting character position of the guard (its `[`) in the source text.
+    pub pos_start: usize,
+    /// The ending character position of the guard (after its `]`) in the source text.
+    pub pos_end: usize,
+}
+
+/// What a [`Guard`] requires of its variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GuardCondition {
+    /// `@if(NAME)`: the variable is set.
+    IsSet,
+    /// `@if(NAME == "value")`: the variable is set to `value`.
+    Equals(String),
+    /// `@if(NAME != "value")`: the variable is not set to `value`, or not set at all.
+    NotEquals(String),
+}
+
+impl Guard {
+    /// Returns whether the guard holds for the variables in `env`.
+    #[must_use]
+    pub fn holds(&self, env: &HashMap<String, String>) -> bool {
+        let value = env.get(&self.variable);
+        match &self.condition {
+            GuardCondition::IsSet => value.is_some(),
+            GuardCondition::Equals(expected) => value == Some(expected),
+            GuardCondition::NotEquals(expected) => value != Some(expected),
+        }
+    }
+}
+
+impl Display for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[@if({}", self.variable)?;
+        match &self.condition {
+            GuardCondition::IsSet => {}
+            GuardCondition::Equals(value) => {
+                write!(f, " == ")?;
+                write_string(f, value)?;
+            }
+            GuardCondition::NotEquals(value) => {
+                write!(f, " != ")?;
+                write_string(f, value)?;
+            }
+        }
+        write!(f, ")]")
+    }
 }
 
 /// Represents a key-value pair within a MON object.
@@ -111,12 +474,21 @@ pub enum Member {
 pub struct Pair {
     /// The key of the pair.
     pub key: String,
+    /// The location of the key in the source text, without the `&` of an anchored key.
+    ///
+    /// Pairs that are not written in the source, such as inserted default values, have an
+    /// empty span at offset 0.
+    pub key_span: SourceSpan,
     /// The value of the pair.
     pub value: MonValue,
     /// An optional type specification used for validation, e.g., `key::String`.
     ///
     /// [NOTE] This is lazily generated, check if it exists first
     pub validation: Option<TypeSpec>,
+    /// The key the pair is serialized under, if it differs from `key`.
+    ///
+    /// Set by the resolver from the alias of the `#struct` field the pair is validated as.
+    pub serialized_key: Option<String>,
 }
 
 /// Represents an `import` statement, e.g., `import "path/to/file.mon" as my_namespace;`
@@ -139,6 +511,8 @@ pub enum ImportSpec {
     Namespace(String),
     /// Imports specific items (anchors or types) from the file, e.g., `{ MyType, &my_anchor }`.
     Named(Vec<ImportSpecifier>),
+    /// Imports the data of the file's root object as an anchor, e.g., `import defaults from ...`.
+    Default(String),
 }
 
 /// Represents a single item being imported by name.
@@ -148,6 +522,17 @@ pub struct ImportSpecifier {
     pub name: String,
     /// Whether the imported item is an anchor (e.g., `&my_anchor`).
     pub is_anchor: bool,
+    /// The name the item is bound to in the importing file (e.g., `Account` in `User as Account`).
+    pub alias: Option<String>,
+}
+
+impl ImportSpecifier {
+    /// Returns the name the item is bound to in the importing file: the alias if there is one,
+    /// otherwise the original name.
+    #[must_use]
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
 }
 
 /// Represents a type definition, either a `#struct` or an `#enum`.
@@ -165,13 +550,16 @@ pub struct TypeDefinition {
     pub pos_end: usize,
 }
 
-/// An enum that holds either a struct or an enum definition.
+/// An enum that holds a struct, an enum or an alias definition.
 #[derive(Debug, PartialEq, Clone)]
 pub enum TypeDef {
     /// A struct definition.
     Struct(StructDef),
     /// An enum definition.
     Enum(EnumDef),
+    /// An `#alias` definition, which gives a name to another type spec, e.g.
+    /// `Pair: #alias [String, Number]`.
+    Alias(TypeSpec),
 }
 
 impl TypeDef {
@@ -179,8 +567,9 @@ impl TypeDef {
     #[must_use]
     pub fn get_span(&self) -> SourceSpan {
         match self {
-            TypeDef::Struct(s) => (s.pos_start, s.pos_end - s.pos_start).into(),
-            TypeDef::Enum(e) => (e.pos_start, e.pos_end - e.pos_start).into(),
+            TypeDef::Struct(s) => source_span(s.pos_start, s.pos_end),
+            TypeDef::Enum(e) => source_span(e.pos_start, e.pos_end),
+            TypeDef::Alias(spec) => spec.get_span(),
         }
     }
 }
@@ -189,7 +578,17 @@ impl TypeDef {
 #[derive(Debug, PartialEq, Clone)]
 pub struct StructDef {
     /// The fields that make up the struct.
+    ///
+    /// Once resolved, these include the fields of the base struct, see `extends`.
     pub fields: Vec<FieldDef>,
+    /// The base struct of `#struct extends Base { ... }`, whose fields the resolver adds to
+    /// this struct's. Fields of this struct replace base fields of the same name.
+    pub extends: Option<String>,
+    /// Whether the struct ends with `...`, allowing fields it does not declare.
+    pub open: bool,
+    /// The field groups of `one_of(a, b)` annotations after the struct, of which exactly one
+    /// field must be given.
+    pub one_of: Vec<Vec<String>>,
     /// The starting character position of this struct definition in the source text.
     pub pos_start: usize,
     /// The ending character position of this struct definition in the source text.
@@ -203,6 +602,12 @@ pub struct FieldDef {
     pub name: String,
     /// The type specification for this field.
     pub type_spec: TypeSpec,
+    /// Whether the field may be left out, from `name(Type)?`. An optional field has no
+    /// default value.
+    pub optional: bool,
+    /// The key this field is serialized under, from `name(Type) as "alias"`, e.g. to produce
+    /// camelCase JSON from snake_case MON.
+    pub alias: Option<String>,
     /// An optional default value for this field.
     pub default_value: Option<MonValue>,
 }
@@ -210,14 +615,33 @@ pub struct FieldDef {
 /// Represents an `#enum` definition.
 #[derive(Debug, PartialEq, Clone)]
 pub struct EnumDef {
-    /// The variants of the enum.
-    pub variants: Vec<String>,
+    /// The variants of the enum, each with its discriminant from `Variant = value`, if any.
+    pub variants: Vec<(String, Option<MonValue>)>,
     /// The starting character position of this enum definition in the source text.
     pub pos_start: usize,
     /// The ending character position of this enum definition in the source text.
     pub pos_end: usize,
 }
 
+impl EnumDef {
+    /// Returns whether the enum has a variant called `name`.
+    #[must_use]
+    pub fn has_variant(&self, name: &str) -> bool {
+        self.variants.iter().any(|(variant, _)| variant == name)
+    }
+
+    /// Returns the discriminant of the variant called `name`, e.g. `1` for `Active = 1`.
+    ///
+    /// Returns `None` if there is no such variant or it has no discriminant.
+    #[must_use]
+    pub fn discriminant(&self, name: &str) -> Option<&MonValue> {
+        self.variants
+            .iter()
+            .find(|(variant, _)| variant == name)
+            .and_then(|(_, value)| value.as_ref())
+    }
+}
+
 /// Represents a type specification used for validation, e.g., `:: String` or `:: [Number, String]`.
 #[derive(Debug, PartialEq, Clone)]
 pub enum TypeSpec {
@@ -227,6 +651,8 @@ pub enum TypeSpec {
     Collection(Vec<TypeSpec>, SourceSpan),
     /// A spread type within a collection, e.g., `[Number...]`.
     Spread(Box<TypeSpec>, SourceSpan),
+    /// A fixed number of elements of the same type, e.g., `[String; 3]`.
+    Repeat(Box<TypeSpec>, usize, SourceSpan),
 }
 
 impl TypeSpec {
@@ -235,18 +661,56 @@ impl TypeSpec {
         match self {
             TypeSpec::Simple(_, span)
             | TypeSpec::Collection(_, span)
-            | TypeSpec::Spread(_, span) => *span,
+            | TypeSpec::Spread(_, span)
+            | TypeSpec::Repeat(_, _, span) => *span,
+        }
+    }
+
+    /// Returns `true` if both specs describe the same type, ignoring their spans.
+    ///
+    /// Unlike `==`, this can compare specs from different files, e.g. to check whether two
+    /// schemas are compatible.
+    ///
+    /// ```rust
+    /// use mon_core::ast::TypeSpec;
+    ///
+    /// let a = TypeSpec::Collection(vec![TypeSpec::Simple("String".into(), (1, 6).into())], (0, 8).into());
+    /// let b = TypeSpec::Collection(vec![TypeSpec::Simple("String".into(), (11, 6).into())], (10, 8).into());
+    /// assert_ne!(a, b);
+    /// assert!(a.same_shape(&b));
+    /// ```
+    #[must_use]
+    pub fn same_shape(&self, other: &TypeSpec) -> bool {
+        match (self, other) {
+            (TypeSpec::Simple(a, _), TypeSpec::Simple(b, _)) => a == b,
+            (TypeSpec::Collection(a, _), TypeSpec::Collection(b, _)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_shape(b))
+            }
+            (TypeSpec::Spread(a, _), TypeSpec::Spread(b, _)) => a.same_shape(b),
+            (TypeSpec::Repeat(a, a_count, _), TypeSpec::Repeat(b, b_count, _)) => {
+                a_count == b_count && a.same_shape(b)
+            }
+            _ => false,
         }
     }
 }
 
-impl Display for Member {
+impl Display for TypeSpec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Member::Pair(p) => write!(f, "Pair({}: {})", p.key, p.value),
-            Member::Spread(s) => write!(f, "Spread(...*{s})"),
-            Member::Import(i) => write!(f, "Import({i:?})"),
-            Member::TypeDefinition(t) => write!(f, "TypeDef({t:?})"),
+            TypeSpec::Simple(name, _) => write!(f, "{name}"),
+            TypeSpec::Collection(types, _) => {
+                write!(f, "[")?;
+                for (i, t) in types.iter().enumerate() {
+                    write!(f, "{t}")?;
+                    if i < types.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+                write!(f, "]")
+            }
+            TypeSpec::Spread(t, _) => write!(f, "{t}..."),
+            TypeSpec::Repeat(t, count, _) => write!(f, "[{t}; {count}]"),
         }
     }
 }
@@ -256,6 +720,9 @@ impl Display for Member {
 pub struct SymbolTable {
     /// A map of type names to their definitions.
     pub types: std::collections::HashMap<String, TypeDefinition>,
+    /// The types of the modules the document imports with `import * as ns`, by namespace and
+    /// type name, so that `ns.Type` is looked up in its own module.
+    pub namespaces: HashMap<String, HashMap<String, TypeDefinition>>,
 }
 
 impl SymbolTable {
@@ -266,61 +733,537 @@ impl SymbolTable {
     }
 }
 
+// === MON Source Output ===
+//
+// The `Display` impls below emit valid MON that the parser accepts again. Values are written on a
+// single line by default; the alternate flag (`{:#}`) and `MonDocument` produce indented output.
+// `to_mon` renders indented output with custom `MonFormatOptions`.
+
+/// How [`MonFormatOptions`] quotes object keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    /// Quote a key only if the parser would not read it back as the same key otherwise.
+    #[default]
+    AsNeeded,
+    /// Quote every key.
+    Always,
+}
+
+/// Options for rendering MON source with [`MonDocument::to_mon`] and [`MonValue::to_mon`].
+///
+/// # Example
+///
+/// ```rust
+/// use mon_core::ast::MonFormatOptions;
+/// use mon_core::parser::Parser;
+///
+/// let doc = Parser::new("{ b: 1, a: [2] }").unwrap().parse_document().unwrap();
+/// let options = MonFormatOptions {
+///     indent: 2,
+///     sort_keys: true,
+///     trailing_comma: false,
+///     ..MonFormatOptions::default()
+/// };
+/// assert_eq!(doc.to_mon(&options), "{\n  a: [\n    2\n  ],\n  b: 1\n}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonFormatOptions {
+    /// The number of spaces per indentation level. Defaults to 4.
+    pub indent: usize,
+    /// Whether to sort the members of objects by key. Defaults to `false`, keeping the order
+    /// of the source.
+    ///
+    /// Spreads stay where they are and only the members between them are sorted, so sorting
+    /// never changes which keys override which.
+    pub sort_keys: bool,
+    /// Whether to write a comma after the last item of an object or array. Defaults to `true`.
+    pub trailing_comma: bool,
+    /// How to quote object keys. Defaults to [`QuoteStyle::AsNeeded`].
+    pub quote_style: QuoteStyle,
+}
+
+/// The options the `Display` impls use.
+const DEFAULT_FORMAT: MonFormatOptions = MonFormatOptions {
+    indent: 4,
+    sort_keys: false,
+    trailing_comma: true,
+    quote_style: QuoteStyle::AsNeeded,
+};
+
+impl Default for MonFormatOptions {
+    fn default() -> Self {
+        DEFAULT_FORMAT
+    }
+}
+
+impl MonDocument {
+    /// Renders the document as indented MON source, formatted according to `options`.
+    #[must_use]
+    pub fn to_mon(&self, options: &MonFormatOptions) -> String {
+        WithOptions(self, options).to_string()
+    }
+}
+
+impl MonValue {
+    /// Renders the value as indented MON source, formatted according to `options`.
+    #[must_use]
+    pub fn to_mon(&self, options: &MonFormatOptions) -> String {
+        WithOptions(self, options).to_string()
+    }
+}
+
+/// Pairs an AST node with the options to display it with.
+struct WithOptions<'a, T>(&'a T, &'a MonFormatOptions);
+
+impl Display for WithOptions<'_, MonDocument> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write_document(f, self.0, self.1)
+    }
+}
+
+impl Display for WithOptions<'_, MonValue> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write_value(f, self.0, Layout::indented(self.1))
+    }
+}
+
+/// How to lay out a value: single-line (`depth` is `None`), or indented at the given level.
+#[derive(Clone, Copy)]
+struct Layout<'a> {
+    options: &'a MonFormatOptions,
+    depth: Option<usize>,
+    /// The comments to write around elements and members, when formatting a whole document.
+    docs: Option<&'a HashMap<usize, ElementDoc>>,
+    /// The source text of numbers, when formatting a whole document.
+    number_literals: Option<&'a HashMap<usize, String>>,
+}
+
+impl<'a> Layout<'a> {
+    fn indented(options: &'a MonFormatOptions) -> Self {
+        Layout {
+            options,
+            depth: Some(0),
+            docs: None,
+            number_literals: None,
+        }
+    }
+
+    /// The layout of the `Display` impls, depending on the alternate flag.
+    fn of(f: &std::fmt::Formatter) -> Self {
+        Layout {
+            options: &DEFAULT_FORMAT,
+            depth: f.alternate().then_some(0),
+            docs: None,
+            number_literals: None,
+        }
+    }
+}
+
 impl Display for MonDocument {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.root)
+        write_document(f, self, &DEFAULT_FORMAT)
     }
 }
 
-impl Display for MonValueKind {
+fn write_document(
+    f: &mut std::fmt::Formatter,
+    document: &MonDocument,
+    options: &MonFormatOptions,
+) -> std::fmt::Result {
+    let mut metadata: Vec<_> = document.metadata.iter().collect();
+    metadata.sort();
+    for (key, value) in &metadata {
+        writeln!(f, "//! {key}: {value}")?;
+    }
+    if !metadata.is_empty() {
+        writeln!(f)?;
+    }
+    for import in &document.imports {
+        writeln!(f, "{import}")?;
+    }
+    if !document.imports.is_empty() {
+        writeln!(f)?;
+    }
+    let layout = Layout {
+        docs: Some(&document.element_docs),
+        number_literals: Some(&document.number_literals),
+        ..Layout::indented(options)
+    };
+    write_value(f, &document.root, layout)
+}
+
+impl Display for ImportStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            MonValueKind::String(s) => write!(f, "\"{s}\""),
-            MonValueKind::Number(n) => write!(f, "{n}"),
-            MonValueKind::Boolean(b) => write!(f, "{b}"),
-            MonValueKind::Null => write!(f, "null"),
-            MonValueKind::Object(o) => {
+        write!(f, "import ")?;
+        match &self.spec {
+            ImportSpec::Namespace(name) => write!(f, "* as {name}")?,
+            ImportSpec::Default(name) => write!(f, "{name}")?,
+            ImportSpec::Named(specifiers) => {
                 write!(f, "{{ ")?;
-                for (i, member) in o.iter().enumerate() {
-                    write!(f, "{member}")?;
-                    if i < o.len() - 1 {
+                for (i, specifier) in specifiers.iter().enumerate() {
+                    if i > 0 {
                         write!(f, ", ")?;
                     }
+                    if specifier.is_anchor {
+                        write!(f, "&")?;
+                    }
+                    write!(f, "{}", specifier.name)?;
+                    if let Some(alias) = &specifier.alias {
+                        write!(f, " as {alias}")?;
+                    }
                 }
-                write!(f, " }}")
+                write!(f, " }}")?;
             }
-            MonValueKind::Array(a) => {
-                write!(f, "[ ")?;
-                for (i, value) in a.iter().enumerate() {
-                    write!(f, "{value}")?;
-                    if i < a.len() - 1 {
-                        write!(f, ", ")?;
+        }
+        write!(f, " from ")?;
+        write_string(f, &self.path)
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_member(f, self, Layout::of(f))
+    }
+}
+
+impl Display for MonValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write_kind(f, self, Layout::of(f))
+    }
+}
+
+impl Display for MonValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write_value(f, self, Layout::of(f))
+    }
+}
+
+/// Writes a value with its anchor.
+fn write_value(f: &mut std::fmt::Formatter, value: &MonValue, layout: Layout) -> std::fmt::Result {
+    if let Some(anchor) = &value.anchor {
+        write!(f, "&{anchor} ")?;
+    }
+    write_unanchored(f, value, layout)
+}
+
+/// Writes a value without its anchor, and a number as it was written in the source.
+fn write_unanchored(
+    f: &mut std::fmt::Formatter,
+    value: &MonValue,
+    layout: Layout,
+) -> std::fmt::Result {
+    if let MonValueKind::Number(n) = value.kind {
+        let literal = layout
+            .number_literals
+            .and_then(|literals| literals.get(&value.pos_start));
+        // A changed value, e.g. after merging, no longer matches the literal at its position
+        if let Some(literal) = literal.filter(|literal| literal.parse() == Ok(n)) {
+            return write!(f, "{literal}");
+        }
+    }
+    write_kind(f, &value.kind, layout)
+}
+
+fn write_kind(
+    f: &mut std::fmt::Formatter,
+    kind: &MonValueKind,
+    layout: Layout,
+) -> std::fmt::Result {
+    match kind {
+        MonValueKind::String(s) => write_string(f, s),
+        MonValueKind::Number(n) => write!(f, "{n}"),
+        MonValueKind::Boolean(b) => write!(f, "{b}"),
+        MonValueKind::Null => write!(f, "null"),
+        MonValueKind::Object(members) => {
+            let mut members: Vec<&Member> = members.iter().collect();
+            if layout.options.sort_keys {
+                // Sort the runs of members between spreads and imports
+                for run in members.split_mut(|member| member_sort_key(member).is_none()) {
+                    run.sort_by_key(|member| member_sort_key(member));
+                }
+            }
+            write_block(
+                f,
+                "{",
+                "}",
+                &members,
+                layout,
+                |member| member.doc_key(),
+                |f, member, layout| write_member(f, member, layout),
+            )
+        }
+        MonValueKind::Array(values) => write_block(
+            f,
+            "[",
+            "]",
+            values,
+            layout,
+            |value| Some(value.pos_start),
+            write_value,
+        ),
+        MonValueKind::Alias(a) => {
+            write!(f, "*")?;
+            write_key(f, a)
+        }
+        MonValueKind::EnumValue {
+            enum_name,
+            variant_name,
+        } => write!(f, "${enum_name}.{variant_name}"),
+        MonValueKind::ArraySpread(s) => {
+            write!(f, "...*")?;
+            write_key(f, s)
+        }
+        MonValueKind::BareWord(word) => write!(f, "{word}"),
+        MonValueKind::Quantity { value, unit } => write!(f, "{value}{unit}"),
+        // Read back as the same bytes where `Bytes` is expected
+        MonValueKind::Bytes(bytes) => write_string(f, &encode_base64(bytes)),
+    }
+}
+
+/// The key a member is sorted by, or `None` if it must stay in place.
+fn member_sort_key(member: &Member) -> Option<&str> {
+    match member {
+        Member::Pair(pair) => Some(&pair.key),
+        Member::TypeDefinition(type_def) => Some(&type_def.name),
+        Member::Guarded(_, member) => member_sort_key(member),
+        Member::Spread(..) | Member::Import(_) => None,
+    }
+}
+
+fn write_member(f: &mut std::fmt::Formatter, member: &Member, layout: Layout) -> std::fmt::Result {
+    match member {
+        Member::Pair(pair) => {
+            // `&name: value` is shorthand for an anchor named after its key
+            let anchored_key = pair.value.anchor.as_deref() == Some(pair.key.as_str());
+            if anchored_key {
+                write!(f, "&")?;
+            }
+            match layout.options.quote_style {
+                QuoteStyle::AsNeeded => write_key(f, &pair.key)?,
+                QuoteStyle::Always => write_string(f, &pair.key)?,
+            }
+            match &pair.validation {
+                Some(validation) => write!(f, " :: {validation} = ")?,
+                None => write!(f, ": ")?,
+            }
+            if anchored_key {
+                write_unanchored(f, &pair.value, layout)
+            } else {
+                write_value(f, &pair.value, layout)
+            }
+        }
+        Member::Spread(name, _) => {
+            write!(f, "...*")?;
+            write_key(f, name)
+        }
+        Member::Import(import) => write!(f, "{import}"),
+        Member::Guarded(guard, member) => {
+            write!(f, "{guard} ")?;
+            write_member(f, member, layout)
+        }
+        Member::TypeDefinition(type_def) => {
+            write_key(f, &type_def.name)?;
+            match &type_def.def_type {
+                TypeDef::Struct(struct_def) => {
+                    write!(f, ": #struct ")?;
+                    if let Some(base) = &struct_def.extends {
+                        write!(f, "extends ")?;
+                        write_key(f, base)?;
+                        write!(f, " ")?;
                     }
+                    // `None` stands for the `...` that marks an open struct.
+                    let items: Vec<Option<&FieldDef>> = struct_def
+                        .fields
+                        .iter()
+                        .map(Some)
+                        .chain(struct_def.open.then_some(None))
+                        .collect();
+                    write_block(
+                        f,
+                        "{",
+                        "}",
+                        &items,
+                        layout,
+                        |_| None,
+                        |f, field, layout| {
+                            let Some(field) = field else {
+                                return write!(f, "...");
+                            };
+                            write_key(f, &field.name)?;
+                            write!(f, "({})", field.type_spec)?;
+                            if field.optional {
+                                write!(f, "?")?;
+                            }
+                            if let Some(alias) = &field.alias {
+                                write!(f, " as ")?;
+                                write_string(f, alias)?;
+                            }
+                            if let Some(default_value) = &field.default_value {
+                                write!(f, " = ")?;
+                                write_value(f, default_value, layout)?;
+                            }
+                            Ok(())
+                        },
+                    )?;
+                    for group in &struct_def.one_of {
+                        write!(f, " one_of(")?;
+                        for (i, name) in group.iter().enumerate() {
+                            if i > 0 {
+                                write!(f, ", ")?;
+                            }
+                            write_key(f, name)?;
+                        }
+                        write!(f, ")")?;
+                    }
+                    Ok(())
                 }
-                write!(f, "]")
+                TypeDef::Enum(enum_def) => {
+                    write!(f, ": #enum ")?;
+                    write_block(
+                        f,
+                        "{",
+                        "}",
+                        &enum_def.variants,
+                        layout,
+                        |_| None,
+                        |f, (variant, discriminant), layout| {
+                            write_key(f, variant)?;
+                            if let Some(value) = discriminant {
+                                write!(f, " = ")?;
+                                write_value(f, value, layout)?;
+                            }
+                            Ok(())
+                        },
+                    )
+                }
+                TypeDef::Alias(spec) => write!(f, ": #alias {spec}"),
             }
-            MonValueKind::Alias(a) => write!(f, "*{a}"),
-            MonValueKind::EnumValue {
-                enum_name,
-                variant_name,
-            } => {
-                write!(f, "${enum_name}.{variant_name}")
+        }
+    }
+}
+
+/// Writes a delimited, comma-separated list of items, one per line when indenting.
+///
+/// When indenting, the comments the layout has for an item's `doc_key` are written around it.
+fn write_block<T>(
+    f: &mut std::fmt::Formatter,
+    open: &str,
+    close: &str,
+    items: &[T],
+    layout: Layout,
+    doc_key: impl Fn(&T) -> Option<usize>,
+    write_item: impl Fn(&mut std::fmt::Formatter, &T, Layout) -> std::fmt::Result,
+) -> std::fmt::Result {
+    if items.is_empty() {
+        return write!(f, "{open}{close}");
+    }
+    match layout.depth {
+        None => {
+            write!(f, "{open} ")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_item(f, item, layout)?;
             }
-            MonValueKind::ArraySpread(s) => write!(f, "...*{s}"),
+            write!(f, " {close}")
+        }
+        Some(depth) => {
+            let indent = " ".repeat(layout.options.indent);
+            let nested = Layout {
+                depth: Some(depth + 1),
+                ..layout
+            };
+            writeln!(f, "{open}")?;
+            for (i, item) in items.iter().enumerate() {
+                let doc = layout
+                    .docs
+                    .zip(doc_key(item))
+                    .and_then(|(docs, key)| docs.get(&key));
+                let leading = doc.and_then(|doc| doc.leading_doc.as_deref());
+                for line in leading.into_iter().flat_map(str::lines) {
+                    write!(f, "{}", indent.repeat(depth + 1))?;
+                    write_comment(f, line)?;
+                    writeln!(f)?;
+                }
+                write!(f, "{}", indent.repeat(depth + 1))?;
+                write_item(f, item, nested)?;
+                if i + 1 < items.len() || layout.options.trailing_comma {
+                    write!(f, ",")?;
+                }
+                if let Some(trailing) = doc.and_then(|doc| doc.trailing_doc.as_deref()) {
+                    write!(f, " ")?;
+                    write_comment(f, trailing)?;
+                }
+                writeln!(f)?;
+                let closing = doc.and_then(|doc| doc.closing_doc.as_deref());
+                for line in closing.into_iter().flat_map(str::lines) {
+                    write!(f, "{}", indent.repeat(depth + 1))?;
+                    write_comment(f, line)?;
+                    writeln!(f)?;
+                }
+            }
+            write!(f, "{}{close}", indent.repeat(depth))
         }
     }
 }
 
-impl Display for MonValue {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if let Some(anchor) = &self.anchor {
-            write!(f, "&{anchor} ")?;
+/// Writes a `//` comment with the text the lexer read from it.
+fn write_comment(f: &mut std::fmt::Formatter, text: &str) -> std::fmt::Result {
+    if text.is_empty() || text.starts_with(['/', '!']) {
+        write!(f, "//{text}")
+    } else {
+        write!(f, "// {text}")
+    }
+}
+
+/// Writes a key bare if the parser would read it back as the same key, and quoted otherwise.
+fn write_key(f: &mut std::fmt::Formatter, key: &str) -> std::fmt::Result {
+    let is_bare = key.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !matches!(
+                part,
+                "true" | "on" | "false" | "off" | "null" | "import" | "from" | "as"
+            )
+    });
+    if is_bare {
+        write!(f, "{key}")
+    } else {
+        write_string(f, key)
+    }
+}
+
+/// Writes a double-quoted string literal, escaping it the way the lexer reads it back.
+fn write_string(f: &mut std::fmt::Formatter, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            '\u{8}' => write!(f, "\\b")?,
+            '\u{c}' => write!(f, "\\f")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => write!(f, "{c}")?,
         }
-        write!(f, "{}", self.kind)
     }
+    write!(f, "\"")
 }
 
 impl Pair {
+    /// Returns the key this pair is serialized and deserialized under: its
+    /// [`serialized_key`](Pair::serialized_key) if set, and its `key` otherwise.
+    #[must_use]
+    pub fn output_key(&self) -> &str {
+        self.serialized_key.as_deref().unwrap_or(&self.key)
+    }
+
     #[track_caller]
     #[must_use]
     pub fn get_span(&self) -> SourceSpan {
@@ -332,11 +1275,7 @@ impl Pair {
                 );
                 SourceSpan::new(0.into(), 0)
             }
-            Some(valid) => match valid {
-                TypeSpec::Simple(_, source_span)
-                | TypeSpec::Collection(_, source_span)
-                | TypeSpec::Spread(_, source_span) => *source_span,
-            },
+            Some(valid) => valid.get_span(),
         }
     }
 }
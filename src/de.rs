@@ -0,0 +1,449 @@
+//! # Direct `serde` Deserialization of MON Values
+//!
+//! This module implements [`serde::Deserializer`] for resolved [`MonValue`]s, so a typed Rust
+//! value can be read straight out of the AST without first serializing it to JSON and parsing
+//! that string back.
+//!
+//! ## Architectural Overview
+//!
+//! The deserializer borrows from the `MonValue` it reads, so string fields can be deserialized
+//! as `&str` without copying. The data model mirrors the one used by
+//! [`serialization`](crate::serialization):
+//!
+//! - Objects are exposed as maps. Only `Pair` members are visible; type definitions are skipped.
+//! - Arrays are exposed as sequences.
+//! - Enum values (`$Status.Active`) are exposed as their variant name, so they can be read into a
+//!   Rust enum with a matching unit variant or into a `String`.
+//! - Integral numbers are offered as integers, so they can be read into `u16`, `i64`, and so on.
+//!
+//! Aliases and spreads must have been resolved before deserialization. If one is found, a
+//! [`DeserializeError::UnresolvedValue`] is returned.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use mon_core::de::from_value;
+//! use mon_core::parser::Parser;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Server<'a> {
+//!     host: &'a str,
+//!     port: u16,
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let source = r#"{ host: "localhost", port: 8080 }"#;
+//! let document = Parser::new(source)?.parse_document()?;
+//! let server: Server = from_value(&document.root)?;
+//!
+//! assert_eq!(server.host, "localhost");
+//! assert_eq!(server.port, 8080);
+//! # Ok(())
+//! # }
+//! ```
+use crate::ast::{Member, MonValue, MonValueKind, Pair};
+use crate::error::DeserializeError;
+use crate::utils::source_span;
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::forward_to_deserialize_any;
+use std::fmt::Display;
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DeserializeError::Custom(msg.to_string())
+    }
+}
+
+/// Deserializes a typed value from a resolved [`MonValue`].
+///
+/// # Errors
+/// Returns a [`DeserializeError`] if the value does not fit the target type, or if it still
+/// contains aliases or spreads.
+pub fn from_value<'de, T>(value: &'de MonValue) -> Result<T, DeserializeError>
+where
+    T: de::Deserialize<'de>,
+{
+    T::deserialize(value)
+}
+
+fn unresolved(kind: &str, value: &MonValue) -> DeserializeError {
+    DeserializeError::UnresolvedValue {
+        kind: kind.to_string(),
+        span: value.pos_span(),
+    }
+}
+
+/// Returns `n` as an `i64` if it is a whole number that fits without loss.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn as_integer(n: f64) -> Option<i64> {
+    // 2^63 is exactly representable, so this bound excludes everything `i64` cannot hold.
+    if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de MonValue {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.kind {
+            MonValueKind::String(s) => visitor.visit_borrowed_str(s),
+            MonValueKind::Number(n) => match as_integer(*n) {
+                Some(i) => visitor.visit_i64(i),
+                None => visitor.visit_f64(*n),
+            },
+            MonValueKind::Boolean(b) => visitor.visit_bool(*b),
+            MonValueKind::Null => visitor.visit_unit(),
+            MonValueKind::Array(elements) => visitor.visit_seq(SeqDeserializer {
+                iter: elements.iter(),
+            }),
+            MonValueKind::Object(members) => visitor.visit_map(MapDeserializer {
+                iter: members.iter(),
+                value: None,
+            }),
+            MonValueKind::EnumValue { variant_name, .. } | MonValueKind::BareWord(variant_name) => {
+                visitor.visit_borrowed_str(variant_name)
+            }
+            // Quantities that were not normalized by a `Duration` or `ByteSize` validation
+            // keep their literal form.
+            MonValueKind::Quantity { value, unit } => {
+                visitor.visit_string(format!("{value}{unit}"))
+            }
+            // Like `Vec<u8>` serializes, see `deserialize_bytes` for byte buffers
+            MonValueKind::Bytes(bytes) => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(bytes.iter().copied()))
+            }
+            MonValueKind::Alias(_) => Err(unresolved("alias", self)),
+            MonValueKind::ArraySpread(_) => Err(unresolved("array spread", self)),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.kind {
+            MonValueKind::Number(n) => visitor.visit_f64(*n),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.kind {
+            MonValueKind::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match &self.kind {
+            MonValueKind::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match &self.kind {
+            // `$Status.Active` and `"Active"` both name a unit variant.
+            MonValueKind::EnumValue { variant_name, .. } | MonValueKind::BareWord(variant_name) => {
+                visitor.visit_enum(variant_name.as_str().into_deserializer())
+            }
+            MonValueKind::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            // `{ Variant: value }` is the externally tagged form for variants with data.
+            MonValueKind::Object(members) => {
+                let mut pairs = members.iter().filter_map(|member| match member {
+                    Member::Pair(pair) => Some(pair),
+                    _ => None,
+                });
+                match (pairs.next(), pairs.next()) {
+                    (Some(pair), None) => visitor.visit_enum(EnumDeserializer { pair }),
+                    _ => Err(de::Error::invalid_value(
+                        de::Unexpected::Map,
+                        &"an object with exactly one key",
+                    )),
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char str string
+        unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for &'de MonValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+struct SeqDeserializer<'de> {
+    iter: std::slice::Iter<'de, MonValue>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.iter
+            .next()
+            .map(|value| seed.deserialize(value))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<'de> {
+    iter: std::slice::Iter<'de, Member>,
+    value: Option<&'de MonValue>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        for member in self.iter.by_ref() {
+            match member {
+                Member::Pair(pair) => {
+                    self.value = Some(&pair.value);
+                    return seed
+                        .deserialize(de::value::BorrowedStrDeserializer::new(pair.output_key()))
+                        .map(Some);
+                }
+                // Type definitions are not part of the data.
+                Member::TypeDefinition(_) | Member::Import(_) => {}
+                Member::Spread(_, span) => {
+                    return Err(DeserializeError::UnresolvedValue {
+                        kind: "object spread".to_string(),
+                        span: *span,
+                    })
+                }
+                Member::Guarded(guard, _) => {
+                    return Err(DeserializeError::UnresolvedValue {
+                        kind: "guarded member".to_string(),
+                        span: source_span(guard.pos_start, guard.pos_end),
+                    })
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+struct EnumDeserializer<'de> {
+    pair: &'de Pair,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = DeserializeError;
+    type Variant = &'de MonValue;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(de::value::BorrowedStrDeserializer::new(
+            self.pair.output_key(),
+        ))?;
+        Ok((variant, &self.pair.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &'de MonValue {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(all(test, feature = "resolver"))]
+mod tests {
+    use super::*;
+    use crate::api::analyze;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Status {
+        Active,
+        Inactive,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct User<'a> {
+        name: &'a str,
+        age: u8,
+        score: f64,
+        status: Status,
+        tags: Vec<String>,
+        nickname: Option<String>,
+    }
+
+    #[test]
+    fn test_deserialize_struct() {
+        let source = r#"
+        {
+            Status: #enum { Active, Inactive },
+            &base: { tags: ["a", "b"], nickname: null },
+            user: {
+                ...*base,
+                name: "Alice",
+                age: 30,
+                score: 9.5,
+                status: $Status.Active,
+            },
+        }
+        "#;
+        let result = analyze(source, "test.mon").unwrap();
+        #[derive(Deserialize)]
+        struct Root<'a> {
+            #[serde(borrow)]
+            user: User<'a>,
+        }
+        let root: Root = result.deserialize().unwrap();
+
+        assert_eq!(
+            root.user,
+            User {
+                name: "Alice",
+                age: 30,
+                score: 9.5,
+                status: Status::Active,
+                tags: vec!["a".to_string(), "b".to_string()],
+                nickname: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_externally_tagged_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Shape {
+            Circle { radius: f64 },
+            Point,
+        }
+
+        let result = analyze(
+            r#"{ a: { Circle: { radius: 2 } }, b: "Point" }"#,
+            "test.mon",
+        )
+        .unwrap();
+        let shapes: HashMap<String, Shape> = result.deserialize().unwrap();
+
+        assert_eq!(shapes["a"], Shape::Circle { radius: 2.0 });
+        assert_eq!(shapes["b"], Shape::Point);
+    }
+
+    #[test]
+    fn test_deserialize_numbers() {
+        #[derive(Deserialize)]
+        struct Numbers {
+            ratio: f64,
+            whole: f64,
+            count: i64,
+        }
+
+        let result = analyze("{ ratio: 1.5, whole: 2, count: -3 }", "test.mon").unwrap();
+        let numbers: Numbers = result.deserialize().unwrap();
+        assert!((numbers.ratio - 1.5).abs() < f64::EPSILON);
+        assert!((numbers.whole - 2.0).abs() < f64::EPSILON);
+        assert_eq!(numbers.count, -3);
+    }
+
+    #[test]
+    fn test_deserialize_integer_out_of_range() {
+        let result = analyze("{ port: 70000 }", "test.mon").unwrap();
+        let err = result.deserialize::<HashMap<String, u16>>().unwrap_err();
+        assert!(matches!(err, DeserializeError::Custom(_)));
+    }
+
+    #[test]
+    fn test_deserialize_unresolved_alias() {
+        let value = MonValue {
+            kind: MonValueKind::Alias("missing".to_string()),
+            anchor: None,
+            pos_start: 3,
+            pos_end: 11,
+        };
+        let err = from_value::<String>(&value).unwrap_err();
+        assert_eq!(
+            err,
+            DeserializeError::UnresolvedValue {
+                kind: "alias".to_string(),
+                span: (3, 8).into(),
+            }
+        );
+    }
+}
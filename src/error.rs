@@ -22,6 +22,9 @@
 //!     during type validation. This includes type mismatches, missing or extra fields in structs,
 //!     and undefined enum variants.
 //!
+//! 4.  **[`MonWarning`]**: Problems that do not fail the analysis, such as a spread whose keys
+//!     are all overridden. They are collected alongside the result instead of being returned as errors.
+//!
 //! ## Use Cases
 //!
 //! When you use the `mon-core` library, you will primarily interact with `MonError`. You can
@@ -33,12 +36,13 @@
 //! ## Example: Handling an Error
 //!
 //! ```rust
-//! use mon_core::api::analyze;
+//! use mon_core::parser::Parser;
 //!
 //! // This source code has a syntax error (a missing comma).
 //! let source = "{ key1: \"value1\" key2: \"value2\" }";
 //!
-//! let result = analyze(source, "bad.mon");
+//! let result = Parser::new_with_name(source, "bad.mon".to_string())
+//!     .and_then(|mut parser| parser.parse_document());
 //!
 //! match result {
 //!     Ok(_) => println!("This should not have succeeded!"),
@@ -47,6 +51,10 @@
 //!         // (Note: The actual output is a graphical report with colors and source snippets)
 //!         println!("{:?}", err);
 //!
+//!         // Or render it to a string, e.g. for a log file.
+//!         let report = mon_core::error::render(&err, false);
+//!         assert!(report.contains("bad.mon"));
+//!
 //!         // You can also programmatically inspect the error.
 //!         match err {
 //!             mon_core::error::MonError::Parser(p_err) => {
@@ -57,7 +65,11 @@
 //!     }
 //! }
 //! ```
-use miette::{Diagnostic, NamedSource, SourceSpan};
+use crate::utils::get_line_and_column;
+use miette::{
+    Diagnostic, GraphicalReportHandler, GraphicalTheme, NamedSource, NarratableReportHandler,
+    SourceSpan,
+};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -77,6 +89,80 @@ pub enum MonError {
     Resolver(Box<ResolverError>),
 }
 
+/// The broad class of a [`MonError`], so tools can branch on the kind of failure
+/// without matching on rendered messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The source text could not be split into tokens.
+    Lexing,
+    /// The tokens did not follow the MON grammar.
+    Parsing,
+    /// Imports, anchors, aliases, or spreads could not be resolved.
+    Resolution,
+    /// The data did not match its declared type.
+    Validation,
+    /// A file could not be read.
+    Io,
+}
+
+impl MonError {
+    /// Returns the stable diagnostic code of this error, e.g. `"validation::type_mismatch"`.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            MonError::Parser(err) => err.error_code(),
+            MonError::Resolver(err) => err.error_code(),
+        }
+    }
+
+    /// Returns the category this error belongs to.
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            MonError::Parser(err) => err.category(),
+            MonError::Resolver(err) => err.category(),
+        }
+    }
+
+    /// Returns whether the error is local to one token, value or field, so that the rest of
+    /// the document is still sound, e.g. a type mismatch or an invalid token.
+    ///
+    /// Structural errors, such as an unclosed brace, leave the parser unable to tell where
+    /// anything after them belongs. An editor can keep showing its last analysis while the
+    /// error is recoverable, and should drop it otherwise.
+    #[must_use]
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            MonError::Parser(err) => err.is_recoverable(),
+            MonError::Resolver(err) => err.is_recoverable(),
+        }
+    }
+
+    /// Returns the span the error points at, i.e. the span of its first label.
+    ///
+    /// This gives uniform access to the position of any error, e.g. to sort a list of
+    /// errors by `primary_span().map(|span| span.offset())`.
+    #[must_use]
+    pub fn primary_span(&self) -> Option<SourceSpan> {
+        self.labels()?.next().map(|label| *label.inner())
+    }
+
+    /// Returns the 1-based line and column where the error is reported, for logging
+    /// without rendering the full report.
+    ///
+    /// Returns `None` if the error has no source location.
+    #[must_use]
+    pub fn location(&self) -> Option<(usize, usize)> {
+        let offset = self.primary_span()?.offset();
+        let preceding = self
+            .source_code()?
+            .read_span(&(0, offset).into(), 0, 0)
+            .ok()?;
+        let text = std::str::from_utf8(preceding.data()).ok()?;
+        Some(get_line_and_column(text, offset))
+    }
+}
+
 impl From<ParserError> for MonError {
     fn from(err: ParserError) -> Self {
         MonError::Parser(Box::new(err))
@@ -89,6 +175,112 @@ impl From<ResolverError> for MonError {
     }
 }
 
+/// Renders `err` as a graphical report with a source snippet, as miette prints it to a terminal.
+///
+/// Set `use_color` to `false` to drop the ANSI escape codes, e.g. when writing to a log file.
+#[must_use]
+pub fn render(err: &MonError, use_color: bool) -> String {
+    let theme = if use_color {
+        GraphicalTheme::unicode()
+    } else {
+        GraphicalTheme::unicode_nocolor()
+    };
+    let mut buffer = String::new();
+    GraphicalReportHandler::new_themed(theme)
+        .render_report(&mut buffer, err)
+        .expect("writing to a String cannot fail");
+    buffer
+}
+
+/// Renders `err` as plain text without box drawing or colors, for CI logs and screen readers.
+#[must_use]
+pub fn render_plain(err: &MonError) -> String {
+    let mut buffer = String::new();
+    NarratableReportHandler::new()
+        .render_report(&mut buffer, err)
+        .expect("writing to a String cannot fail");
+    buffer
+}
+
+/// A problem that does not stop analysis, but likely points to a mistake in the source.
+#[derive(Error, Debug, Diagnostic, Clone)]
+pub enum MonWarning {
+    /// Every key copied by a spread is overridden later in the same object, so the spread
+    /// has no effect.
+    #[error("Spread '...*{name}' has no effect")]
+    #[diagnostic(
+        code(resolver::redundant_spread),
+        severity(Warning),
+        help("Every key from '&{name}' is overridden in this object. Remove the spread.")
+    )]
+    RedundantSpread {
+        name: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("All keys of this spread are overridden")]
+        span: SourceSpan,
+    },
+
+    /// A document defines or imports by name two different types with the same name, so the
+    /// later one replaces the earlier one.
+    #[error("Type '{name}' shadows a type of the same name defined in {original_file}")]
+    #[diagnostic(
+        code(resolver::type_shadowed),
+        severity(Warning),
+        help("Rename one of the types, or import it under another name with `import {{ {name} as ... }}`.")
+    )]
+    TypeShadowed {
+        name: String,
+        /// The file of the definition that is replaced.
+        original_file: String,
+        /// The span of the replaced definition's name, in `original_file`.
+        original_span: SourceSpan,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        /// The definition or import that replaces it.
+        #[label("'{name}' is redefined here")]
+        shadowing_span: SourceSpan,
+    },
+}
+
+impl MonWarning {
+    /// Returns the stable diagnostic code of this warning, e.g. `"resolver::redundant_spread"`.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            MonWarning::RedundantSpread { .. } => "resolver::redundant_spread",
+            MonWarning::TypeShadowed { .. } => "resolver::type_shadowed",
+        }
+    }
+
+    /// Returns the span the warning points at, like [`MonError::primary_span`].
+    #[must_use]
+    pub fn primary_span(&self) -> Option<SourceSpan> {
+        self.labels()?.next().map(|label| *label.inner())
+    }
+}
+
+/// The limit of [`ParserLimits`](crate::parser::ParserLimits) a document exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitKind {
+    /// [`ParserLimits::max_members`](crate::parser::ParserLimits::max_members)
+    Members,
+    /// [`ParserLimits::max_array_len`](crate::parser::ParserLimits::max_array_len)
+    ArrayLength,
+    /// [`ParserLimits::max_string_len`](crate::parser::ParserLimits::max_string_len)
+    StringLength,
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LimitKind::Members => "member",
+            LimitKind::ArrayLength => "array length",
+            LimitKind::StringLength => "string length",
+        })
+    }
+}
+
 /// An error that occurred during the parsing phase.
 #[derive(Error, Debug, Diagnostic, Clone)]
 #[error("Parser Error")]
@@ -107,6 +299,108 @@ pub enum ParserError {
         expected: String,
     },
 
+    /// The lexer could not turn part of the source into a token, e.g. an unclosed string.
+    #[error("Invalid token")]
+    #[diagnostic(
+        code(lexer::invalid_token),
+        help(
+            "This text is not valid MON. Check for unclosed strings, malformed escapes, \
+             and malformed or out-of-range numbers."
+        )
+    )]
+    InvalidToken {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Invalid token here")]
+        span: SourceSpan,
+    },
+
+    /// A validated member used `:` instead of `=`, e.g. `key :: Type : value`.
+    #[error("Validated members must use '=' to assign")]
+    #[diagnostic(
+        code(parser::colon_after_validation),
+        help("Write `key :: Type = value`. The `:` form is only for members without a type.")
+    )]
+    ColonAfterValidation {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Replace this ':' with '='")]
+        span: SourceSpan,
+    },
+
+    /// A collection type has more than one spread, e.g. `[String..., Number...]`.
+    #[error("A collection type can only have one spread")]
+    #[diagnostic(
+        code(parser::multiple_spreads_in_type),
+        help("With two spreads it is ambiguous which elements belong to which type. Keep one spread, e.g. `[String...]` or `[String, Number...]`.")
+    )]
+    MultipleSpreadsInType {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This is the second spread")]
+        span: SourceSpan,
+    },
+
+    /// Something other than the end of the file follows the root object, e.g. the second
+    /// object in `{ a: 1 } { b: 2 }`.
+    #[error("Unexpected content after the root object")]
+    #[diagnostic(
+        code(parser::trailing_content),
+        help("A document has a single root object. Move this content into the root object, or put a line with only `---` before it to start a new document and parse the file with `analyze_multi`.")
+    )]
+    TrailingContent {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("The document already ended before this")]
+        span: SourceSpan,
+    },
+
+    /// A document formatted by [`MonDocument::normalize_spans`](crate::ast::MonDocument::normalize_spans)
+    /// parsed back into a document of a different shape.
+    #[error("The formatted document does not parse back into the same document")]
+    #[diagnostic(
+        code(parser::unstable_formatting),
+        help("The document contains text that the formatter writes verbatim, such as an alias or anchor name, that is not valid there.")
+    )]
+    UnstableFormatting {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("The formatted document differs from here on")]
+        span: SourceSpan,
+    },
+
+    /// The modifiers after a struct field's type are out of order or contradict each other,
+    /// e.g. an optional field with a default value.
+    #[error("Invalid modifiers for field '{field}': {reason}")]
+    #[diagnostic(
+        code(parser::invalid_field_modifiers),
+        help("Modifiers follow the field's type in the order `name(Type)? as \"key\" = default`, and an optional field (`?`) cannot have a default value.")
+    )]
+    InvalidFieldModifiers {
+        field: String,
+        reason: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("{reason}")]
+        span: SourceSpan,
+    },
+
+    /// The document is larger than the [`ParserLimits`](crate::parser::ParserLimits) the
+    /// parser was created with.
+    #[error("The {kind} limit of {limit} was exceeded")]
+    #[diagnostic(
+        code(parser::limit_exceeded),
+        help("The parser was configured to reject documents this large.")
+    )]
+    LimitExceeded {
+        kind: LimitKind,
+        limit: usize,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("The limit is exceeded here")]
+        span: SourceSpan,
+    },
+
     /// The end of the file was reached unexpectedly.
     #[error("Unexpected end of file")]
     #[diagnostic(
@@ -134,6 +428,109 @@ pub enum ParserError {
         expected: String,
     },
 }
+impl ParserError {
+    /// Returns the stable diagnostic code of this error, e.g. `"parser::unexpected_token"`.
+    ///
+    /// Unlike [`Diagnostic::code`], the code is returned as a `&'static str`.
+    #[must_use]
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ParserError::UnexpectedToken { .. } => "parser::unexpected_token",
+            ParserError::InvalidToken { .. } => "lexer::invalid_token",
+            ParserError::ColonAfterValidation { .. } => "parser::colon_after_validation",
+            ParserError::MultipleSpreadsInType { .. } => "parser::multiple_spreads_in_type",
+            ParserError::TrailingContent { .. } => "parser::trailing_content",
+            ParserError::UnstableFormatting { .. } => "parser::unstable_formatting",
+            ParserError::InvalidFieldModifiers { .. } => "parser::invalid_field_modifiers",
+            ParserError::LimitExceeded { .. } => "parser::limit_exceeded",
+            ParserError::UnexpectedEof { .. } => "parser::unexpected_eof",
+            ParserError::MissingExpectedToken { .. } => "parser::missing_expected_token",
+        }
+    }
+
+    /// Returns the category this error belongs to.
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ParserError::InvalidToken { .. } => ErrorCategory::Lexing,
+            _ => ErrorCategory::Parsing,
+        }
+    }
+
+    /// Returns whether the error is local, see [`MonError::is_recoverable`].
+    ///
+    /// An unexpected or missing token may come from unbalanced delimiters, so only errors
+    /// about a single, otherwise well-placed construct count as local. An invalid token that
+    /// runs to the end of the source, like an unclosed string, swallowed everything after it.
+    #[must_use]
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ParserError::InvalidToken { src, span } => {
+                span.offset() + span.len() < src.inner().trim_end().len()
+            }
+            ParserError::ColonAfterValidation { .. }
+            | ParserError::MultipleSpreadsInType { .. }
+            | ParserError::InvalidFieldModifiers { .. } => true,
+            ParserError::UnexpectedToken { .. }
+            | ParserError::TrailingContent { .. }
+            | ParserError::UnstableFormatting { .. }
+            | ParserError::LimitExceeded { .. }
+            | ParserError::UnexpectedEof { .. }
+            | ParserError::MissingExpectedToken { .. } => false,
+        }
+    }
+}
+
+/// An error that occurred while turning source text into tokens, see
+/// [`Lexer::lex_checked`](crate::lexer::Lexer::lex_checked).
+///
+/// The parser reports the same problems as [`ParserError::InvalidToken`].
+#[derive(Error, Debug, Diagnostic, Clone)]
+#[error("Lexer Error")]
+pub enum LexerError {
+    /// Part of the source is not a valid token, e.g. an unclosed string.
+    #[error("Invalid token")]
+    #[diagnostic(
+        code(lexer::invalid_token),
+        help(
+            "This text is not valid MON. Check for unclosed strings, malformed escapes, \
+             and malformed or out-of-range numbers."
+        )
+    )]
+    InvalidToken {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Invalid token here")]
+        span: SourceSpan,
+    },
+}
+
+impl LexerError {
+    /// Returns the stable diagnostic code of this error, e.g. `"lexer::invalid_token"`.
+    ///
+    /// Unlike [`Diagnostic::code`], the code is returned as a `&'static str`.
+    #[must_use]
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            LexerError::InvalidToken { .. } => "lexer::invalid_token",
+        }
+    }
+}
+
+impl From<LexerError> for ParserError {
+    fn from(err: LexerError) -> Self {
+        match err {
+            LexerError::InvalidToken { src, span } => ParserError::InvalidToken { src, span },
+        }
+    }
+}
+
+impl From<LexerError> for MonError {
+    fn from(err: LexerError) -> Self {
+        ParserError::from(err).into()
+    }
+}
+
 /// An error that occurred during the resolution or validation phase.
 #[derive(Error, Debug, Diagnostic, Clone)]
 #[error("Resolver Error")]
@@ -152,6 +549,80 @@ pub enum ResolverError {
         span: SourceSpan,
     },
 
+    /// An imported module exists but may not be read by the current user.
+    #[error("Permission denied reading module: {path}")]
+    #[diagnostic(
+        code(resolver::module_permission_denied),
+        help("Check the permissions of the file and the directories leading to it.")
+    )]
+    ModulePermissionDenied {
+        path: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("...referenced here")]
+        span: SourceSpan,
+    },
+
+    /// An imported module is not valid UTF-8 text.
+    #[error("Module is not valid UTF-8: {path}")]
+    #[diagnostic(
+        code(resolver::module_not_utf8),
+        help("MON files must be UTF-8 encoded. Check that the path points to a MON file.")
+    )]
+    ModuleNotUtf8 {
+        path: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("...referenced here")]
+        span: SourceSpan,
+    },
+
+    /// An imported module could not be read for another reason, e.g. because it is a directory.
+    #[error("Failed to read module {path}: {reason}")]
+    #[diagnostic(code(resolver::module_read_failed))]
+    ModuleReadFailed {
+        path: String,
+        reason: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("...referenced here")]
+        span: SourceSpan,
+    },
+
+    /// An imported module is not valid MON. The parser error in the module is its source.
+    #[error("Failed to parse imported module {path}")]
+    #[diagnostic(
+        code(resolver::imported_module_invalid),
+        help("Fix the error in the imported module shown below.")
+    )]
+    ImportedModuleInvalid {
+        path: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("...referenced here")]
+        span: SourceSpan,
+        #[source]
+        #[diagnostic_source]
+        cause: ParserError,
+    },
+
+    /// A relative file name could not be made absolute because the current directory is
+    /// unavailable, e.g. because it was deleted or the process runs in a sandbox without one.
+    #[error("Cannot determine the current directory: {reason}")]
+    #[diagnostic(
+        code(resolver::current_dir_unavailable),
+        help("Pass an absolute file name, or a base directory with `analyze_in`.")
+    )]
+    CurrentDirUnavailable { reason: String },
+
+    /// A file passed to [`analyze_layers`](crate::api::analyze_layers) could not be read.
+    #[error("Cannot read layer '{path}': {reason}")]
+    #[diagnostic(
+        code(resolver::layer_unreadable),
+        help("Check that the file exists, is readable, and is valid UTF-8.")
+    )]
+    LayerUnreadable { path: String, reason: String },
+
     /// An anchor referenced by an alias or spread could not be found.
     #[error("Anchor '&{name}' not found")]
     #[diagnostic(
@@ -166,6 +637,20 @@ pub enum ResolverError {
         span: SourceSpan,
     },
 
+    /// A spread (`...*name`) refers to an anchor that does not exist.
+    #[error("Spread source '&{name}' not found")]
+    #[diagnostic(
+        code(resolver::spread_source_not_found),
+        help("Define the anchor with '&{name}: ...' before spreading it with '...*{name}'.")
+    )]
+    SpreadSourceNotFound {
+        name: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This spread has no anchor to copy from")]
+        span: SourceSpan,
+    },
+
     /// The spread operator (`...*`) was used on a value that is not an object.
     #[error("Cannot spread a non-object value")]
     #[diagnostic(
@@ -194,6 +679,34 @@ pub enum ResolverError {
         span: SourceSpan,
     },
 
+    /// A bare word such as `Active` was used where no enum type is expected.
+    #[error("Unexpected bare word '{word}'")]
+    #[diagnostic(
+        code(resolver::unexpected_bare_word),
+        help("Bare words are only allowed for values validated as an enum. Write \"{word}\" for a string.")
+    )]
+    UnexpectedBareWord {
+        word: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This is not a known value")]
+        span: SourceSpan,
+    },
+
+    /// A module imports itself, e.g. `import * as me from "./me.mon"` inside `me.mon`.
+    #[error("A module cannot import itself")]
+    #[diagnostic(
+        code(resolver::self_import),
+        help("Remove this import. Types and anchors of this file are already in scope.")
+    )]
+    SelfImport {
+        path: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("'{path}' is this file")]
+        span: SourceSpan,
+    },
+
     /// A circular dependency was detected in module imports.
     #[error("Circular dependency detected")]
     #[diagnostic(
@@ -208,6 +721,82 @@ pub enum ResolverError {
         span: SourceSpan,
     },
 
+    /// A struct extends a struct that, directly or through others, extends it.
+    #[error("Struct '{name}' inherits from itself")]
+    #[diagnostic(
+        code(resolver::cyclic_inheritance),
+        help("The following chain of `extends` forms a loop: {cycle}")
+    )]
+    CyclicInheritance {
+        name: String,
+        cycle: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This struct is part of the cycle")]
+        span: SourceSpan,
+    },
+
+    /// A struct extends a type that is not a struct, e.g. an enum.
+    #[error("Struct '{name}' extends '{base}', which is not a struct")]
+    #[diagnostic(
+        code(resolver::extends_non_struct),
+        help("A struct can only extend another struct.")
+    )]
+    ExtendsNonStruct {
+        name: String,
+        base: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Extends '{base}' here")]
+        span: SourceSpan,
+    },
+
+    /// A `one_of` group of a struct that extends another lists a field that neither struct has.
+    #[error("Struct '{struct_name}' has no field '{field}' for its `one_of` group")]
+    #[diagnostic(
+        code(resolver::unknown_one_of_field),
+        help("A `one_of` group can list the struct's own fields and the fields it inherits.")
+    )]
+    UnknownOneOfField {
+        struct_name: String,
+        field: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Defined here")]
+        span: SourceSpan,
+    },
+
+    /// Two fields of a struct are serialized under the same key, because of an `as "key"`
+    /// alias, e.g. `#struct { user_name(String) as "name", name(String) }`.
+    #[error("Struct '{struct_name}' has more than one field serialized as '{key}'")]
+    #[diagnostic(
+        code(resolver::duplicate_field_key),
+        help("Each field is written under its alias, or its name if it has none. Give the fields different keys.")
+    )]
+    DuplicateFieldKey {
+        struct_name: String,
+        key: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Defined here")]
+        span: SourceSpan,
+    },
+
+    /// An alias names an alias that, directly or through others, names it.
+    #[error("Alias '{name}' refers to itself")]
+    #[diagnostic(
+        code(resolver::cyclic_alias),
+        help("The following chain of `#alias` definitions forms a loop: {cycle}")
+    )]
+    CyclicAlias {
+        name: String,
+        cycle: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This value's type is an alias in the cycle")]
+        span: SourceSpan,
+    },
+
     /// An error occurred during data validation against a schema.
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -225,12 +814,75 @@ impl From<ParserError> for ResolverError {
     }
 }
 
+impl ResolverError {
+    /// Returns the stable diagnostic code of this error, e.g. `"resolver::anchor_not_found"`.
+    ///
+    /// Unlike [`Diagnostic::code`], the code is returned as a `&'static str`.
+    #[must_use]
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ResolverError::ModuleNotFound { .. } => "resolver::module_not_found",
+            ResolverError::ModulePermissionDenied { .. } => "resolver::module_permission_denied",
+            ResolverError::ModuleNotUtf8 { .. } => "resolver::module_not_utf8",
+            ResolverError::ModuleReadFailed { .. } => "resolver::module_read_failed",
+            ResolverError::ImportedModuleInvalid { .. } => "resolver::imported_module_invalid",
+            ResolverError::CurrentDirUnavailable { .. } => "resolver::current_dir_unavailable",
+            ResolverError::LayerUnreadable { .. } => "resolver::layer_unreadable",
+            ResolverError::AnchorNotFound { .. } => "resolver::anchor_not_found",
+            ResolverError::SpreadSourceNotFound { .. } => "resolver::spread_source_not_found",
+            ResolverError::SpreadOnNonObject { .. } => "resolver::spread_on_non_object",
+            ResolverError::SpreadOnNonArray { .. } => "resolver::spread_on_non_array",
+            ResolverError::UnexpectedBareWord { .. } => "resolver::unexpected_bare_word",
+            ResolverError::SelfImport { .. } => "resolver::self_import",
+            ResolverError::CircularDependency { .. } => "resolver::circular_dependency",
+            ResolverError::CyclicInheritance { .. } => "resolver::cyclic_inheritance",
+            ResolverError::ExtendsNonStruct { .. } => "resolver::extends_non_struct",
+            ResolverError::UnknownOneOfField { .. } => "resolver::unknown_one_of_field",
+            ResolverError::DuplicateFieldKey { .. } => "resolver::duplicate_field_key",
+            ResolverError::CyclicAlias { .. } => "resolver::cyclic_alias",
+            ResolverError::Validation(err) => err.error_code(),
+            ResolverError::WrappedParserError(err) => err.error_code(),
+        }
+    }
+
+    /// Returns the category this error belongs to.
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ResolverError::ModuleNotFound { .. }
+            | ResolverError::ModulePermissionDenied { .. }
+            | ResolverError::ModuleNotUtf8 { .. }
+            | ResolverError::ModuleReadFailed { .. }
+            | ResolverError::CurrentDirUnavailable { .. }
+            | ResolverError::LayerUnreadable { .. } => ErrorCategory::Io,
+            ResolverError::Validation(_) => ErrorCategory::Validation,
+            ResolverError::WrappedParserError(err) => err.category(),
+            ResolverError::ImportedModuleInvalid { cause, .. } => cause.category(),
+            _ => ErrorCategory::Resolution,
+        }
+    }
+
+    /// Returns whether the error is local, see [`MonError::is_recoverable`].
+    ///
+    /// The document parsed, so every resolver error is local, except for a parser error
+    /// in the document itself. An imported module that does not parse only breaks its import.
+    #[must_use]
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ResolverError::WrappedParserError(err) => err.is_recoverable(),
+            _ => true,
+        }
+    }
+}
+
 /// An error that occurred during data validation against a schema.
 #[derive(Error, Debug, Diagnostic, Clone)]
 #[error("Validation Error")]
 pub enum ValidationError {
     /// A field's value did not match its declared type.
-    #[error("Type mismatch for field '{field_name}'. Expected type {expected_type} but got {found_type}.")]
+    #[error(
+        "Type mismatch for field '{path}'. Expected type {expected_type} but got {found_type}."
+    )]
     #[diagnostic(
         code(validation::type_mismatch),
         help(
@@ -239,6 +891,8 @@ pub enum ValidationError {
     )]
     TypeMismatch {
         field_name: String,
+        /// The full path to the field from the document root, e.g. `user1.profile.username`.
+        path: String,
         expected_type: String,
         found_type: String,
         #[source_code]
@@ -247,6 +901,38 @@ pub enum ValidationError {
         span: SourceSpan,
     },
 
+    /// A string validated as a `Color` is not a hex color.
+    #[error("Invalid color {value:?} for field '{path}'.")]
+    #[diagnostic(
+        code(validation::invalid_color),
+        help("Write colors as `#RGB` or `#RRGGBB` with hexadecimal digits, e.g. \"#1e90ff\".")
+    )]
+    InvalidColor {
+        /// The full path to the field from the document root, e.g. `theme.accent`.
+        path: String,
+        value: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Not a hex color")]
+        span: SourceSpan,
+    },
+
+    /// A value of the `Bytes` type is not valid hex or base64.
+    #[error("Invalid bytes at '{path}': {value:?} is neither `0x` hex nor base64.")]
+    #[diagnostic(
+        code(validation::invalid_bytes),
+        help("Write bytes as hex digits after `0x`, e.g. \"0xdeadbeef\", or as standard base64, e.g. \"aGk=\".")
+    )]
+    InvalidBytes {
+        /// The full path to the field from the document root, e.g. `tls.key`.
+        path: String,
+        value: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Not hex or base64")]
+        span: SourceSpan,
+    },
+
     /// A required field was missing from a struct.
     #[error("Missing required field '{field_name}' for struct '{struct_name}'.")]
     #[diagnostic(
@@ -277,14 +963,36 @@ pub enum ValidationError {
         span: SourceSpan,
     },
 
-    /// A type name was used that has not been defined or imported.
-    #[error("Undefined type '{type_name}'.")]
+    /// An object did not give exactly one of the fields of a struct's `one_of(...)` group.
+    #[error(
+        "Expected exactly one of the fields {} of struct '{struct_name}', found {}.",
+        .fields.join(", "),
+        .given.len()
+    )]
     #[diagnostic(
-        code(validation::undefined_type),
-        help("Ensure the type is in scope or imported correctly.")
+        code(validation::mutual_exclusion),
+        help("Give one of the listed fields and remove the others.")
     )]
+    MutualExclusion {
+        /// The fields of the group.
+        fields: Vec<String>,
+        /// The fields of the group the object gives.
+        given: Vec<String>,
+        struct_name: String,
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("Validated against '{struct_name}' here")]
+        span: SourceSpan,
+    },
+
+    /// A type name was used that has not been defined or imported.
+    #[error("Undefined type '{type_name}'.")]
+    #[diagnostic(code(validation::undefined_type))]
     UndefinedType {
         type_name: String,
+        /// The help text, including a "did you mean" suggestion when the resolver runs in strict mode.
+        #[help]
+        help: String,
         #[source_code]
         src: Arc<NamedSource<String>>,
         #[label("Undefined type used here")]
@@ -319,6 +1027,42 @@ pub enum ValidationError {
         #[label("Complex collection type here")]
         span: SourceSpan,
     },
+
+    /// A number is infinite or not a number, e.g. the literal `1e400`, which overflows `f64`.
+    #[error("Number is not finite.")]
+    #[diagnostic(
+        code(validation::non_finite_number),
+        help("JSON and YAML cannot represent infinite numbers or NaN. Use a smaller number.")
+    )]
+    NonFiniteNumber {
+        #[source_code]
+        src: Arc<NamedSource<String>>,
+        #[label("This number is not finite")]
+        span: SourceSpan,
+    },
+}
+
+impl ValidationError {
+    /// Returns the stable diagnostic code of this error, e.g. `"validation::missing_field"`.
+    ///
+    /// Unlike [`Diagnostic::code`], the code is returned as a `&'static str`.
+    #[must_use]
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ValidationError::TypeMismatch { .. } => "validation::type_mismatch",
+            ValidationError::InvalidColor { .. } => "validation::invalid_color",
+            ValidationError::InvalidBytes { .. } => "validation::invalid_bytes",
+            ValidationError::MissingField { .. } => "validation::missing_field",
+            ValidationError::UnexpectedField { .. } => "validation::unexpected_field",
+            ValidationError::MutualExclusion { .. } => "validation::mutual_exclusion",
+            ValidationError::UndefinedType { .. } => "validation::undefined_type",
+            ValidationError::UndefinedEnumVariant { .. } => "validation::undefined_enum_variant",
+            ValidationError::UnimplementedCollectionValidation { .. } => {
+                "validation::unimplemented_collection_validation"
+            }
+            ValidationError::NonFiniteNumber { .. } => "validation::non_finite_number",
+        }
+    }
 }
 
 impl From<MonError> for ResolverError {
@@ -329,3 +1073,56 @@ impl From<MonError> for ResolverError {
         }
     }
 }
+
+/// An error that occurred while deserializing a resolved [`MonValue`](crate::ast::MonValue)
+/// into a typed Rust value through [`serde`].
+#[derive(Error, Debug, Diagnostic, Clone, PartialEq)]
+pub enum DeserializeError {
+    /// A message reported by the `Deserialize` implementation of the target type.
+    #[error("{0}")]
+    #[diagnostic(code(deserialize::custom))]
+    Custom(String),
+
+    /// A value that should have been removed by the resolver was found, such as an alias or a spread.
+    #[error("Cannot deserialize an unresolved {kind}.")]
+    #[diagnostic(
+        code(deserialize::unresolved_value),
+        help("Only fully resolved documents can be deserialized. Run the resolver first.")
+    )]
+    UnresolvedValue {
+        kind: String,
+        #[label("Unresolved value here")]
+        span: SourceSpan,
+    },
+}
+
+/// An error that occurred while editing a resolved document by path,
+/// e.g. with [`AnalysisResult::set`](crate::api::AnalysisResult::set).
+#[derive(Error, Debug, Diagnostic, Clone, PartialEq)]
+pub enum PathError {
+    /// The path was empty or contained an empty segment, e.g. `a..b`.
+    #[error("Invalid path '{path}'.")]
+    #[diagnostic(
+        code(path::invalid),
+        help("Use dot-separated keys, e.g. `server.port`.")
+    )]
+    InvalidPath { path: String },
+
+    /// The path runs through a value that is not an object.
+    #[error("Cannot set '{path}' because '{segment}' is not an object.")]
+    #[diagnostic(code(path::not_an_object))]
+    NotAnObject { path: String, segment: String },
+}
+
+/// An error that occurred while converting a `serde_json::Value` into a
+/// [`Value`](crate::serialization::Value).
+#[derive(Error, Debug, Diagnostic, Clone, PartialEq)]
+pub enum ValueConversionError {
+    /// A JSON integer that an `f64` cannot hold exactly, e.g. `9007199254740993`, which is 2^53 + 1.
+    #[error("The number {number} cannot be represented exactly.")]
+    #[diagnostic(
+        code(conversion::inexact_number),
+        help("MON numbers are 64-bit floats, which hold every integer up to 2^53, but only some larger ones, exactly. Store such integers as strings.")
+    )]
+    InexactNumber { number: String },
+}
@@ -55,6 +55,12 @@
 //! assert!(matches!(lexer.next_token().ttype, TokenType::Comment(_)));
 //! assert_eq!(lexer.next_token().ttype, TokenType::Eof);
 //! ```
+use crate::ast::QuantityKind;
+use crate::error::LexerError;
+use crate::utils::{get_line_and_column, source_span};
+use miette::NamedSource;
+use std::sync::Arc;
+
 /// Represents the different kinds of tokens that the lexer can produce.
 /// Each token is a meaningful unit of the MON language syntax.
 #[derive(Debug, PartialEq, Clone)]
@@ -79,6 +85,9 @@ pub enum TokenType {
     String(String),
     /// A number literal, which can be an integer or a floating-point value.
     Number(f64),
+    /// A number immediately followed by a unit suffix, e.g. `30s` or `10MB`.
+    /// See [`QuantityKind`] for the known units.
+    Quantity(f64, String),
 
     // == Keywords ==
     /// The boolean `true` value, can be written as `true` or `on`.
@@ -109,6 +118,8 @@ pub enum TokenType {
     RParen,
     /// Comma: `,`
     Comma,
+    /// Semicolon: `;` (used for repeated collection types, e.g., `[String; 3]`)
+    Semicolon,
     /// Colon: `:`
     Colon,
     /// Double Colon: `::` (used for type annotations)
@@ -117,6 +128,14 @@ pub enum TokenType {
     Dot,
     /// Equals: `=` (used for using structs)
     Equals,
+    /// Double Equals: `==` (used in member guards, e.g., `[@if(ENV == "prod")]`)
+    DoubleEquals,
+    /// Not Equals: `!=` (used in member guards)
+    NotEquals,
+    /// At Sign: `@` (used to start a member guard, e.g., `[@if(DEBUG)]`)
+    At,
+    /// Question Mark: `?` (used to mark optional struct fields, e.g., `nickname(String)?`)
+    Question,
     /// Hash: `#` (used as a prefix for type definitions, e.g., `#struct`)
     Hash,
     /// Dollar Sign: `$` (used for accessing enum variants)
@@ -127,6 +146,8 @@ pub enum TokenType {
     Asterisk,
     /// Spread: `...` (used to spread an anchor into an object or array)
     Spread,
+    /// Document Separator: `---` on a line of its own (used to split a file into multiple documents)
+    DocumentSeparator,
 }
 
 /// Represents a single lexical token, containing its type and position in the source text.
@@ -193,21 +214,103 @@ impl Token {
 /// assert_eq!(lexer.next_token().ttype, TokenType::LBrace);
 /// assert_eq!(lexer.next_token().ttype, TokenType::Whitespace);
 /// ```
+///
+/// A `Lexer` can be cloned to look ahead speculatively: the clone continues from the same
+/// position, and advancing it leaves the original untouched.
+#[derive(Clone)]
 pub struct Lexer<'a> {
+    input: &'a str,
     chars: std::iter::Peekable<std::str::Chars<'a>>,
     position: usize,
+    options: LexerOptions,
+    name: String,
+}
+
+/// Options that change how a [`Lexer`] tokenizes its input.
+///
+/// # Example
+///
+/// ```rust
+/// use mon_core::lexer::{Lexer, LexerOptions, TokenType};
+///
+/// let options = LexerOptions::default().with_yes_no();
+/// let mut lexer = Lexer::new_with_options("yes", options);
+/// assert_eq!(lexer.next_token().ttype, TokenType::True);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexerOptions {
+    /// Words, besides `true` and `false`, that are read as boolean literals.
+    ///
+    /// Defaults to `on` (`true`) and `off` (`false`).
+    pub boolean_aliases: Vec<(String, bool)>,
+}
+
+impl Default for LexerOptions {
+    fn default() -> Self {
+        Self {
+            boolean_aliases: vec![("on".to_string(), true), ("off".to_string(), false)],
+        }
+    }
+}
+
+impl LexerOptions {
+    /// Adds `word` as an alias for the boolean `value`.
+    #[must_use]
+    pub fn with_boolean_alias(mut self, word: impl Into<String>, value: bool) -> Self {
+        self.boolean_aliases.push((word.into(), value));
+        self
+    }
+
+    /// Adds `yes` and `no` as boolean aliases.
+    #[must_use]
+    pub fn with_yes_no(self) -> Self {
+        self.with_boolean_alias("yes", true)
+            .with_boolean_alias("no", false)
+    }
+
+    /// Adds `enabled` and `disabled` as boolean aliases.
+    #[must_use]
+    pub fn with_enabled_disabled(self) -> Self {
+        self.with_boolean_alias("enabled", true)
+            .with_boolean_alias("disabled", false)
+    }
 }
 
 impl<'a> Lexer<'a> {
     /// Creates a new `Lexer` for the given input string.
+    ///
+    /// A leading UTF-8 byte order mark (`U+FEFF`) is skipped. Token positions still count it,
+    /// so they remain valid byte offsets into `input`.
     #[must_use]
     pub fn new(input: &'a str) -> Self {
+        Self::new_with_options(input, LexerOptions::default())
+    }
+
+    /// Creates a new `Lexer` for the given input string, using the given [`LexerOptions`].
+    #[must_use]
+    pub fn new_with_options(input: &'a str, options: LexerOptions) -> Self {
+        let bom_len = if input.starts_with('\u{FEFF}') {
+            '\u{FEFF}'.len_utf8()
+        } else {
+            0
+        };
         Self {
-            chars: input.chars().peekable(),
-            position: 0,
+            input,
+            chars: input[bom_len..].chars().peekable(),
+            position: bom_len,
+            options,
+            name: "source.mon".to_string(),
         }
     }
 
+    /// Names the input in the errors of [`Lexer::lex_checked`], which is "source.mon" by
+    /// default.
+    #[must_use]
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
     /// Consumes the `Lexer` and returns a `Vec<Token>` containing all tokens from the source.
     ///
     /// This method will tokenize the entire input string up to and including the final [`TokenType::Eof`] token.
@@ -224,6 +327,42 @@ impl<'a> Lexer<'a> {
         tokens
     }
 
+    /// Tokenizes the entire input like [`Lexer::lex`], but fails at the first token that is not
+    /// valid MON, e.g. an unclosed string, instead of returning it as [`TokenType::Unknown`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LexerError::InvalidToken`] with the span of the first invalid token.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mon_core::lexer::Lexer;
+    ///
+    /// assert!(Lexer::new("{ a: 1 }").lex_checked().is_ok());
+    /// let err = Lexer::new(r#"{ a: "open }"#).lex_checked().unwrap_err();
+    /// assert_eq!(err.error_code(), "lexer::invalid_token");
+    /// ```
+    pub fn lex_checked(&mut self) -> Result<Vec<Token>, LexerError> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next_token();
+            match token.ttype {
+                TokenType::Unknown => {
+                    return Err(LexerError::InvalidToken {
+                        src: Arc::new(NamedSource::new(&self.name, self.input.to_string())),
+                        span: source_span(token.pos_start, token.pos_end),
+                    })
+                }
+                TokenType::Eof => {
+                    tokens.push(token);
+                    return Ok(tokens);
+                }
+                _ => tokens.push(token),
+            }
+        }
+    }
+
     /// Scans and returns the next [`Token`] from the input stream.
     ///
     /// This is the core tokenizing function. When the end of the input is reached,
@@ -240,11 +379,29 @@ impl<'a> Lexer<'a> {
                 '(' => TokenType::LParen,
                 ')' => TokenType::RParen,
                 ',' => TokenType::Comma,
+                ';' => TokenType::Semicolon,
                 '#' => TokenType::Hash,
                 '$' => TokenType::Dollar,
                 '&' => TokenType::Ampersand,
                 '*' => TokenType::Asterisk,
-                '=' => TokenType::Equals,
+                '=' => {
+                    if self.peek() == Some(&'=') {
+                        self.advance();
+                        TokenType::DoubleEquals
+                    } else {
+                        TokenType::Equals
+                    }
+                }
+                '!' => {
+                    if self.peek() == Some(&'=') {
+                        self.advance();
+                        TokenType::NotEquals
+                    } else {
+                        TokenType::Unknown
+                    }
+                }
+                '@' => TokenType::At,
+                '?' => TokenType::Question,
 
                 ':' => {
                     if self.peek() == Some(&':') {
@@ -274,6 +431,11 @@ impl<'a> Lexer<'a> {
                         TokenType::Unknown
                     }
                 }
+                '-' if self.is_document_separator(start_pos) => {
+                    self.advance();
+                    self.advance();
+                    TokenType::DocumentSeparator
+                }
                 '"' => self.read_string(),
                 c if c.is_whitespace() => self.read_whitespace(),
                 c if c.is_ascii_alphabetic() || c == '_' => self.read_identifier(c),
@@ -292,6 +454,12 @@ impl<'a> Lexer<'a> {
         Token::new(ttype, start_pos, self.position)
     }
 
+    /// Lexes `source` and formats its tokens with [`tokens_to_pretty_string`].
+    #[must_use]
+    pub fn debug_dump(source: &str) -> String {
+        tokens_to_pretty_string(&Lexer::new(source).lex(), source)
+    }
+
     fn advance(&mut self) -> Option<char> {
         let char = self.chars.next();
         if let Some(c) = char {
@@ -304,6 +472,18 @@ impl<'a> Lexer<'a> {
         self.chars.peek()
     }
 
+    /// Checks whether the `-` just consumed at `start_pos` begins a line that is exactly `---`.
+    fn is_document_separator(&self, start_pos: usize) -> bool {
+        let before = self.input[..start_pos].trim_start_matches('\u{FEFF}');
+        if !(before.is_empty() || before.ends_with('\n')) {
+            return false;
+        }
+        let mut rest = self.chars.clone();
+        rest.next() == Some('-')
+            && rest.next() == Some('-')
+            && matches!(rest.next(), None | Some('\n' | '\r'))
+    }
+
     fn read_whitespace(&mut self) -> TokenType {
         while let Some(c) = self.peek() {
             if c.is_whitespace() {
@@ -317,25 +497,30 @@ impl<'a> Lexer<'a> {
 
     fn read_comment(&mut self) -> TokenType {
         self.advance(); // Consume the second '/'
-        let mut comment_text = String::new();
+        let start = self.position;
         while let Some(c) = self.peek() {
-            if *c == '\n' {
+            // Stop before `\r` too, so a CRLF line ending is left to the whitespace token
+            if *c == '\n' || *c == '\r' {
                 break;
             }
-            comment_text.push(self.advance().unwrap());
+            self.advance();
         }
-        TokenType::Comment(comment_text.trim().to_string())
+        TokenType::Comment(self.input[start..self.position].trim().to_string())
     }
 
     fn read_string(&mut self) -> TokenType {
         let mut value = String::new();
+        // Characters are copied from the input in runs, up to the next escape or the end
+        let mut run_start = self.position;
         loop {
             match self.peek() {
                 Some('"') => {
+                    value.push_str(&self.input[run_start..self.position]);
                     self.advance(); // Consume the closing quote
                     return TokenType::String(value);
                 }
                 Some('\\') => {
+                    value.push_str(&self.input[run_start..self.position]);
                     self.advance(); // Consume the backslash
                     match self.advance() {
                         Some('"') => value.push('"'),
@@ -343,15 +528,22 @@ impl<'a> Lexer<'a> {
                         Some('n') => value.push('\n'),
                         Some('r') => value.push('\r'),
                         Some('t') => value.push('\t'),
+                        Some('/') => value.push('/'),
+                        Some('b') => value.push('\u{8}'),
+                        Some('f') => value.push('\u{c}'),
+                        Some('u') => match self.read_unicode_escape() {
+                            Some(c) => value.push(c),
+                            None => return TokenType::Unknown, // Malformed unicode escape
+                        },
                         Some(other) => {
                             value.push('\\');
                             value.push(other);
                         }
                         None => return TokenType::Unknown, // Unclosed escape sequence
                     }
+                    run_start = self.position;
                 }
-                Some(c) => {
-                    value.push(*c);
+                Some(_) => {
                     self.advance();
                 }
                 None => return TokenType::Unknown, // Unclosed string
@@ -359,26 +551,61 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn read_identifier(&mut self, first_char: char) -> TokenType {
-        let mut ident = String::new();
-        ident.push(first_char);
+    /// Reads the `XXXX` part of a JSON-style `\uXXXX` escape, combining UTF-16 surrogate pairs.
+    fn read_unicode_escape(&mut self) -> Option<char> {
+        let high = self.read_hex_code_unit()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+
+        // A high surrogate must be followed by an escaped low surrogate
+        if self.advance() != Some('\\') || self.advance() != Some('u') {
+            return None;
+        }
+        let low = self.read_hex_code_unit()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
 
+    fn read_hex_code_unit(&mut self) -> Option<u32> {
+        let mut code_unit = 0;
+        for _ in 0..4 {
+            code_unit = code_unit * 16 + self.advance()?.to_digit(16)?;
+        }
+        Some(code_unit)
+    }
+
+    fn read_identifier(&mut self, first_char: char) -> TokenType {
+        let start = self.position - first_char.len_utf8();
         while let Some(c) = self.peek() {
             if c.is_ascii_alphanumeric() || *c == '_' {
-                ident.push(self.advance().unwrap());
+                self.advance();
             } else {
                 break;
             }
         }
+        let ident = &self.input[start..self.position];
 
-        match ident.as_str() {
-            "true" | "on" => TokenType::True,
-            "false" | "off" => TokenType::False,
+        match ident {
+            "true" => TokenType::True,
+            "false" => TokenType::False,
             "null" => TokenType::Null,
             "import" => TokenType::Import,
             "from" => TokenType::From,
             "as" => TokenType::As,
-            _ => TokenType::Identifier(ident),
+            // Aliases cannot shadow the keywords above
+            _ => match self
+                .options
+                .boolean_aliases
+                .iter()
+                .find(|(word, _)| word == ident)
+            {
+                Some((_, true)) => TokenType::True,
+                Some((_, false)) => TokenType::False,
+                None => TokenType::Identifier(ident.to_string()),
+            },
         }
     }
 
@@ -408,23 +635,70 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        if let Ok(num) = number_str.parse::<f64>() {
-            TokenType::Number(num)
-        } else {
-            TokenType::Unknown
+        // A number cannot contain a second dot, nor a dot after its exponent. Rather than
+        // splitting `1.2.3` into `1.2`, `.` and `3`, the whole run is one invalid token.
+        if self.peek() == Some(&'.') {
+            while self.peek().is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                self.advance();
+            }
+            return TokenType::Unknown;
         }
+
+        // Numbers too large for an `f64`, like `1e999`, would silently become infinity.
+        let num = match number_str.parse::<f64>() {
+            Ok(num) if num.is_finite() => num,
+            _ => return TokenType::Unknown,
+        };
+
+        // A known unit directly after the number makes it a quantity, e.g. `30s`. Other
+        // words are left for the next token, as before.
+        let rest = &self.input[self.position..];
+        let suffix_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let suffix = &rest[..suffix_len];
+        if QuantityKind::of_unit(suffix).is_some() {
+            let unit = suffix.to_string();
+            for _ in 0..suffix_len {
+                self.advance();
+            }
+            return TokenType::Quantity(num, unit);
+        }
+
+        TokenType::Number(num)
     }
 }
 
-/// QOL function
-#[allow(dead_code)]
-pub(crate) fn tokens_to_pretty_string(tokens: &[Token]) -> String {
+/// Formats `tokens` lexed from `source` one per line, for debugging the lexer or learning
+/// how MON is tokenized.
+///
+/// Each line shows the 1-based line and column where the token starts, its byte range, its
+/// type, and the source text it covers.
+///
+/// # Example
+///
+/// ```rust
+/// use mon_core::lexer::{tokens_to_pretty_string, Lexer};
+///
+/// let source = "{ a: 1 }";
+/// let tokens = Lexer::new(source).lex();
+/// let dump = tokens_to_pretty_string(&tokens, source);
+/// assert_eq!(dump.lines().nth(2), Some(r#"1:3 2..3 Identifier("a") "a""#));
+/// ```
+#[must_use]
+pub fn tokens_to_pretty_string(tokens: &[Token], source: &str) -> String {
     let mut buff: Vec<String> = Vec::with_capacity(tokens.len());
 
     for token in tokens {
+        let (line, column) = get_line_and_column(source, token.pos_start);
         buff.push(format!(
-            "{:?}, {}, {}",
-            token.ttype, token.pos_start, token.pos_end,
+            "{line}:{column} {}..{} {:?} {:?}",
+            token.pos_start,
+            token.pos_end,
+            token.ttype,
+            source
+                .get(token.pos_start..token.pos_end)
+                .unwrap_or_default(),
         ));
     }
 
@@ -480,8 +754,17 @@ mod tests {
 
     #[test]
     fn test_multi_char_operators() {
-        let input = ":: ...";
-        let expected = vec![TokenType::DoubleColon, TokenType::Spread, TokenType::Eof];
+        let input = ":: ... == != = @ ?";
+        let expected = vec![
+            TokenType::DoubleColon,
+            TokenType::Spread,
+            TokenType::DoubleEquals,
+            TokenType::NotEquals,
+            TokenType::Equals,
+            TokenType::At,
+            TokenType::Question,
+            TokenType::Eof,
+        ];
         assert_tokens(input, &expected);
     }
 
@@ -618,6 +901,31 @@ mod tests {
         assert_tokens(input, &expected);
     }
 
+    #[test]
+    fn test_lex_checked() {
+        let tokens = Lexer::new("{ a: 1 }").lex_checked().unwrap();
+        assert_eq!(tokens.len(), 9);
+        assert_eq!(tokens.last().unwrap().ttype, TokenType::Eof);
+
+        // The first invalid token is reported with its span
+        for (input, invalid) in [("{ a: ~, b: ~ }", "~"), ("{ a: 1e999 }", "1e999")] {
+            let err = Lexer::new(input).lex_checked().unwrap_err();
+            let LexerError::InvalidToken { span, .. } = &err;
+            assert_eq!(span.offset(), input.find(invalid).unwrap(), "{input}");
+            assert_eq!(span.len(), invalid.len(), "{input}");
+            assert_eq!(err.error_code(), "lexer::invalid_token");
+            let err: crate::error::MonError = err.into();
+            assert_eq!(err.code(), "lexer::invalid_token");
+        }
+
+        let err = Lexer::new("~")
+            .with_name("config.mon".to_string())
+            .lex_checked()
+            .unwrap_err();
+        let LexerError::InvalidToken { src, .. } = &err;
+        assert_eq!(src.name(), "config.mon");
+    }
+
     #[test]
     fn test_unclosed_string() {
         let input = r#"{ key: "unclosed }"#;
@@ -647,6 +955,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_clone_forks_lexer_state() {
+        let mut lexer =
+            Lexer::new_with_options("{ a: yes }", LexerOptions::default().with_yes_no());
+        lexer.next_token();
+        lexer.next_token();
+
+        let mut fork = lexer.clone();
+        let ahead: Vec<Token> = fork.lex();
+        assert_eq!(ahead[0].ttype, TokenType::Identifier("a".to_string()));
+        assert_eq!(ahead[3].ttype, TokenType::True);
+
+        // The original continues where the fork started, with the same positions
+        let rest = lexer.lex();
+        assert_eq!(rest.len(), ahead.len());
+        for (original, forked) in rest.iter().zip(&ahead) {
+            assert_eq!(original.ttype, forked.ttype);
+            assert_eq!(
+                (original.pos_start, original.pos_end),
+                (forked.pos_start, forked.pos_end)
+            );
+        }
+        assert_eq!(rest[0].pos_start, 2);
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let dump = Lexer::debug_dump("{\n  k: \"v\" // c\n}");
+        assert_eq!(
+            dump,
+            [
+                r#"1:1 0..1 LBrace "{""#,
+                r#"1:2 1..4 Whitespace "\n  ""#,
+                r#"2:3 4..5 Identifier("k") "k""#,
+                r#"2:4 5..6 Colon ":""#,
+                r#"2:5 6..7 Whitespace " ""#,
+                r#"2:6 7..10 String("v") "\"v\"""#,
+                r#"2:9 10..11 Whitespace " ""#,
+                r#"2:10 11..15 Comment("c") "// c""#,
+                r#"2:14 15..16 Whitespace "\n""#,
+                r#"3:1 16..17 RBrace "}""#,
+                r#"3:2 17..17 Eof """#,
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_long_tokens() {
+        let long = "ä".repeat(100_000);
+        let input = format!("\"{long}\\n{long}\" {} // {long}", "x".repeat(100_000));
+        let tokens = Lexer::new(&input).lex();
+        assert_eq!(
+            tokens[0].ttype,
+            TokenType::String(format!("{long}\n{long}"))
+        );
+        assert_eq!(tokens[2].ttype, TokenType::Identifier("x".repeat(100_000)));
+        assert_eq!(tokens[4].ttype, TokenType::Comment(long));
+    }
+
     #[test]
     fn test_invalid_escape_at_eof() {
         let input = r#""test\"#;
@@ -668,6 +1036,91 @@ mod tests {
         assert!(matches!(tok2.ttype, TokenType::Number(n) if (n - 4.5e-3).abs() < 1e-9));
     }
 
+    #[test]
+    fn test_number_with_second_dot() {
+        // The whole run is a single invalid token instead of `1.2`, `.` and `3`.
+        let tokens = Lexer::new("1.2.3 4").lex();
+        assert_eq!(tokens[0].ttype, TokenType::Unknown);
+        assert_eq!((tokens[0].pos_start, tokens[0].pos_end), (0, 5));
+        assert_eq!(tokens[2].ttype, TokenType::Number(4.0));
+
+        assert_tokens("1e5.2", &[TokenType::Unknown, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_quantities() {
+        assert_tokens(
+            "30s 1.5h 250ms 10MB 4KiB",
+            &[
+                TokenType::Quantity(30.0, "s".to_string()),
+                TokenType::Quantity(1.5, "h".to_string()),
+                TokenType::Quantity(250.0, "ms".to_string()),
+                TokenType::Quantity(10.0, "MB".to_string()),
+                TokenType::Quantity(4.0, "KiB".to_string()),
+                TokenType::Eof,
+            ],
+        );
+        // Unknown suffixes are not part of the number, and the unit must follow directly.
+        assert_tokens(
+            "5px 5 s",
+            &[
+                TokenType::Number(5.0),
+                TokenType::Identifier("px".to_string()),
+                TokenType::Number(5.0),
+                TokenType::Identifier("s".to_string()),
+                TokenType::Eof,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        assert_tokens("1e999", &[TokenType::Unknown, TokenType::Eof]);
+        assert_tokens("-1e999", &[TokenType::Unknown, TokenType::Eof]);
+        // Underflow is not an error, the number is just rounded to zero.
+        assert_tokens("1e-999", &[TokenType::Number(0.0), TokenType::Eof]);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_number_like_input_tokenizes_consistently(input in "[0-9.eE+-]{0,24}") {
+            let tokens = Lexer::new(&input).lex();
+
+            // The tokens cover the input without gaps and end with `Eof`.
+            let mut position = 0;
+            for token in &tokens {
+                proptest::prop_assert_eq!(token.pos_start, position);
+                position = token.pos_end;
+                if let TokenType::Number(n) = token.ttype {
+                    proptest::prop_assert!(n.is_finite());
+                }
+            }
+            proptest::prop_assert_eq!(position, input.len());
+            proptest::prop_assert_eq!(&tokens.last().unwrap().ttype, &TokenType::Eof);
+
+            // Lexing is deterministic.
+            let spans = |tokens: &[Token]| {
+                tokens
+                    .iter()
+                    .map(|t| (t.ttype.clone(), t.pos_start, t.pos_end))
+                    .collect::<Vec<_>>()
+            };
+            proptest::prop_assert_eq!(spans(&Lexer::new(&input).lex()), spans(&tokens));
+        }
+
+        #[test]
+        fn prop_valid_numbers_are_one_token(
+            input in "-?[0-9]{1,10}(\\.[0-9]{1,10})?([eE][+-]?[0-9]{1,2})?"
+        ) {
+            let tokens = Lexer::new(&input).lex();
+            proptest::prop_assert_eq!(tokens.len(), 2);
+            proptest::prop_assert_eq!(
+                &tokens[0].ttype,
+                &TokenType::Number(input.parse::<f64>().unwrap())
+            );
+        }
+    }
+
     #[test]
     fn test_negative_numbers() {
         let input = "-42 -3.2";
@@ -692,11 +1145,11 @@ mod tests {
 
     #[test]
     fn test_unknown_character() {
-        let input = "{ @invalid }";
+        let input = "{ ~invalid }";
         let mut lexer = Lexer::new(input);
         let tokens: Vec<TokenType> = lexer.lex().into_iter().map(|t| t.ttype).collect();
 
-        // Should have Unknown token for @
+        // Should have Unknown token for ~
         assert!(tokens.iter().any(|t| matches!(t, TokenType::Unknown)));
     }
 
@@ -735,6 +1188,34 @@ mod tests {
         assert!(matches!(token.ttype, TokenType::String(_)));
     }
 
+    #[test]
+    fn test_json_escapes() {
+        let input = r#""a\/b\bc\fd""#;
+        let mut lexer = Lexer::new(input);
+        assert_eq!(
+            lexer.next_token().ttype,
+            TokenType::String("a/b\u{8}c\u{c}d".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unicode_escapes() {
+        let input = r#""\u00e9 \uD83D\uDE00""#;
+        let mut lexer = Lexer::new(input);
+        assert_eq!(
+            lexer.next_token().ttype,
+            TokenType::String("\u{e9} \u{1F600}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_malformed_unicode_escape() {
+        for input in [r#""\u12""#, r#""\uZZZZ""#, r#""\uD83D""#] {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.next_token().ttype, TokenType::Unknown, "{input}");
+        }
+    }
+
     #[test]
     fn test_zero_number() {
         assert_tokens("0", &[TokenType::Number(0.0), TokenType::Eof]);
@@ -797,6 +1278,49 @@ mod tests {
         assert_tokens(input, &expected);
     }
 
+    #[test]
+    fn test_boolean_aliases_with_options() {
+        let input = "yes no enabled disabled on off";
+        let options = LexerOptions::default()
+            .with_yes_no()
+            .with_enabled_disabled();
+        let tokens: Vec<TokenType> = Lexer::new_with_options(input, options)
+            .lex()
+            .into_iter()
+            .map(|t| t.ttype)
+            .filter(|t| !matches!(t, TokenType::Whitespace))
+            .collect();
+        let expected = vec![
+            TokenType::True,
+            TokenType::False,
+            TokenType::True,
+            TokenType::False,
+            TokenType::True,
+            TokenType::False,
+            TokenType::Eof,
+        ];
+        assert_eq!(tokens, expected);
+
+        // Keywords cannot be turned into booleans
+        let options = LexerOptions::default().with_boolean_alias("null", true);
+        assert_eq!(
+            Lexer::new_with_options("null", options).next_token().ttype,
+            TokenType::Null
+        );
+    }
+
+    #[test]
+    fn test_boolean_aliases_default_set() {
+        // `yes`/`no` are plain identifiers unless opted in
+        let input = "yes no";
+        let expected = vec![
+            TokenType::Identifier("yes".to_string()),
+            TokenType::Identifier("no".to_string()),
+            TokenType::Eof,
+        ];
+        assert_tokens(input, &expected);
+    }
+
     #[test]
     fn test_identifiers_with_underscores() {
         let input = "my_var _private __dunder";
@@ -858,6 +1382,69 @@ mod tests {
         assert_tokens(input, &expected);
     }
 
+    #[test]
+    fn test_document_separator() {
+        let input = "{}\n---\n{}\n---";
+        let expected = vec![
+            TokenType::LBrace,
+            TokenType::RBrace,
+            TokenType::DocumentSeparator,
+            TokenType::LBrace,
+            TokenType::RBrace,
+            TokenType::DocumentSeparator,
+            TokenType::Eof,
+        ];
+        assert_tokens(input, &expected);
+    }
+
+    #[test]
+    fn test_document_separator_must_be_whole_line() {
+        let input = "{} ---\n----\n--- x";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        assert!(!tokens
+            .iter()
+            .any(|t| t.ttype == TokenType::DocumentSeparator));
+    }
+
+    #[test]
+    fn test_leading_bom_is_skipped() {
+        let input = "\u{FEFF}{ a: 1 }";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        assert_eq!(tokens[0].ttype, TokenType::LBrace);
+        // Positions stay byte offsets into the original input.
+        assert_eq!(tokens[0].pos_start, 3);
+        assert_eq!(&input[tokens[2].pos_start..tokens[2].pos_end], "a");
+
+        assert_tokens(
+            "\u{FEFF}---\n{}",
+            &[
+                TokenType::DocumentSeparator,
+                TokenType::LBrace,
+                TokenType::RBrace,
+                TokenType::Eof,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let input = "{\r\n  // note\r\n  a: 1\r\n}\r\n---\r\n{}";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        let comment = tokens
+            .iter()
+            .find(|t| matches!(t.ttype, TokenType::Comment(_)))
+            .unwrap();
+        assert_eq!(comment.ttype, TokenType::Comment("note".to_string()));
+        assert_eq!(&input[comment.pos_start..comment.pos_end], "// note");
+        assert!(!tokens.iter().any(|t| t.ttype == TokenType::Unknown));
+        assert!(tokens
+            .iter()
+            .any(|t| t.ttype == TokenType::DocumentSeparator));
+    }
+
     #[test]
     fn test_empty_string() {
         let input = r#""""#;
@@ -1,4 +1,5 @@
-#![doc = include_str!("../README.md")]
+// The README's examples use `analyze`, which needs the `resolver` feature
+#![cfg_attr(feature = "resolver", doc = include_str!("../README.md"))]
 
 //! # Crate Overview
 //!
@@ -37,8 +38,10 @@
 //! This example shows how to use the high-level [`analyze`] function to process a MON string from start to finish.
 //!
 //! ```rust
+//! # #[cfg(feature = "resolver")]
 //! use mon_core::api::analyze;
 //!
+//! # #[cfg(feature = "resolver")]
 //! # fn main() -> Result<(), mon_core::error::MonError> {
 //! let source = r#"
 //! {
@@ -58,25 +61,52 @@
 //! assert!(json_output.contains("\"port\": 8080.0"));
 //! # Ok(())
 //! # }
+//! # #[cfg(not(feature = "resolver"))]
+//! # fn main() {}
 //! ```
 //!
 //! For more granular control over each stage of the process, you can use the components from the [`lexer`], [`parser`],
-//! and [`resolver`] modules directly.
+//! and [`resolver`] modules directly. `use mon_core::prelude::*;` imports the types you will need most, such as
+//! [`MonValue`](ast::MonValue), [`MonError`](error::MonError) and [`Value`](serialization::Value), see [`prelude`].
+//!
+//! ## Cargo Features
+//!
+//! - **`resolver`** (default): Builds the [`resolver`] and [`api`] modules. The resolver reads imported
+//!   modules from the filesystem. Disable default features to use only the filesystem-free core
+//!   ([`lexer`], [`parser`], [`ast`], [`serialization`], [`de`]), e.g. when targeting WASM.
+//! - **`lsp`**: Enables language server helpers such as "go to definition" and semantic tokens.
+//!
+// Without the `resolver` feature, the links to it point to the section above
+#![cfg_attr(
+    not(feature = "resolver"),
+    doc = "[`resolver`]: crate#cargo-features\n[`api`]: crate#cargo-features\n[`analyze`]: crate#cargo-features"
+)]
 
 /// Provides the public API for interacting with the MON core library,
 /// including parsing, analysis, and serialization functions.
+#[cfg(feature = "resolver")]
 pub mod api;
 pub mod ast;
+pub mod de;
 pub mod error;
 
 pub mod lexer;
 #[cfg(feature = "lsp")]
 pub mod lsp;
-#[cfg(feature = "lsp")]
 pub mod utils;
 
 pub mod parser;
+pub mod prelude;
+#[cfg(feature = "resolver")]
 pub mod resolver;
 pub mod serialization;
 
-pub use api::{analyze, AnalysisResult};
+#[cfg(feature = "resolver")]
+pub use api::{
+    analyze, analyze_all, analyze_all_with_options, analyze_fenced, analyze_in, analyze_layers,
+    analyze_layers_with_options, analyze_multi, analyze_multi_with_options, analyze_streaming,
+    analyze_streaming_with_options, analyze_typed, analyze_typed_with_options,
+    analyze_with_metrics, analyze_with_metrics_and_options, analyze_with_options, is_valid,
+    validate_against, validate_against_with_options, validate_only, validate_only_with_options,
+    AnalysisResult, AnalyzeMetrics,
+};
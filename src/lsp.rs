@@ -2,12 +2,14 @@ use crate::ast::{Member, MonValue, MonValueKind, SymbolTable, TypeDef, TypeSpec}
 use miette::SourceSpan;
 use std::collections::HashMap;
 
+/// An AST node found by a position query such as [`find_node_at`].
 #[derive(Debug, Clone, Copy)]
 pub enum FoundNode<'a> {
     Value(&'a MonValue),
     TypeSpec(&'a TypeSpec),
 }
 
+/// The node found by [`find_symbol_at`], with the validation of the pair it belongs to.
 #[derive(Debug, Clone, Copy)]
 pub struct SymbolInfo<'a> {
     pub node: FoundNode<'a>,
@@ -31,6 +33,38 @@ pub enum SemanticTokenType {
     Property, // For object keys
 }
 
+#[cfg(feature = "lsp")]
+impl SemanticTokenType {
+    /// The token type names to announce as the legend of the LSP server's semantic tokens
+    /// provider, in the order of [`SemanticTokenType::legend_index`].
+    ///
+    /// `boolean` and `null` are not among the LSP's predefined types; clients that do not know
+    /// them leave such tokens uncolored.
+    pub const LEGEND: [&'static str; 12] = [
+        "struct", "enum", "variable", "label", "type", "keyword", "comment", "string", "number",
+        "boolean", "null", "property",
+    ];
+
+    /// Returns the index of this token type in [`SemanticTokenType::LEGEND`].
+    #[must_use]
+    pub fn legend_index(&self) -> u32 {
+        match self {
+            SemanticTokenType::Struct => 0,
+            SemanticTokenType::Enum => 1,
+            SemanticTokenType::Alias => 2,
+            SemanticTokenType::Anchor => 3,
+            SemanticTokenType::Type => 4,
+            SemanticTokenType::Keyword => 5,
+            SemanticTokenType::Comment => 6,
+            SemanticTokenType::String => 7,
+            SemanticTokenType::Number => 8,
+            SemanticTokenType::Boolean => 9,
+            SemanticTokenType::Null => 10,
+            SemanticTokenType::Property => 11,
+        }
+    }
+}
+
 #[cfg(feature = "lsp")]
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Clone)]
@@ -39,6 +73,17 @@ pub struct SemanticToken {
     pub token_type: SemanticTokenType,
 }
 
+/// Finds the most specific value or type spec that contains the byte offset `position`.
+///
+/// Returns `None` if `position` lies outside of `value`.
+#[must_use]
+pub fn find_node_at(value: &MonValue, position: usize) -> Option<FoundNode<'_>> {
+    find_symbol_at(value, position).map(|symbol| symbol.node)
+}
+
+/// Finds the node at `position` like [`find_node_at`], along with the validation (`:: Type`)
+/// of the innermost pair whose value contains it.
+#[must_use]
 pub fn find_symbol_at(value: &'_ MonValue, position: usize) -> Option<SymbolInfo<'_>> {
     if position < value.pos_start || position >= value.pos_end {
         return None;
@@ -46,7 +91,7 @@ pub fn find_symbol_at(value: &'_ MonValue, position: usize) -> Option<SymbolInfo
 
     if let MonValueKind::Object(members) = &value.kind {
         for member in members {
-            if let Member::Pair(pair) = member {
+            if let Member::Pair(pair) = member.unguarded() {
                 if let Some(validation) = &pair.validation {
                     if let Some(found) = find_node_in_type_spec(validation, position) {
                         return Some(SymbolInfo {
@@ -85,12 +130,20 @@ fn find_node_in_type_spec(type_spec: &TypeSpec, position: usize) -> Option<Found
         return None;
     }
 
-    if let TypeSpec::Collection(children, _) = type_spec {
-        for child in children {
-            if let Some(found) = find_node_in_type_spec(child, position) {
+    match type_spec {
+        TypeSpec::Collection(children, _) => {
+            for child in children {
+                if let Some(found) = find_node_in_type_spec(child, position) {
+                    return Some(found);
+                }
+            }
+        }
+        TypeSpec::Spread(inner, _) | TypeSpec::Repeat(inner, _, _) => {
+            if let Some(found) = find_node_in_type_spec(inner, position) {
                 return Some(found);
             }
         }
+        TypeSpec::Simple(..) => {}
     }
 
     Some(FoundNode::TypeSpec(type_spec))
@@ -104,16 +157,24 @@ pub fn find_all_usages(root: &MonValue, name: &str) -> Vec<SourceSpan> {
 
 fn find_all_usages_recursive(value: &MonValue, name: &str, usages: &mut Vec<SourceSpan>) {
     match &value.kind {
-        MonValueKind::Alias(alias_name) if alias_name == name => {
+        MonValueKind::Alias(alias_name) | MonValueKind::ArraySpread(alias_name)
+            if alias_name == name =>
+        {
             usages.push(value.get_source_span());
         }
         MonValueKind::Object(members) => {
             for member in members {
-                if let Member::Pair(pair) = member {
-                    if let Some(validation) = &pair.validation {
-                        find_all_usages_in_type_spec(validation, name, usages);
+                match member.unguarded() {
+                    Member::Pair(pair) => {
+                        if let Some(validation) = &pair.validation {
+                            find_all_usages_in_type_spec(validation, name, usages);
+                        }
+                        find_all_usages_recursive(&pair.value, name, usages);
                     }
-                    find_all_usages_recursive(&pair.value, name, usages);
+                    Member::Spread(spread_name, span) if spread_name == name => {
+                        usages.push(*span);
+                    }
+                    _ => {}
                 }
             }
         }
@@ -137,7 +198,7 @@ fn find_all_usages_in_type_spec(type_spec: &TypeSpec, name: &str, usages: &mut V
                 find_all_usages_in_type_spec(child, name, usages);
             }
         }
-        TypeSpec::Spread(child, _) => {
+        TypeSpec::Spread(child, _) | TypeSpec::Repeat(child, _, _) => {
             find_all_usages_in_type_spec(child, name, usages);
         }
         _ => {}
@@ -157,6 +218,87 @@ pub fn generate_semantic_tokens(
     tokens
 }
 
+/// Encodes `tokens` found in `source` as the integer array of an LSP `SemanticTokens` result.
+///
+/// Every token becomes five integers: its line relative to the previous token, its start
+/// character (relative to the previous token's start when on the same line), its length, its
+/// [`SemanticTokenType::legend_index`] and an empty modifier set. Characters are counted in
+/// UTF-16 code units, the LSP's default position encoding.
+///
+/// The protocol does not allow tokens to overlap, but `tokens` nest, e.g. a pair's
+/// [`SemanticTokenType::Property`] token contains its value's token. The inner token wins: an
+/// enclosing token is cut off where the inner one starts. Tokens spanning several lines are
+/// cut off at the end of their first line.
+#[cfg(feature = "lsp")]
+#[must_use]
+pub fn encode_semantic_tokens(tokens: &[SemanticToken], source: &str) -> Vec<u32> {
+    // Outer tokens first, so that the tokens nested in them can cut them off
+    let mut sorted: Vec<&SemanticToken> = tokens.iter().collect();
+    sorted.sort_by_key(|t| (t.span.offset(), std::cmp::Reverse(t.span.len())));
+    let mut ranges: Vec<(usize, usize, u32)> = Vec::with_capacity(sorted.len());
+    for token in sorted {
+        let start = token.span.offset();
+        if let Some(last) = ranges.last_mut() {
+            if start < last.1 {
+                last.1 = start;
+            }
+            if last.0 == last.1 {
+                ranges.pop();
+            }
+        }
+        ranges.push((
+            start,
+            start + token.span.len(),
+            token.token_type.legend_index(),
+        ));
+    }
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let utf16_len = |from: usize, to: usize| {
+        source
+            .get(from..to)
+            .map(|text| u32::try_from(text.encode_utf16().count()).unwrap_or(u32::MAX))
+    };
+
+    let mut data = Vec::with_capacity(ranges.len() * 5);
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for (start, end, token_type) in ranges {
+        let line = line_starts.partition_point(|&line_start| line_start <= start) - 1;
+        let mut line_start = line_starts[line];
+        if line_start == 0 && source.starts_with('\u{FEFF}') {
+            line_start = '\u{FEFF}'.len_utf8();
+        }
+        let line_end = line_starts
+            .get(line + 1)
+            .map_or(source.len(), |next| next - 1);
+        let line_end = if source[..line_end].ends_with('\r') {
+            line_end - 1
+        } else {
+            line_end
+        };
+        let (Some(column), Some(length)) = (
+            utf16_len(line_start, start),
+            utf16_len(start, end.min(line_end)),
+        ) else {
+            continue;
+        };
+        if length == 0 {
+            continue;
+        }
+        let line = u32::try_from(line).unwrap_or(u32::MAX);
+        let delta_start = if line == previous_line {
+            column - previous_start
+        } else {
+            column
+        };
+        data.extend([line - previous_line, delta_start, length, token_type, 0]);
+        (previous_line, previous_start) = (line, column);
+    }
+    data
+}
+
 #[track_caller]
 // this is needed because pair.get_span tracks caller for better error messages.
 #[allow(dead_code)]
@@ -170,7 +312,7 @@ fn generate_semantic_tokens_recursive(
     match &value.kind {
         MonValueKind::Object(members) => {
             for member in members {
-                match member {
+                match member.unguarded() {
                     Member::Pair(pair) => {
                         tokens.push(SemanticToken {
                             span: pair.get_span(),
@@ -192,6 +334,7 @@ fn generate_semantic_tokens_recursive(
                             token_type: match type_def.def_type {
                                 TypeDef::Struct(_) => SemanticTokenType::Struct,
                                 TypeDef::Enum(_) => SemanticTokenType::Enum,
+                                TypeDef::Alias(_) => SemanticTokenType::Type,
                             },
                         });
                         // Recurse into the definition itself
@@ -216,6 +359,9 @@ fn generate_semantic_tokens_recursive(
                                     });
                                 }
                             }
+                            TypeDef::Alias(spec) => {
+                                generate_semantic_tokens_for_type_spec(spec, tokens);
+                            }
                         }
                     }
                     _ => {}
@@ -234,13 +380,13 @@ fn generate_semantic_tokens_recursive(
                 token_type: SemanticTokenType::Alias,
             });
         }
-        MonValueKind::String(_) => {
+        MonValueKind::String(_) | MonValueKind::Bytes(_) => {
             tokens.push(SemanticToken {
                 span: value.get_source_span(),
                 token_type: SemanticTokenType::String,
             });
         }
-        MonValueKind::Number(_) => {
+        MonValueKind::Number(_) | MonValueKind::Quantity { .. } => {
             tokens.push(SemanticToken {
                 span: value.get_source_span(),
                 token_type: SemanticTokenType::Number,
@@ -288,7 +434,7 @@ fn generate_semantic_tokens_for_type_spec(type_spec: &TypeSpec, tokens: &mut Vec
                 generate_semantic_tokens_for_type_spec(child, tokens);
             }
         }
-        TypeSpec::Spread(child, _) => {
+        TypeSpec::Spread(child, _) | TypeSpec::Repeat(child, _, _) => {
             generate_semantic_tokens_for_type_spec(child, tokens);
         }
     }
@@ -311,6 +457,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_find_symbol_at_normalized_built_document() {
+        use crate::ast::MonDocument;
+
+        let pair = |key: &str, value: MonValue| {
+            Member::Pair(Pair {
+                key: key.into(),
+                key_span: (0, 0).into(),
+                value,
+                validation: None,
+                serialized_key: None,
+            })
+        };
+        let value = |kind: MonValueKind| MonValue {
+            kind,
+            anchor: None,
+            pos_start: 0,
+            pos_end: 0,
+        };
+        let mut document = MonDocument {
+            root: value(MonValueKind::Object(vec![
+                pair("name", value(MonValueKind::String("mon".into()))),
+                pair(
+                    "ports",
+                    value(MonValueKind::Array(vec![
+                        value(MonValueKind::Number(80.0)),
+                        value(MonValueKind::Number(443.0)),
+                    ])),
+                ),
+            ])),
+            imports: Vec::new(),
+            metadata: HashMap::new(),
+            element_docs: HashMap::new(),
+            number_literals: HashMap::new(),
+        };
+        assert!(find_symbol_at(&document.root, 3).is_none());
+
+        let text = document.normalize_spans().unwrap();
+        let position = text.find("443").unwrap();
+        let Some(SymbolInfo {
+            node: FoundNode::Value(found),
+            ..
+        }) = find_symbol_at(&document.root, position)
+        else {
+            panic!("Expected a value at {position}");
+        };
+        assert_eq!(found.kind, MonValueKind::Number(443.0));
+        assert_eq!(&text[found.pos_start..found.pos_end], "443");
+
+        // Every key and value now points at its text
+        let mut spans = Vec::new();
+        document.root.walk_mut(&mut |value| {
+            spans.push(text[value.pos_start..value.pos_end].to_string());
+        });
+        assert_eq!(
+            spans[1..],
+            [
+                "\"mon\"",
+                "[\n        80,\n        443,\n    ]",
+                "80",
+                "443"
+            ]
+        );
+        let MonValueKind::Object(members) = &document.root.kind else {
+            panic!("Expected an object");
+        };
+        let Member::Pair(name) = &members[0] else {
+            panic!("Expected a pair");
+        };
+        let key_span = name.key_span;
+        assert_eq!(
+            &text[key_span.offset()..key_span.offset() + key_span.len()],
+            "name"
+        );
+    }
+
     #[test]
     fn test_find_symbol_at_value() {
         let val = make_simple_value(0, 5);
@@ -329,8 +551,10 @@ mod tests {
         let inner_val = make_simple_value(5, 10);
         let pair = Pair {
             key: "inner".into(),
+            key_span: (0, 0).into(),
             value: inner_val.clone(),
             validation: None,
+            serialized_key: None,
         };
         let val = MonValue {
             kind: MonValueKind::Object(vec![Member::Pair(pair.clone())]),
@@ -353,8 +577,10 @@ mod tests {
         let inner_val = make_simple_value(5, 10);
         let pair = Pair {
             key: "inner".into(),
+            key_span: (0, 0).into(),
             value: inner_val.clone(),
             validation: Some(ts.clone()),
+            serialized_key: None,
         };
         let val = MonValue {
             kind: MonValueKind::Object(vec![Member::Pair(pair)]),
@@ -379,6 +605,7 @@ mod tests {
         let val = MonValue {
             kind: MonValueKind::Object(vec![Member::Pair(Pair {
                 key: "x".into(),
+                key_span: (0, 0).into(),
                 value: MonValue {
                     kind: MonValueKind::Alias("MyType".into()),
                     anchor: None,
@@ -386,6 +613,7 @@ mod tests {
                     pos_end: 10,
                 },
                 validation: Some(ts1.clone()),
+                serialized_key: None,
             })]),
             anchor: None,
             pos_start: 0,
@@ -414,11 +642,13 @@ mod tests {
         let val = MonValue {
             kind: MonValueKind::Object(vec![Member::Pair(Pair {
                 key: "key".into(),
+                key_span: (0, 0).into(),
                 value: make_simple_value(5, 10),
                 validation: Some(TypeSpec::Simple(
                     "String".into(),
                     SourceSpan::new(0.into(), 1),
                 )),
+                serialized_key: None,
             })]),
             anchor: Some("my_anchor".into()),
             pos_start: 0,
@@ -443,10 +673,49 @@ mod tests {
             .any(|t| t.token_type == SemanticTokenType::String));
     }
 
+    #[test]
+    fn test_encode_semantic_tokens() {
+        let source = "{ a: \"\u{e9}\",\r\n  b: 10 }";
+        let token = |start: usize, end: usize, token_type| SemanticToken {
+            span: (start, end - start).into(),
+            token_type,
+        };
+        let b = source.find('b').unwrap();
+        let tokens = [
+            token(b + 3, b + 5, SemanticTokenType::Number),
+            token(2, 9, SemanticTokenType::Property),
+            token(b, b + 5, SemanticTokenType::Property),
+            token(5, 9, SemanticTokenType::String),
+        ];
+        let property = SemanticTokenType::Property.legend_index();
+        assert_eq!(SemanticTokenType::LEGEND[property as usize], "property");
+        let encoded = encode_semantic_tokens(&tokens, source);
+        let string = SemanticTokenType::String.legend_index();
+        let number = SemanticTokenType::Number.legend_index();
+        assert_eq!(
+            encoded.chunks(5).collect::<Vec<_>>(),
+            [
+                // `a: ` is what is left of the pair once its value is taken out
+                [0, 2, 3, property, 0],
+                // `"é"` is three UTF-16 code units long
+                [0, 3, 3, string, 0],
+                [1, 2, 3, property, 0],
+                [0, 3, 2, number, 0],
+            ]
+        );
+
+        // A token running over several lines is cut off at the end of its first line
+        let tokens = [token(2, source.len(), SemanticTokenType::Comment)];
+        assert_eq!(
+            encode_semantic_tokens(&tokens, source),
+            vec![0, 2, 7, SemanticTokenType::Comment.legend_index(), 0]
+        );
+    }
+
     #[test]
     fn test_generate_semantic_tokens_enum() {
         let enum_def = TypeDef::Enum(EnumDef {
-            variants: vec!["A".into(), "B".into()],
+            variants: vec![("A".into(), None), ("B".into(), None)],
             pos_start: 0,
             pos_end: 10,
         });
@@ -540,6 +809,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_text_at_definition() {
+        let source = r#"{ &base: { "ä": 1 }, value: *base }"#;
+        let analysis_result = analyze(source, "test.mon").unwrap();
+
+        let span = analysis_result
+            .get_definition_at(source.find("*base").unwrap())
+            .unwrap();
+        assert_eq!(analysis_result.text_at(span), Some(r#"{ "ä": 1 }"#));
+
+        // Spans past the end or inside a character have no text
+        assert_eq!(analysis_result.text_at((source.len(), 1).into()), None);
+        let inside_a = source.find('ä').unwrap() + 1;
+        assert_eq!(analysis_result.text_at((inside_a, 1).into()), None);
+        // A span whose end overflows has no text instead of panicking
+        assert_eq!(analysis_result.text_at((usize::MAX, 2).into()), None);
+    }
+
     #[test]
     fn test_get_definition_of_type() {
         let source = r#"
@@ -563,6 +850,41 @@ mod tests {
         assert_eq!(definition_span.offset(), struct_def_pos);
         assert_eq!(definition_span.len(), struct_def_end_pos - struct_def_pos);
     }
+
+    #[test]
+    fn test_get_definition_of_element_type() {
+        let source = r#"
+        {
+            MyType: #struct { field(String) },
+            many :: [MyType...] = [],
+            pair :: [MyType; 2] = [{ field: "a" }, { field: "b" }],
+        }
+    "#;
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+        let struct_def_pos = source.find("#struct").unwrap();
+
+        for needle in ["MyType...", "MyType; 2"] {
+            let type_pos = source.find(needle).unwrap();
+            let definition_span = analysis_result.get_definition_at(type_pos).unwrap();
+            assert_eq!(definition_span.offset(), struct_def_pos);
+        }
+    }
+
+    #[test]
+    fn test_find_node_at_in_spread_type() {
+        let source = r#"{ a :: [Number...] = [1, 2] }"#;
+        let doc = crate::parser::Parser::new(source)
+            .unwrap()
+            .parse_document()
+            .unwrap();
+
+        assert!(matches!(
+            find_node_at(&doc.root, source.find("Number").unwrap()),
+            Some(FoundNode::TypeSpec(TypeSpec::Simple(name, _))) if name == "Number"
+        ));
+    }
+
     #[test]
     fn test_get_type_info() {
         let source = r#"
@@ -579,7 +901,58 @@ mod tests {
 
         let type_info = analysis_result.get_type_info_at(value_pos).unwrap();
 
-        assert_eq!(type_info, "MyType");
+        assert_eq!(type_info, "MyType { field: String }");
+    }
+
+    #[test]
+    fn test_get_type_info_shows_element_comments() {
+        let source = r#"{
+            ports: [
+                // the public port
+                443,
+                8080, // for debugging
+                9000,
+            ],
+        }"#;
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+
+        let type_info = |needle| {
+            analysis_result
+                .get_type_info_at(source.find(needle).unwrap())
+                .unwrap()
+        };
+        assert_eq!(type_info("443"), "443\n\nthe public port");
+        assert_eq!(type_info("8080"), "8080\n\nfor debugging");
+        assert_eq!(type_info("9000"), "9000");
+    }
+
+    #[test]
+    fn test_get_type_info_lists_struct_fields() {
+        let source = r#"
+        {
+            Role: #enum { Admin, Guest },
+            User: #struct { id(Number), tags([String...]) = [], role(Role) = $Role.Guest, ... },
+            user :: User = { id: 1 },
+            role :: Role = $Role.Admin,
+        }
+    "#;
+
+        let analysis_result = analyze(source, "test.mon").unwrap();
+
+        let type_info = analysis_result
+            .get_type_info_at(source.find("id: 1").unwrap())
+            .unwrap();
+        assert_eq!(
+            type_info,
+            "User { id: Number, tags: [String...] = [], role: Role = $Role.Guest, ... }"
+        );
+
+        // Other types are shown by name.
+        let type_info = analysis_result
+            .get_type_info_at(source.find("$Role.Admin").unwrap())
+            .unwrap();
+        assert_eq!(type_info, "Role");
     }
 
     #[test]
@@ -57,12 +57,16 @@
 //! # }
 //! ```
 use crate::ast::{
-    EnumDef, FieldDef, ImportSpec, ImportSpecifier, ImportStatement, Member, MonDocument, MonValue,
-    MonValueKind, Pair, StructDef, TypeDef, TypeDefinition, TypeSpec,
+    ElementDoc, EnumDef, FieldDef, Guard, GuardCondition, ImportSpec, ImportSpecifier,
+    ImportStatement, Member, MonDocument, MonValue, MonValueKind, Pair, StructDef, TypeDef,
+    TypeDefinition, TypeSpec,
 };
+pub use crate::error::LimitKind;
 use crate::error::{MonError, ParserError};
-use crate::lexer::{Lexer, Token, TokenType};
-use miette::{GraphicalReportHandler, NamedSource, Report};
+use crate::lexer::{Lexer, LexerOptions, Token, TokenType};
+use crate::utils::source_span;
+use miette::NamedSource;
+use std::collections::HashMap;
 use std::panic::Location;
 use std::sync::Arc;
 
@@ -109,9 +113,91 @@ use std::sync::Arc;
 #[derive(Debug)]
 pub struct Parser<'a> {
     source: Arc<NamedSource<String>>,
-    tokens: Vec<Token>,
+    /// The indices into `all_tokens` of the tokens that are not whitespace or comments, so
+    /// that the tokens are not stored twice.
+    tokens: Vec<usize>,
+    all_tokens: Vec<Token>,
     position: usize,
     source_text: &'a str,
+    streaming: Option<Streaming<'a>>,
+    /// The comments of the array elements parsed so far, if comments are preserved.
+    element_docs: Option<HashMap<usize, ElementDoc>>,
+    /// The source text of the numbers parsed so far, if number literals are preserved.
+    number_literals: Option<HashMap<usize, String>>,
+    limits: ParserLimits,
+    /// How many object members the parser has read, for [`ParserLimits::max_members`].
+    member_count: usize,
+}
+
+/// Caps on the size of a parsed document, e.g. for a service that accepts MON from its users.
+/// See [`Parser::new_with_limits`].
+///
+/// `None` means no cap, which is the default for every limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// The most object members in the whole document, counting those of nested objects.
+    pub max_members: Option<usize>,
+    /// The most elements in any one array.
+    pub max_array_len: Option<usize>,
+    /// The longest string or quoted key, in bytes.
+    ///
+    /// Strings are checked after the lexer has read them, so this does not bound memory.
+    pub max_string_len: Option<usize>,
+}
+
+/// Receives the parts of a document while [`Parser::parse_document_streaming`] parses it.
+///
+/// The elements of arrays that are the direct value of a root-level key are handed to
+/// [`DocumentSink::on_element`] one by one instead of being kept in the AST. Arrays with
+/// an anchor, like `&items: [...]`, are kept so they can still be aliased.
+pub trait DocumentSink {
+    /// Called once the import statements at the top of the document have been parsed.
+    ///
+    /// # Errors
+    /// An error stops parsing and is returned by [`Parser::parse_document_streaming`].
+    fn on_imports(&mut self, _imports: &[ImportStatement]) -> Result<(), MonError> {
+        Ok(())
+    }
+
+    /// Called after each member of the root object has been parsed.
+    /// Streamed arrays are passed without their elements.
+    ///
+    /// # Errors
+    /// An error stops parsing and is returned by [`Parser::parse_document_streaming`].
+    fn on_root_member(&mut self, _member: &Member) -> Result<(), MonError> {
+        Ok(())
+    }
+
+    /// Called for each element of a streamed array, in order. `validation` is the type
+    /// annotation of the array's key, if any.
+    ///
+    /// # Errors
+    /// An error stops parsing and is returned by [`Parser::parse_document_streaming`].
+    fn on_element(
+        &mut self,
+        key: &str,
+        validation: Option<&TypeSpec>,
+        element: MonValue,
+    ) -> Result<(), MonError>;
+}
+
+/// The state of a streaming parse, see [`Parser::parse_document_streaming`].
+struct Streaming<'a> {
+    sink: Box<dyn DocumentSink + 'a>,
+    /// How many objects and arrays the parser is currently inside of.
+    depth: usize,
+    /// The key and validation of the root-level pair whose value is being parsed, if its
+    /// value may be streamed.
+    target: Option<(String, Option<TypeSpec>)>,
+}
+
+impl std::fmt::Debug for Streaming<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Streaming")
+            .field("depth", &self.depth)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<'a> Parser<'a> {
@@ -133,6 +219,7 @@ impl<'a> Parser<'a> {
     /// Creates a new `Parser` instance with a specified file name.
     ///
     /// The parser initializes a [`Lexer`] and filters out whitespace and comments.
+    /// The unfiltered token list stays available through [`Parser::all_tokens`].
     ///
     /// # Arguments
     ///
@@ -143,22 +230,93 @@ impl<'a> Parser<'a> {
     ///
     /// Returns a [`MonError`] if lexing the source text fails.
     pub fn new_with_name(source_text: &'a str, name: String) -> Result<Self, MonError> {
+        Self::new_with_options(source_text, name, LexerOptions::default())
+    }
+
+    /// Creates a new `Parser` like [`Parser::new_with_name`] that tokenizes the source with the
+    /// given [`LexerOptions`], e.g. to read `yes` and `no` as booleans.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MonError`] if lexing the source text fails.
+    pub fn new_with_options(
+        source_text: &'a str,
+        name: String,
+        options: LexerOptions,
+    ) -> Result<Self, MonError> {
+        let mut lexer = Lexer::new_with_options(source_text, options).with_name(name.clone());
         let source = Arc::new(NamedSource::new(name, source_text.to_string()));
-        let mut lexer = Lexer::new(source_text);
-        let tokens: Vec<Token> = lexer
-            .lex()
-            .into_iter()
-            .filter(|t| !matches!(t.ttype, TokenType::Whitespace | TokenType::Comment(_)))
+        let all_tokens = lexer.lex();
+        let tokens: Vec<usize> = all_tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| !matches!(t.ttype, TokenType::Whitespace | TokenType::Comment(_)))
+            .map(|(index, _)| index)
             .collect();
 
         Ok(Self {
             source,
             tokens,
+            all_tokens,
             position: 0,
             source_text,
+            streaming: None,
+            element_docs: None,
+            number_literals: None,
+            limits: ParserLimits::default(),
+            member_count: 0,
         })
     }
 
+    /// Creates a new `Parser` like [`Parser::new_with_name`] that fails with
+    /// [`ParserError::LimitExceeded`] once the document grows beyond `limits`.
+    ///
+    /// The whole source is still read into memory, and lexed before any limit is checked; the
+    /// limits bound the size of the AST built from it. In particular, a string longer than
+    /// [`ParserLimits::max_string_len`] is rejected only after it has been read in full, so that
+    /// limit does not bound memory either. Cap the size of the source to bound memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MonError`] if lexing the source text fails.
+    pub fn new_with_limits(
+        source_text: &'a str,
+        name: String,
+        limits: ParserLimits,
+    ) -> Result<Self, MonError> {
+        let mut parser = Self::new_with_name(source_text, name)?;
+        parser.limits = limits;
+        Ok(parser)
+    }
+
+    /// Makes the parser attach the comments around array elements and object members to the
+    /// parsed document, see [`MonDocument::element_docs`]. Formatting the document writes them back.
+    ///
+    /// A comment on its own line before an element becomes its `leading_doc`, a comment after
+    /// it on the same line its `trailing_doc`.
+    #[must_use]
+    pub fn with_comments(mut self) -> Self {
+        self.element_docs = Some(HashMap::new());
+        self
+    }
+
+    /// Makes the parser keep the source text of numbers, see [`MonDocument::number_literals`],
+    /// so that formatting the document keeps notations like `1.23e10` or `0.50`.
+    #[must_use]
+    pub fn with_number_literals(mut self) -> Self {
+        self.number_literals = Some(HashMap::new());
+        self
+    }
+
+    /// Returns every token of the source, including whitespace and comments (the "trivia").
+    ///
+    /// This lets tools such as syntax highlighters map comment, string, and number spans to
+    /// highlight classes without running the [`Lexer`] a second time.
+    #[must_use]
+    pub fn all_tokens(&self) -> &[Token] {
+        &self.all_tokens
+    }
+
     // === Main Parsing Methods ===
 
     /// Parses the entire MON source into a [`MonDocument`].
@@ -170,6 +328,85 @@ impl<'a> Parser<'a> {
     ///
     /// Returns a [`MonError`] if parsing fails at any point.
     pub fn parse_document(&mut self) -> Result<MonDocument, MonError> {
+        let document = self.parse_document_body()?;
+
+        // After the root object, we expect the end of the file.
+        self.expect_end()?;
+        Ok(document)
+    }
+
+    /// Parses the entire source like [`Parser::parse_document`], but hands the elements of
+    /// root-level arrays to `sink` as they are parsed instead of keeping them in the AST.
+    ///
+    /// This bounds the memory used by the AST when processing large record lists one at a
+    /// time. The returned document contains everything else, with the streamed arrays empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MonError`] if parsing fails or if `sink` returns an error.
+    pub fn parse_document_streaming(
+        &mut self,
+        sink: impl DocumentSink + 'a,
+    ) -> Result<MonDocument, MonError> {
+        self.streaming = Some(Streaming {
+            sink: Box::new(sink),
+            depth: 0,
+            target: None,
+        });
+        let document = self.parse_document();
+        self.streaming = None;
+        document
+    }
+
+    /// Parses a multi-document MON source into a list of [`MonDocument`]s.
+    ///
+    /// Documents are separated by a line containing exactly `---`. A leading or
+    /// trailing separator is allowed, so a single document parses the same way
+    /// it does with [`Parser::parse_document`].
+    ///
+    /// `Documents` ::= [ "---" ] Document { "---" Document } [ "---" ]
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MonError`] if parsing any of the documents fails.
+    pub fn parse_documents(&mut self) -> Result<Vec<MonDocument>, MonError> {
+        let mut documents = Vec::new();
+        self.match_token(&TokenType::DocumentSeparator);
+
+        loop {
+            documents.push(self.parse_document_body()?);
+            if !self.match_token(&TokenType::DocumentSeparator) || self.check(&TokenType::Eof) {
+                break;
+            }
+        }
+
+        self.expect_end()?;
+        Ok(documents)
+    }
+
+    /// Expects the end of the file after the last document, reporting anything else as
+    /// [`ParserError::TrailingContent`].
+    fn expect_end(&mut self) -> Result<(), MonError> {
+        let token = self.current_token()?;
+        if matches!(token.ttype, TokenType::Eof | TokenType::Unknown) {
+            return self.expect(&TokenType::Eof);
+        }
+        let start = token.pos_start;
+        let end = (0..self.tokens.len())
+            .rev()
+            .filter_map(|index| self.token_at(index))
+            .find(|token| token.ttype != TokenType::Eof)
+            .map_or(start, |token| token.pos_end);
+        Err(ParserError::TrailingContent {
+            src: (*self.source).clone().into(),
+            span: (start, end - start).into(),
+        }
+        .into())
+    }
+
+    /// Document ::= { `ImportStatement` } Object
+    fn parse_document_body(&mut self) -> Result<MonDocument, MonError> {
+        let metadata = self.parse_metadata()?;
         let mut imports: Vec<ImportStatement> = Vec::new();
 
         // consume zero-or-more import statements
@@ -177,13 +414,56 @@ impl<'a> Parser<'a> {
             let imp = self.parse_import_statement()?;
             imports.push(imp);
         }
+        if let Some(streaming) = &mut self.streaming {
+            streaming.sink.on_imports(&imports)?;
+        }
 
         // After imports, we expect the root object.
         let root = self.parse_object()?;
+        Ok(MonDocument {
+            root,
+            imports,
+            metadata,
+            element_docs: self
+                .element_docs
+                .as_mut()
+                .map(std::mem::take)
+                .unwrap_or_default(),
+            number_literals: self
+                .number_literals
+                .as_mut()
+                .map(std::mem::take)
+                .unwrap_or_default(),
+        })
+    }
 
-        // After the root object, we expect the end of the file.
-        self.expect(&TokenType::Eof)?;
-        Ok(MonDocument { root, imports })
+    /// Collects the `//! key: value` annotation comments between the previous token (or the
+    /// start of the source) and the start of the current document.
+    ///
+    /// Annotation comments without a `:` are ignored, like ordinary comments.
+    fn parse_metadata(&self) -> Result<HashMap<String, String>, MonError> {
+        let document_start = self.current_token()?.pos_start;
+        let trivia_start = if self.position == 0 {
+            0
+        } else {
+            self.current_token_before_advance()?.pos_end
+        };
+
+        let mut metadata = HashMap::new();
+        for token in &self.all_tokens {
+            if token.pos_start < trivia_start || token.pos_start >= document_start {
+                continue;
+            }
+            if let TokenType::Comment(text) = &token.ttype {
+                if let Some((key, value)) = text
+                    .strip_prefix('!')
+                    .and_then(|annotation| annotation.split_once(':'))
+                {
+                    metadata.insert(key.trim().to_string(), value.trim().to_string());
+                }
+            }
+        }
+        Ok(metadata)
     }
 
     /// Object ::= "{" [ `MemberList` ] "}"
@@ -191,24 +471,28 @@ impl<'a> Parser<'a> {
     fn parse_object(&mut self) -> Result<MonValue, MonError> {
         let start_token = self.current_token()?.clone();
         self.expect(&TokenType::LBrace)?;
+        let depth = self.enter_nesting();
         let mut members = Vec::new();
         if !self.check(&TokenType::RBrace) {
             // Parse the first member
-            members.push(self.parse_member()?);
+            members.push(self.parse_documented_member()?);
+            self.notify_root_member(depth, &members)?;
             // Keep parsing members as long as they are preceded by a comma
             while self.match_token(&TokenType::Comma) {
                 // If we match a comma but the next token is a brace, it's a trailing comma
                 if self.check(&TokenType::RBrace) {
                     break;
                 }
-                members.push(self.parse_member()?);
+                members.push(self.parse_documented_member()?);
+                self.notify_root_member(depth, &members)?;
             }
         }
+        self.leave_nesting();
         let end_token = self.current_token()?.clone();
         self.expect(&TokenType::RBrace)?;
         Ok(MonValue {
             kind: MonValueKind::Object(members),
-            anchor: None, // Anchors are attached to values, not objects themselves
+            anchor: None, // `parse_value` attaches an inline anchor like `&name { ... }`
             pos_start: start_token.pos_start,
             pos_end: end_token.pos_end,
         })
@@ -219,21 +503,52 @@ impl<'a> Parser<'a> {
     fn parse_array(&mut self) -> Result<MonValue, MonError> {
         let start_token = self.current_token()?.clone();
         self.expect(&TokenType::LBracket)?;
+        // The value of a root-level pair is parsed two levels deep, inside the root object.
+        let stream_target = if self.enter_nesting() == 2 {
+            self.streaming
+                .as_mut()
+                .and_then(|streaming| streaming.target.take())
+        } else {
+            None
+        };
         let mut values = Vec::new();
+        // Streamed elements are not kept in `values`, but count towards the limit
+        let mut element_count = 0;
         if !self.check(&TokenType::RBracket) {
             loop {
-                if self.check(&TokenType::Spread) {
+                // The `[` or `,` before the element
+                let preceding_end = self.current_token_before_advance()?.pos_end;
+                let value = if self.check(&TokenType::Spread) {
                     let spread_start_token = self.current_token()?.clone();
                     let spread_name = self.parse_spread()?;
                     let spread_end_token = self.current_token_before_advance()?.clone(); // Get token before advance
-                    values.push(MonValue {
+                    MonValue {
                         kind: MonValueKind::ArraySpread(spread_name),
                         anchor: None,
                         pos_start: spread_start_token.pos_start,
                         pos_end: spread_end_token.pos_end,
-                    });
+                    }
                 } else {
-                    values.push(self.parse_value()?);
+                    self.parse_value()?
+                };
+                element_count += 1;
+                self.check_limit(
+                    LimitKind::ArrayLength,
+                    self.limits.max_array_len,
+                    element_count,
+                    value.pos_start,
+                    value.pos_end,
+                )?;
+                self.record_element_doc(
+                    preceding_end,
+                    (value.pos_start, value.pos_end),
+                    Some(value.pos_start),
+                );
+                match (&stream_target, &mut self.streaming) {
+                    (Some((key, validation)), Some(streaming)) => {
+                        streaming.sink.on_element(key, validation.as_ref(), value)?;
+                    }
+                    _ => values.push(value),
                 }
 
                 if !self.match_token(&TokenType::Comma) {
@@ -244,6 +559,7 @@ impl<'a> Parser<'a> {
                 }
             }
         }
+        self.leave_nesting();
         let end_token = self.current_token()?.clone();
         self.expect(&TokenType::RBracket)?;
         Ok(MonValue {
@@ -254,10 +570,102 @@ impl<'a> Parser<'a> {
         })
     }
 
-    /// Value ::= Object | Array | Alias | `EnumValue` | Literal
+    /// Parses an object member and records the comments around it, if comments are preserved.
+    fn parse_documented_member(&mut self) -> Result<Member, MonError> {
+        self.member_count += 1;
+        let start_token = self.current_token()?;
+        self.check_limit(
+            LimitKind::Members,
+            self.limits.max_members,
+            self.member_count,
+            start_token.pos_start,
+            start_token.pos_end,
+        )?;
+        if self.element_docs.is_none() {
+            return self.parse_member();
+        }
+        // The `{` or `,` before the member
+        let preceding_end = self.current_token_before_advance()?.pos_end;
+        let start = self.current_token()?.pos_start;
+        let member = self.parse_member()?;
+        let end = self.current_token_before_advance()?.pos_end;
+        self.record_element_doc(preceding_end, (start, end), member.doc_key());
+        Ok(member)
+    }
+
+    /// Records the comments around an array element or object member spanning `start..end`
+    /// that follows `preceding_end`, under `key`, if comments are preserved.
+    fn record_element_doc(
+        &mut self,
+        preceding_end: usize,
+        (start, end): (usize, usize),
+        key: Option<usize>,
+    ) {
+        let (Some(element_docs), Some(key)) = (&mut self.element_docs, key) else {
+            return;
+        };
+        let comment_text = |token: &Token| match &token.ttype {
+            TokenType::Comment(text) => Some(text.clone()),
+            _ => None,
+        };
+        // The tokens are ordered, so only the trivia around the element is looked at
+        let tokens_from = |pos: usize| {
+            let index = self
+                .all_tokens
+                .partition_point(|token| token.pos_start < pos);
+            &self.all_tokens[index..]
+        };
+
+        // Comments on the line of a `,` belong to the previous element
+        let after_comma = self.source_text[..preceding_end].ends_with(',');
+        let leading: Vec<String> = tokens_from(preceding_end)
+            .iter()
+            .take_while(|token| token.pos_end <= start)
+            .filter(|token| {
+                !after_comma || self.source_text[preceding_end..token.pos_start].contains('\n')
+            })
+            .filter_map(comment_text)
+            .collect();
+
+        // A comment on the element's line is its trailing comment. The comments on the lines
+        // after the last element, before the closing bracket, are kept with it too.
+        let mut trailing = None;
+        let mut following = Vec::new();
+        let mut same_line = true;
+        for token in tokens_from(end) {
+            match &token.ttype {
+                TokenType::Whitespace => {
+                    same_line &= !self.source_text[token.pos_start..token.pos_end].contains('\n');
+                }
+                TokenType::Comma => {}
+                TokenType::Comment(text) if same_line => trailing = Some(text.clone()),
+                TokenType::Comment(text) => following.push(text.clone()),
+                TokenType::RBrace | TokenType::RBracket => break,
+                _ => {
+                    following.clear();
+                    break;
+                }
+            }
+        }
+
+        let doc = ElementDoc {
+            leading_doc: (!leading.is_empty()).then(|| leading.join("\n")),
+            trailing_doc: trailing,
+            closing_doc: (!following.is_empty()).then(|| following.join("\n")),
+        };
+        if doc != ElementDoc::default() {
+            element_docs.insert(key, doc);
+        }
+    }
+
+    /// Value ::= Object | Array | Alias | `EnumValue` | `BareWord` | Literal
     /// Attaches an anchor if one is present.
     fn parse_value(&mut self) -> Result<MonValue, MonError> {
         let anchor = self.parse_optional_anchor()?;
+        if anchor.is_some() {
+            // An anchored array may be aliased later, so it is never streamed.
+            self.clear_stream_target();
+        }
 
         let start_token = self.current_token()?.clone(); // Capture start token for pos_start
 
@@ -266,6 +674,7 @@ impl<'a> Parser<'a> {
             TokenType::LBrace => self.parse_object(),
             TokenType::LBracket => self.parse_array(),
             TokenType::String(s) => {
+                self.check_string_len(s, &start_token)?;
                 self.advance();
                 Ok(MonValue {
                     kind: MonValueKind::String(s.clone()),
@@ -275,6 +684,12 @@ impl<'a> Parser<'a> {
                 })
             }
             TokenType::Number(n) => {
+                if let Some(number_literals) = &mut self.number_literals {
+                    number_literals.insert(
+                        start_token.pos_start,
+                        self.source_text[start_token.pos_start..start_token.pos_end].to_string(),
+                    );
+                }
                 self.advance();
                 Ok(MonValue {
                     kind: MonValueKind::Number(*n),
@@ -283,6 +698,19 @@ impl<'a> Parser<'a> {
                     pos_end: start_token.pos_end,
                 })
             }
+            TokenType::Quantity(value, unit) => {
+                let kind = MonValueKind::Quantity {
+                    value: *value,
+                    unit: unit.clone(),
+                };
+                self.advance();
+                Ok(MonValue {
+                    kind,
+                    anchor: None,
+                    pos_start: start_token.pos_start,
+                    pos_end: start_token.pos_end,
+                })
+            }
             TokenType::True => {
                 self.advance();
                 Ok(MonValue {
@@ -312,6 +740,15 @@ impl<'a> Parser<'a> {
             }
             TokenType::Asterisk => self.parse_alias(),
             TokenType::Dollar => self.parse_enum_value(),
+            TokenType::Identifier(word) => {
+                self.advance();
+                Ok(MonValue {
+                    kind: MonValueKind::BareWord(word.clone()),
+                    anchor: None,
+                    pos_start: start_token.pos_start,
+                    pos_end: start_token.pos_end,
+                })
+            }
             _ => self.err_unexpected("a value"),
         }?;
 
@@ -319,10 +756,23 @@ impl<'a> Parser<'a> {
         Ok(value)
     }
 
-    /// Member ::= Pair | `TypeDefinition` | Spread
+    /// Member ::= { Guard } ( Pair | `TypeDefinition` | Spread )
     fn parse_member(&mut self) -> Result<Member, MonError> {
         match self.current_token()?.ttype {
-            TokenType::Spread => self.parse_spread().map(Member::Spread),
+            TokenType::LBracket => {
+                let guard = self.parse_guard()?;
+                let member = self.parse_member()?;
+                Ok(Member::Guarded(guard, Box::new(member)))
+            }
+            TokenType::Spread => {
+                let start_token = self.current_token()?.clone();
+                let name = self.parse_spread()?;
+                let end_token = self.current_token_before_advance()?;
+                Ok(Member::Spread(
+                    name,
+                    source_span(start_token.pos_start, end_token.pos_end),
+                ))
+            }
             // A TypeDefinition starts with an Identifier followed by a Colon and a Hash
             TokenType::Identifier(_)
                 if self.peek_is(&TokenType::Colon) && self.peek_next_is(&TokenType::Hash) =>
@@ -334,6 +784,54 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Guard ::= "[" "@" "if" "(" Identifier [ ( "==" | "!=" ) String ] ")" "]"
+    fn parse_guard(&mut self) -> Result<Guard, MonError> {
+        let start_token = self.current_token()?.clone();
+        self.expect(&TokenType::LBracket)?;
+        self.expect(&TokenType::At)?;
+        if !matches!(&self.current_token()?.ttype, TokenType::Identifier(s) if s == "if") {
+            return self.err_unexpected("`if` after `@`");
+        }
+        self.advance();
+        self.expect(&TokenType::LParen)?;
+        let TokenType::Identifier(variable) = &self.current_token()?.ttype else {
+            return self.err_unexpected("the name of a variable");
+        };
+        let variable = variable.clone();
+        self.advance();
+
+        let condition = if self.check(&TokenType::RParen) {
+            GuardCondition::IsSet
+        } else {
+            let equals = if self.match_token(&TokenType::DoubleEquals) {
+                true
+            } else if self.match_token(&TokenType::NotEquals) {
+                false
+            } else {
+                return self.err_unexpected("`==`, `!=` or `)`");
+            };
+            let TokenType::String(value) = &self.current_token()?.ttype else {
+                return self.err_unexpected("a string to compare the variable with");
+            };
+            let value = value.clone();
+            self.advance();
+            if equals {
+                GuardCondition::Equals(value)
+            } else {
+                GuardCondition::NotEquals(value)
+            }
+        };
+        self.expect(&TokenType::RParen)?;
+        let end_token = self.current_token()?.clone();
+        self.expect(&TokenType::RBracket)?;
+        Ok(Guard {
+            variable,
+            condition,
+            pos_start: start_token.pos_start,
+            pos_end: end_token.pos_end,
+        })
+    }
+
     /// Pair ::= `KeyPart` [ Validation ] ( ":" | "=" ) Value
     /// `KeyPart` ::= [ Anchor ] Key
     /// Key ::= Identifier | String
@@ -341,21 +839,38 @@ impl<'a> Parser<'a> {
         let mut anchor_from_key: Option<String> = None;
 
         // Handle the case where the key itself is an anchor, e.g., `&my_anchor: value`
-        let key = if self.match_token(&TokenType::Ampersand) {
-            let key_name = self.parse_key()?;
-            anchor_from_key = Some(key_name.clone());
-            key_name
-        } else {
-            self.parse_key()?
-        };
+        let is_anchor = self.match_token(&TokenType::Ampersand);
+        let key_start = self.current_token()?.pos_start;
+        let key = self.parse_key()?;
+        let key_end = self.current_token_before_advance()?.pos_end;
+        if is_anchor {
+            anchor_from_key = Some(key.clone());
+        }
 
         let validation = self.parse_optional_validation()?;
 
+        if validation.is_some() && self.check(&TokenType::Colon) {
+            let token = self.current_token()?;
+            return Err(ParserError::ColonAfterValidation {
+                src: (*self.source).clone().into(),
+                span: source_span(token.pos_start, token.pos_end),
+            }
+            .into());
+        }
+
         if !self.match_token(&TokenType::Colon) && !self.match_token(&TokenType::Equals) {
             return self.err_unexpected("':' or '=' after key");
         }
 
+        if anchor_from_key.is_none() {
+            if let Some(streaming) = &mut self.streaming {
+                if streaming.depth == 1 {
+                    streaming.target = Some((key.clone(), validation.clone()));
+                }
+            }
+        }
         let mut value = self.parse_value()?;
+        self.clear_stream_target();
 
         // If the key was an anchor, attach the anchor to the value.
         // This handles `&anchor: value`.
@@ -366,8 +881,10 @@ impl<'a> Parser<'a> {
 
         Ok(Pair {
             key,
+            key_span: (key_start, key_end - key_start).into(),
             value,
             validation,
+            serialized_key: None,
         })
     }
 
@@ -379,10 +896,17 @@ impl<'a> Parser<'a> {
         let mut key_parts = Vec::new();
 
         match &token.ttype {
-            TokenType::Identifier(s) | TokenType::String(s) => {
+            TokenType::Identifier(s) => {
                 key_parts.push(s.clone());
                 self.advance();
             }
+            // A quoted key is taken as written, dots and all, and is never joined with more parts
+            TokenType::String(s) => {
+                let key = s.clone();
+                self.check_string_len(&key, token)?;
+                self.advance();
+                return Ok(key);
+            }
             _ => return self.err_unexpected("an identifier or string for a key"),
         }
 
@@ -449,7 +973,7 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// `ImportStatement` ::= "import" ( `NamespaceImport` | `NamedImport` ) "from" String
+    /// `ImportStatement` ::= "import" ( `NamespaceImport` | `NamedImport` | `DefaultImport` ) "from" String
     fn parse_import_statement(&mut self) -> Result<ImportStatement, MonError> {
         let start_token = self.current_token()?.clone(); // Capture start token for pos_start
         self.expect(&TokenType::Import)?;
@@ -459,16 +983,30 @@ impl<'a> Parser<'a> {
             self.expect(&TokenType::As)?;
             let name = self.parse_key()?;
             ImportSpec::Namespace(name)
+        } else if let TokenType::Identifier(name) = &self.current_token()?.ttype {
+            // DefaultImport ::= Identifier
+            let name = name.clone();
+            self.advance();
+            ImportSpec::Default(name)
         } else {
             // NamedImport ::= "{" [ ImportSpecifier { "," ImportSpecifier } [ "," ] ] "}"
             self.expect(&TokenType::LBrace)?;
             let mut specifiers = Vec::new();
             if !self.check(&TokenType::RBrace) {
                 loop {
-                    // ImportSpecifier ::= [ "&" ] Identifier
+                    // ImportSpecifier ::= [ "&" ] Identifier [ "as" Identifier ]
                     let is_anchor = self.match_token(&TokenType::Ampersand);
                     let name = self.parse_key()?;
-                    specifiers.push(ImportSpecifier { name, is_anchor });
+                    let alias = if self.match_token(&TokenType::As) {
+                        Some(self.parse_key()?)
+                    } else {
+                        None
+                    };
+                    specifiers.push(ImportSpecifier {
+                        name,
+                        is_anchor,
+                        alias,
+                    });
                     if !self.match_token(&TokenType::Comma) {
                         break;
                     }
@@ -493,7 +1031,9 @@ impl<'a> Parser<'a> {
         })
     }
 
-    /// `TypeDefinition` ::= Identifier ":" ( `StructDefinition` | `EnumDefinition` )
+    /// `TypeDefinition` ::= Identifier ":" ( `StructDefinition` | `EnumDefinition` |
+    /// `AliasDefinition` ), where a struct may name its base first: `#struct extends Base { ... }`
+    /// and an alias names a type spec: `#alias [String, Number]`
     fn parse_type_definition(&mut self) -> Result<TypeDefinition, MonError> {
         let name_token = self.current_token()?.clone();
         let name = self.parse_key()?;
@@ -505,7 +1045,14 @@ impl<'a> Parser<'a> {
         let (def_type, end_pos) = match &token.ttype {
             TokenType::Identifier(s) if s == "struct" => {
                 self.advance();
-                let mut struct_def = self.parse_struct_definition()?;
+                let extends = if matches!(&self.current_token()?.ttype, TokenType::Identifier(s) if s == "extends")
+                {
+                    self.advance();
+                    Some(self.parse_key()?)
+                } else {
+                    None
+                };
+                let mut struct_def = self.parse_struct_definition(extends)?;
                 let end_pos = struct_def.pos_end;
                 struct_def.pos_start = hash_token.pos_start;
                 Ok((TypeDef::Struct(struct_def), end_pos))
@@ -517,29 +1064,41 @@ impl<'a> Parser<'a> {
                 enum_def.pos_start = hash_token.pos_start;
                 Ok((TypeDef::Enum(enum_def), end_pos))
             }
-            _ => self.err_unexpected("'struct' or 'enum' keyword"),
+            TokenType::Identifier(s) if s == "alias" => {
+                self.advance();
+                let spec = self.parse_type_spec()?;
+                let end_pos = spec.get_span().offset() + spec.get_span().len();
+                Ok((TypeDef::Alias(spec), end_pos))
+            }
+            _ => self.err_unexpected("'struct', 'enum' or 'alias' keyword"),
         }?;
 
         Ok(TypeDefinition {
             name,
-            name_span: (
-                name_token.pos_start,
-                name_token.pos_end - name_token.pos_start,
-            )
-                .into(),
+            name_span: source_span(name_token.pos_start, name_token.pos_end),
             def_type,
             pos_start: name_token.pos_start,
             pos_end: end_pos,
         })
     }
 
-    /// `StructDefinition` ::= "{" [ `FieldList` ] "}"
-    fn parse_struct_definition(&mut self) -> Result<StructDef, MonError> {
+    /// `StructDefinition` ::= "{" [ `FieldList` ] [ "..." [ "," ] ] "}" { "one_of" "(" Identifier { "," Identifier } [ "," ] ")" }
+    ///
+    /// The fields named by `one_of` are checked here unless the struct `extends` a base, whose
+    /// fields the resolver adds and checks them against.
+    fn parse_struct_definition(&mut self, extends: Option<String>) -> Result<StructDef, MonError> {
         let start_token = self.current_token()?.clone();
         self.expect(&TokenType::LBrace)?;
         let mut fields = Vec::new();
+        let mut open = false;
         if !self.check(&TokenType::RBrace) {
             loop {
+                // A trailing `...` allows fields the struct does not declare.
+                if self.match_token(&TokenType::Spread) {
+                    open = true;
+                    self.match_token(&TokenType::Comma);
+                    break;
+                }
                 fields.push(self.parse_field_definition()?);
                 if !self.match_token(&TokenType::Comma) {
                     break;
@@ -549,43 +1108,138 @@ impl<'a> Parser<'a> {
                 }
             }
         }
-        let end_token = self.current_token()?.clone();
+        let mut end_token = self.current_token()?.clone();
         self.expect(&TokenType::RBrace)?;
+
+        // `one_of(a, b, ...)` after the struct requires exactly one of the listed fields.
+        let mut one_of = Vec::new();
+        while matches!(&self.current_token()?.ttype, TokenType::Identifier(s) if s == "one_of") {
+            self.advance();
+            self.expect(&TokenType::LParen)?;
+            let mut group = Vec::new();
+            loop {
+                let name_token = self.current_token()?.clone();
+                let name = self.parse_key()?;
+                if extends.is_none() && !fields.iter().any(|field: &FieldDef| field.name == name) {
+                    return Err(ParserError::UnexpectedToken {
+                        src: (*self.source).clone().into(),
+                        span: source_span(name_token.pos_start, name_token.pos_end),
+                        expected: "a field of the struct".to_string(),
+                    }
+                    .into());
+                }
+                group.push(name);
+                if !self.match_token(&TokenType::Comma) || self.check(&TokenType::RParen) {
+                    break;
+                }
+            }
+            end_token = self.current_token()?.clone();
+            self.expect(&TokenType::RParen)?;
+            one_of.push(group);
+        }
+
         Ok(StructDef {
             fields,
+            extends,
+            open,
+            one_of,
             pos_start: start_token.pos_start,
             pos_end: end_token.pos_end,
         })
     }
 
-    /// `FieldDefinition` ::= Identifier "(" Type ")" [ "=" Value ]
+    /// `FieldDefinition` ::= Identifier "(" Type ")" [ "?" ] [ "as" String ] [ "=" Value ]
+    ///
+    /// The modifiers must come in this order, and `?` cannot be combined with a default.
     fn parse_field_definition(&mut self) -> Result<FieldDef, MonError> {
         let name = self.parse_key()?;
         self.expect(&TokenType::LParen)?;
         let type_spec = self.parse_type_spec()?;
         self.expect(&TokenType::RParen)?;
 
+        let optional = self.match_token(&TokenType::Question);
+
+        let alias = if self.match_token(&TokenType::As) {
+            let TokenType::String(alias) = &self.current_token()?.ttype else {
+                return self.err_unexpected("a string with the field's output key");
+            };
+            let alias = alias.clone();
+            self.advance();
+            Some(alias)
+        } else {
+            None
+        };
+        self.reject_misplaced_field_modifier(&name, false)?;
+
         let default_value = if self.match_token(&TokenType::Equals) {
-            Some(self.parse_value()?)
+            let value = self.parse_value()?;
+            if optional {
+                return Err(ParserError::InvalidFieldModifiers {
+                    field: name,
+                    reason: "an optional field cannot have a default value".to_string(),
+                    src: (*self.source).clone().into(),
+                    span: value.pos_span(),
+                }
+                .into());
+            }
+            Some(value)
         } else {
             None
         };
+        self.reject_misplaced_field_modifier(&name, true)?;
 
         Ok(FieldDef {
             name,
             type_spec,
+            optional,
+            alias,
             default_value,
         })
     }
 
-    /// `EnumDefinition` ::= `{ [ Identifier { , Identifier } [ , ] ] }`
+    /// Fails if the current token is a field modifier that belongs further left, e.g. the `as`
+    /// in `name(Type) = 1 as "key"`.
+    fn reject_misplaced_field_modifier(
+        &self,
+        field: &str,
+        after_default: bool,
+    ) -> Result<(), MonError> {
+        let token = self.current_token()?;
+        let reason = match token.ttype {
+            TokenType::Question => "`?` must directly follow the field's type",
+            TokenType::As if after_default => "`as \"key\"` must come before the default value",
+            _ => return Ok(()),
+        };
+        Err(ParserError::InvalidFieldModifiers {
+            field: field.to_string(),
+            reason: reason.to_string(),
+            src: (*self.source).clone().into(),
+            span: source_span(token.pos_start, token.pos_end),
+        }
+        .into())
+    }
+
+    /// `EnumDefinition` ::= `{ [ Variant { , Variant } [ , ] ] }`, where
+    /// `Variant` ::= Identifier [ "=" ( Number | String ) ]
     fn parse_enum_definition(&mut self) -> Result<EnumDef, MonError> {
         let start_token = self.current_token()?.clone();
         self.expect(&TokenType::LBrace)?;
         let mut variants = Vec::new();
         if !self.check(&TokenType::RBrace) {
             loop {
-                variants.push(self.parse_key()?);
+                let name = self.parse_key()?;
+                let discriminant = if self.match_token(&TokenType::Equals) {
+                    if !matches!(
+                        self.current_token()?.ttype,
+                        TokenType::Number(_) | TokenType::String(_)
+                    ) {
+                        return self.err_unexpected("a number or string as the variant's value");
+                    }
+                    Some(self.parse_value()?)
+                } else {
+                    None
+                };
+                variants.push((name, discriminant));
                 if !self.match_token(&TokenType::Comma) {
                     break;
                 }
@@ -617,18 +1271,25 @@ impl<'a> Parser<'a> {
         let start_token = self.current_token()?.clone();
         if self.check(&TokenType::LBracket) {
             // CollectionType ::= "[" Type [ "..." ] { "," Type [ "..." ] } "]"
+            //                  | "[" Type ";" Number "]"
             self.expect(&TokenType::LBracket)?;
             let mut types = Vec::new();
             if !self.check(&TokenType::RBracket) {
                 loop {
                     let mut type_spec = self.parse_type_spec()?;
+                    if types.is_empty() && self.match_token(&TokenType::Semicolon) {
+                        return self.parse_repeat_type_spec(type_spec, &start_token);
+                    }
                     if self.match_token(&TokenType::Spread) {
                         let end_token = self.current_token_before_advance()?.clone();
-                        let span = (
-                            type_spec.get_span().offset(),
-                            end_token.pos_end - type_spec.get_span().offset(),
-                        )
-                            .into();
+                        let span = source_span(type_spec.get_span().offset(), end_token.pos_end);
+                        if types.iter().any(|t| matches!(t, TypeSpec::Spread(..))) {
+                            return Err(ParserError::MultipleSpreadsInType {
+                                src: (*self.source).clone().into(),
+                                span,
+                            }
+                            .into());
+                        }
                         type_spec = TypeSpec::Spread(Box::new(type_spec), span);
                     }
                     types.push(type_spec);
@@ -643,33 +1304,44 @@ impl<'a> Parser<'a> {
             }
             let end_token = self.current_token()?.clone();
             self.expect(&TokenType::RBracket)?;
-            let span = (
-                start_token.pos_start,
-                end_token.pos_end - start_token.pos_start,
-            )
-                .into();
+            let span = source_span(start_token.pos_start, end_token.pos_end);
             Ok(TypeSpec::Collection(types, span))
         } else {
             // Simple Type
             let name = self.parse_key()?;
             let end_token = self.current_token_before_advance()?.clone();
-            let span = (
-                start_token.pos_start,
-                end_token.pos_end - start_token.pos_start,
-            )
-                .into();
+            let span = source_span(start_token.pos_start, end_token.pos_end);
             Ok(TypeSpec::Simple(name, span))
         }
     }
 
-    /// `EnumValue` ::= "$" Identifier "." Identifier
+    /// Parses the `Number "]"` rest of a repeated collection type like `[String; 3]`,
+    /// after the element type and the `;` have been consumed.
+    fn parse_repeat_type_spec(
+        &mut self,
+        element_type: TypeSpec,
+        start_token: &Token,
+    ) -> Result<TypeSpec, MonError> {
+        let count = match self.current_token()?.ttype {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            TokenType::Number(n) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+            _ => return self.err_unexpected("a whole number of elements after ';'"),
+        };
+        self.advance();
+        let end_token = self.current_token()?.clone();
+        self.expect(&TokenType::RBracket)?;
+        let span = source_span(start_token.pos_start, end_token.pos_end);
+        Ok(TypeSpec::Repeat(Box::new(element_type), count, span))
+    }
+
+    /// `EnumValue` ::= "$" [ Identifier "." ] Identifier "." Identifier
     fn parse_enum_value(&mut self) -> Result<MonValue, MonError> {
         let start_token = self.current_token()?.clone();
         self.expect(&TokenType::Dollar)?;
 
         // parse enum name as a single Identifier
         let enum_token = self.current_token()?.clone();
-        let enum_name = if let TokenType::Identifier(s) = &enum_token.ttype {
+        let mut enum_name = if let TokenType::Identifier(s) = &enum_token.ttype {
             let s = s.clone();
             self.advance();
             s
@@ -680,8 +1352,8 @@ impl<'a> Parser<'a> {
         self.expect(&TokenType::Dot)?;
 
         // parse variant name as a single Identifier
-        let variant_token = self.current_token()?.clone();
-        let variant_name = if let TokenType::Identifier(s) = &variant_token.ttype {
+        let mut variant_token = self.current_token()?.clone();
+        let mut variant_name = if let TokenType::Identifier(s) = &variant_token.ttype {
             let s = s.clone();
             self.advance();
             s
@@ -689,6 +1361,18 @@ impl<'a> Parser<'a> {
             return self.err_unexpected("an identifier for enum variant");
         };
 
+        // `$ns.Enum.Variant`: what we read so far was the namespace and the enum name
+        if self.match_token(&TokenType::Dot) {
+            variant_token = self.current_token()?.clone();
+            if let TokenType::Identifier(s) = &variant_token.ttype {
+                enum_name = format!("{enum_name}.{variant_name}");
+                variant_name = s.clone();
+                self.advance();
+            } else {
+                return self.err_unexpected("an identifier for enum variant");
+            }
+        }
+
         Ok(MonValue {
             kind: MonValueKind::EnumValue {
                 enum_name,
@@ -702,8 +1386,13 @@ impl<'a> Parser<'a> {
 
     // === Tokenizer Helper Methods ===
 
+    /// Returns the `index`-th token that is not whitespace or a comment.
+    fn token_at(&self, index: usize) -> Option<&Token> {
+        self.tokens.get(index).map(|&i| &self.all_tokens[i])
+    }
+
     fn current_token(&self) -> Result<&Token, MonError> {
-        self.tokens.get(self.position).ok_or_else(|| {
+        self.token_at(self.position).ok_or_else(|| {
             let pos = self.source_text.len().saturating_sub(1);
             ParserError::UnexpectedEof {
                 src: (*self.source).clone().into(), // ineficiency is my passion
@@ -714,8 +1403,7 @@ impl<'a> Parser<'a> {
     }
 
     fn current_token_before_advance(&self) -> Result<&Token, MonError> {
-        self.tokens
-            .get(self.position.saturating_sub(1))
+        self.token_at(self.position.saturating_sub(1))
             .ok_or_else(|| {
                 let pos = self.source_text.len().saturating_sub(1);
                 ParserError::UnexpectedEof {
@@ -761,7 +1449,7 @@ impl<'a> Parser<'a> {
     }
 
     fn peek_is(&self, ttype: &TokenType) -> bool {
-        if let Some(token) = self.tokens.get(self.position + 1) {
+        if let Some(token) = self.token_at(self.position + 1) {
             std::mem::discriminant(&token.ttype) == std::mem::discriminant(ttype)
         } else {
             false
@@ -769,20 +1457,92 @@ impl<'a> Parser<'a> {
     }
 
     fn peek_next_is(&self, ttype: &TokenType) -> bool {
-        if let Some(token) = self.tokens.get(self.position + 2) {
+        if let Some(token) = self.token_at(self.position + 2) {
             std::mem::discriminant(&token.ttype) == std::mem::discriminant(ttype)
         } else {
             false
         }
     }
 
+    /// Fails with [`ParserError::LimitExceeded`] if `count` is above `limit`, pointing at
+    /// `start..end`.
+    fn check_limit(
+        &self,
+        kind: LimitKind,
+        limit: Option<usize>,
+        count: usize,
+        start: usize,
+        end: usize,
+    ) -> Result<(), MonError> {
+        match limit {
+            Some(limit) if count > limit => Err(ParserError::LimitExceeded {
+                kind,
+                limit,
+                src: (*self.source).clone().into(),
+                span: source_span(start, end),
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the string `s` of `token` against [`ParserLimits::max_string_len`].
+    fn check_string_len(&self, s: &str, token: &Token) -> Result<(), MonError> {
+        self.check_limit(
+            LimitKind::StringLength,
+            self.limits.max_string_len,
+            s.len(),
+            token.pos_start,
+            token.pos_end,
+        )
+    }
+
+    /// Records that the parser entered an object or array, returning the new depth.
+    /// Depth is only tracked while streaming; otherwise this returns 0.
+    fn enter_nesting(&mut self) -> usize {
+        match &mut self.streaming {
+            Some(streaming) => {
+                streaming.depth += 1;
+                streaming.depth
+            }
+            None => 0,
+        }
+    }
+
+    fn leave_nesting(&mut self) {
+        if let Some(streaming) = &mut self.streaming {
+            streaming.depth -= 1;
+        }
+    }
+
+    fn clear_stream_target(&mut self) {
+        if let Some(streaming) = &mut self.streaming {
+            streaming.target = None;
+        }
+    }
+
+    /// Passes the last parsed member of the root object (at `depth` 1) to the streaming sink.
+    fn notify_root_member(&mut self, depth: usize, members: &[Member]) -> Result<(), MonError> {
+        match (&mut self.streaming, members.last()) {
+            (Some(streaming), Some(member)) if depth == 1 => streaming.sink.on_root_member(member),
+            _ => Ok(()),
+        }
+    }
+
     #[track_caller]
     fn err_unexpected<T>(&self, expected: &str) -> Result<T, MonError> {
         let token = self.current_token()?;
         print!("caller: {}", Location::caller());
+        if token.ttype == TokenType::Unknown {
+            return Err(ParserError::InvalidToken {
+                src: (*self.source).clone().into(),
+                span: source_span(token.pos_start, token.pos_end),
+            }
+            .into());
+        }
         Err(ParserError::UnexpectedToken {
             src: (*self.source).clone().into(),
-            span: (token.pos_start, token.pos_end - token.pos_start).into(),
+            span: source_span(token.pos_start, token.pos_end),
             expected: expected.to_string(),
         }
         .into())
@@ -793,32 +1553,150 @@ impl<'a> Parser<'a> {
 #[allow(dead_code)]
 fn pretty_result(out: Result<MonDocument, MonError>) -> String {
     match out {
-        Ok(doc) => format!("{doc:#?}"), // debug format for success
-        Err(err) => {
-            let report: Report = Report::new(err);
-            let handler = GraphicalReportHandler::new(); // pretty ANSI colors
-            let mut buffer = String::new();
-            handler.render_report(&mut buffer, &*report).unwrap();
-            buffer
-        }
+        Ok(doc) => doc.to_string(), // pretty MON output for success
+        Err(err) => crate::error::render(&err, true),
     }
 }
 
-#[cfg(test)]
-#[allow(clippy::match_wildcard_for_single_variants)]
-mod tests {
-    use super::*;
-    use miette::Report;
+// Defined here rather than in `ast`, which does not depend on the parser
+impl MonDocument {
+    /// Gives every node the position it has in the formatted document, and returns that text.
+    ///
+    /// A document built in code has no meaningful positions, so the position-based queries of
+    /// the `lsp` module cannot find its nodes. Afterwards, positions
+    /// point into the returned text, and so do the keys of `element_docs` and `number_literals`.
+    /// The values themselves are left as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MonError`] if the formatted document cannot be parsed back, e.g. because a
+    /// key is empty, and [`ParserError::UnstableFormatting`] if it parses back into a document
+    /// of a different shape, e.g. because an alias name contains a comma. The document is
+    /// left unchanged then.
+    pub fn normalize_spans(&mut self) -> Result<String, MonError> {
+        let text = self.to_string();
+        let mut parsed = {
+            let mut parser = Parser::new(&text)?;
+            if !self.element_docs.is_empty() {
+                parser = parser.with_comments();
+            }
+            if !self.number_literals.is_empty() {
+                parser = parser.with_number_literals();
+            }
+            parser.parse_document()?
+        };
 
-    impl Member {
-        fn unwrap_pair(self) -> Pair {
-            match self {
-                Member::Pair(p) => p,
-                _ => panic!("Expected Pair, got {self:?}"),
+        if let Some(mismatch) = first_shape_mismatch(&self.root, &parsed.root) {
+            return Err(ParserError::UnstableFormatting {
+                src: Arc::new(NamedSource::new("source.mon", text.clone())),
+                span: mismatch.pos_span(),
             }
+            .into());
         }
-        fn unwrap_type_definition(self) -> TypeDefinition {
-            match self {
+
+        // The parsed tree has the same shape, so its values come in the same order.
+        let mut positions = Vec::new();
+        parsed.root.walk_mut(&mut |value| {
+            let members = match &value.kind {
+                MonValueKind::Object(members) => members
+                    .iter()
+                    .map(|member| match member {
+                        // The value is visited on its own
+                        Member::Pair(pair) => Member::Pair(Pair {
+                            key: String::new(),
+                            key_span: pair.key_span,
+                            value: MonValue {
+                                kind: MonValueKind::Null,
+                                anchor: None,
+                                pos_start: 0,
+                                pos_end: 0,
+                            },
+                            validation: pair.validation.clone(),
+                            serialized_key: None,
+                        }),
+                        member => member.clone(),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            positions.push((value.pos_start, value.pos_end, members));
+        });
+        let mut positions = positions.into_iter();
+        self.root.walk_mut(&mut |value| {
+            let Some((pos_start, pos_end, members)) = positions.next() else {
+                return;
+            };
+            value.pos_start = pos_start;
+            value.pos_end = pos_end;
+            let MonValueKind::Object(value_members) = &mut value.kind else {
+                return;
+            };
+            for (member, parsed) in value_members.iter_mut().zip(members) {
+                match (member, parsed) {
+                    (Member::Pair(pair), Member::Pair(parsed)) => {
+                        pair.key_span = parsed.key_span;
+                        pair.validation = parsed.validation;
+                    }
+                    (Member::Pair(_), _) => {}
+                    (member, parsed) => *member = parsed,
+                }
+            }
+        });
+
+        self.imports = parsed.imports;
+        self.element_docs = parsed.element_docs;
+        self.number_literals = parsed.number_literals;
+        Ok(text)
+    }
+}
+
+/// Returns the first value of `parsed` that is not an object or array of the same length as
+/// the value at the same place in `original`, or vice versa.
+fn first_shape_mismatch<'a>(original: &MonValue, parsed: &'a MonValue) -> Option<&'a MonValue> {
+    match (&original.kind, &parsed.kind) {
+        (MonValueKind::Object(original_members), MonValueKind::Object(parsed_members))
+            if original_members.len() == parsed_members.len() =>
+        {
+            original_members
+                .iter()
+                .zip(parsed_members)
+                .find_map(|members| match members {
+                    (Member::Pair(original), Member::Pair(parsed)) => {
+                        first_shape_mismatch(&original.value, &parsed.value)
+                    }
+                    (Member::Pair(_), _) | (_, Member::Pair(_)) => Some(parsed),
+                    _ => None,
+                })
+        }
+        (MonValueKind::Array(original_elements), MonValueKind::Array(parsed_elements))
+            if original_elements.len() == parsed_elements.len() =>
+        {
+            original_elements
+                .iter()
+                .zip(parsed_elements)
+                .find_map(|(original, parsed)| first_shape_mismatch(original, parsed))
+        }
+        (MonValueKind::Object(_) | MonValueKind::Array(_), _)
+        | (_, MonValueKind::Object(_) | MonValueKind::Array(_)) => Some(parsed),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::match_wildcard_for_single_variants)]
+mod tests {
+    use super::*;
+    use miette::Report;
+
+    impl Member {
+        fn unwrap_pair(self) -> Pair {
+            match self {
+                Member::Pair(p) => p,
+                _ => panic!("Expected Pair, got {self:?}"),
+            }
+        }
+        fn unwrap_type_definition(self) -> TypeDefinition {
+            match self {
                 Member::TypeDefinition(td) => td,
                 _ => panic!("Expected TypeDefinition, got {self:?}"),
             }
@@ -899,6 +1777,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_inline_anchor_on_literals() {
+        let doc = parse_ok(r#"{ obj: &base { a: 1 }, list: [&ids [1, 2], 3] }"#);
+        let members = match doc.root.kind {
+            MonValueKind::Object(m) => m,
+            _ => panic!(),
+        };
+        match &members[0] {
+            Member::Pair(p) => {
+                assert_eq!(p.key, "obj");
+                assert_eq!(p.value.anchor, Some("base".to_string()));
+                assert!(matches!(p.value.kind, MonValueKind::Object(_)));
+            }
+            _ => panic!(),
+        }
+        match &members[1] {
+            Member::Pair(p) => match &p.value.kind {
+                MonValueKind::Array(elements) => {
+                    assert_eq!(p.value.anchor, None);
+                    assert_eq!(elements[0].anchor, Some("ids".to_string()));
+                    assert!(matches!(elements[0].kind, MonValueKind::Array(_)));
+                }
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn test_spread() {
         let doc = parse_ok(r#"{ ...*my_anchor }"#);
@@ -908,7 +1814,10 @@ mod tests {
         };
         assert_eq!(members.len(), 1);
         match &members[0] {
-            Member::Spread(name) => assert_eq!(name, "my_anchor"),
+            Member::Spread(name, span) => {
+                assert_eq!(name, "my_anchor");
+                assert_eq!(*span, miette::SourceSpan::from((2, 13)));
+            }
             _ => panic!(),
         }
     }
@@ -1037,6 +1946,166 @@ mod tests {
         assert_eq!(pair.key, "a-b-c");
     }
 
+    #[test]
+    fn test_normalize_spans_rejects_a_different_shape() {
+        let mut doc = parse_ok("{ a: x, b: [1] }");
+        let original = doc.clone();
+        // Written as `x, c: 2`, which adds a member
+        doc.root.walk_mut(&mut |value| {
+            if let MonValueKind::BareWord(word) = &mut value.kind {
+                *word = "x, c: 2".to_string();
+            }
+        });
+        let changed = doc.clone();
+
+        let err = doc.normalize_spans().unwrap_err();
+        assert_eq!(err.code(), "parser::unstable_formatting");
+        assert_eq!(doc, changed);
+
+        let mut doc = original;
+        let text = doc.normalize_spans().unwrap();
+        assert_eq!(parse_ok(&text), doc);
+    }
+
+    #[test]
+    fn test_document_metadata() {
+        let source = r#"//! schema-version: 2
+// An ordinary comment
+//! owner: platform team
+//! no separator
+import * as s from "./s.mon"
+{
+    //! nested: ignored
+    a: 1
+}"#;
+        let doc = parse_ok(source);
+        assert_eq!(doc.metadata.len(), 2);
+        assert_eq!(doc.metadata["schema-version"], "2");
+        assert_eq!(doc.metadata["owner"], "platform team");
+
+        // The metadata survives a round trip through `Display`.
+        assert_eq!(parse_ok(&doc.to_string()).metadata, doc.metadata);
+
+        // Each document of a multi-document source has its own metadata.
+        let mut parser = Parser::new("//! id: 1\n{}\n---\n//! id: 2\n{}").unwrap();
+        let docs = parser.parse_documents().unwrap();
+        assert_eq!(docs[0].metadata["id"], "1");
+        assert_eq!(docs[1].metadata["id"], "2");
+    }
+
+    #[test]
+    fn test_element_docs() {
+        let source = r#"{
+    list: [
+        // first
+        // element
+        1, // one
+        2,
+        { a: 3 } // three
+    ],
+}"#;
+        let doc = Parser::new(source)
+            .unwrap()
+            .with_comments()
+            .parse_document()
+            .unwrap();
+        let doc_at = |needle: &str| doc.element_docs.get(&source.find(needle).unwrap());
+
+        assert_eq!(
+            doc_at("1,"),
+            Some(&ElementDoc {
+                leading_doc: Some("first\nelement".to_string()),
+                trailing_doc: Some("one".to_string()),
+                closing_doc: None,
+            })
+        );
+        // "// one" is on the line of the previous element
+        assert_eq!(doc_at("2,"), None);
+        assert_eq!(
+            doc_at("{ a"),
+            Some(&ElementDoc {
+                leading_doc: None,
+                trailing_doc: Some("three".to_string()),
+                closing_doc: None,
+            })
+        );
+        assert_eq!(doc.element_docs.len(), 2);
+
+        // Without `with_comments`, no comments are kept.
+        assert!(parse_ok(source).element_docs.is_empty());
+    }
+
+    #[test]
+    fn test_element_docs_next_to_brackets() {
+        let source = r#"{ // after the brace
+    a: 1,
+    list: [ // after the bracket
+        2, // two
+        // before the bracket
+    ],
+    b: 3 // three
+    // before the brace
+}"#;
+        let doc = Parser::new(source)
+            .unwrap()
+            .with_comments()
+            .parse_document()
+            .unwrap();
+        let doc_at = |needle: &str| doc.element_docs.get(&source.find(needle).unwrap());
+
+        assert_eq!(
+            doc_at("1,").and_then(|doc| doc.leading_doc.as_deref()),
+            Some("after the brace")
+        );
+        assert_eq!(
+            doc_at("2,"),
+            Some(&ElementDoc {
+                leading_doc: Some("after the bracket".to_string()),
+                trailing_doc: Some("two".to_string()),
+                closing_doc: Some("before the bracket".to_string()),
+            })
+        );
+        assert_eq!(
+            doc_at("3 "),
+            Some(&ElementDoc {
+                leading_doc: None,
+                trailing_doc: Some("three".to_string()),
+                closing_doc: Some("before the brace".to_string()),
+            })
+        );
+
+        // Formatting keeps every comment
+        let formatted = doc.to_string();
+        for comment in [
+            "after the brace",
+            "after the bracket",
+            "two",
+            "before the bracket",
+            "three",
+            "before the brace",
+        ] {
+            assert!(formatted.contains(comment), "{comment} in {formatted}");
+        }
+        assert!(formatted.trim_end().ends_with("// before the brace\n}"));
+    }
+
+    #[test]
+    fn test_pair_key_span() {
+        let source = r#"{ name: 1, &base: 2, "quoted key" :: Number = 3 }"#;
+        let doc = parse_ok(source);
+        let spans: Vec<&str> = doc
+            .root
+            .kind
+            .unwrap_object()
+            .into_iter()
+            .map(|member| {
+                let span = member.unwrap_pair().key_span;
+                &source[span.offset()..span.offset() + span.len()]
+            })
+            .collect();
+        assert_eq!(spans, ["name", "base", "\"quoted key\""]);
+    }
+
     #[test]
     fn test_pair_with_equals() {
         let doc = parse_ok(r#"{ key = 1 }"#);
@@ -1045,10 +2114,53 @@ mod tests {
         assert!(matches!(pair.value.kind, MonValueKind::Number(_)));
     }
 
+    #[test]
+    fn test_quoted_keys() {
+        let source = r#"{
+    "a\"b": 1,
+    "line\nbreak\ttab\\": 2,
+    "caf\u00e9 \ud83d\ude00": 3,
+    "ключ": 4,
+    "example.com": 5,
+    "https://example.com/a.b?c=d": 6,
+    "C:\\Program Files\\app.exe": 7,
+    a.b: 8,
+}"#;
+        let keys = |doc: MonDocument| -> Vec<String> {
+            doc.root
+                .kind
+                .unwrap_object()
+                .into_iter()
+                .map(|member| member.unwrap_pair().key)
+                .collect()
+        };
+        let doc = parse_ok(source);
+        assert_eq!(
+            keys(doc.clone()),
+            [
+                "a\"b",
+                "line\nbreak\ttab\\",
+                "café 😀",
+                "ключ",
+                "example.com",
+                "https://example.com/a.b?c=d",
+                r"C:\Program Files\app.exe",
+                "a.b",
+            ]
+        );
+
+        // The keys survive formatting
+        assert_eq!(keys(parse_ok(&doc.to_string())), keys(doc));
+
+        // A quoted key is never joined with a dotted part
+        let mut parser = Parser::new(r#"{ "a.b".c: 1 }"#).unwrap();
+        assert!(parser.parse_document().is_err());
+    }
+
     #[test]
     fn test_named_imports() {
         let doc = parse_ok(
-            r#"import { A, &B, C } from "./types.mon"
+            r#"import { A, &B, C as D, &E as f } from "./types.mon"
 {
     x: 1
 }"#,
@@ -1056,18 +2168,108 @@ mod tests {
         assert!(!doc.imports.is_empty());
         match &doc.imports[0].spec {
             ImportSpec::Named(specifiers) => {
-                assert_eq!(specifiers.len(), 3);
+                assert_eq!(specifiers.len(), 4);
                 assert_eq!(specifiers[0].name, "A");
                 assert!(!specifiers[0].is_anchor);
+                assert_eq!(specifiers[0].alias, None);
                 assert_eq!(specifiers[1].name, "B");
                 assert!(specifiers[1].is_anchor);
                 assert_eq!(specifiers[2].name, "C");
                 assert!(!specifiers[2].is_anchor);
+                assert_eq!(specifiers[2].local_name(), "D");
+                assert!(specifiers[3].is_anchor);
+                assert_eq!(specifiers[3].alias.as_deref(), Some("f"));
             }
             _ => panic!("Expected named import"),
         }
     }
 
+    #[test]
+    fn test_multiline_imports_with_comments() {
+        let source = r#"// The types used below
+import { // opening comment
+    A, // the A type
+    // B is an anchor
+    &B as base,
+    C, // trailing comma, then a comment
+    // comment before the closing brace
+} // after the brace
+from "./types.mon"
+import {
+    // only a comment
+} from "./empty.mon"
+{ x: 1 }"#;
+        let doc = parse_ok(source);
+        assert_eq!(doc.imports.len(), 2);
+        let ImportSpec::Named(specifiers) = &doc.imports[0].spec else {
+            panic!("Expected named import");
+        };
+        let names: Vec<&str> = specifiers.iter().map(|s| s.local_name()).collect();
+        assert_eq!(names, ["A", "base", "C"]);
+        assert!(specifiers[1].is_anchor);
+        assert_eq!(
+            &source[doc.imports[0].pos_start..doc.imports[0].pos_end],
+            &source[source.find("import").unwrap()..source.find("\nimport {\n").unwrap()]
+        );
+        assert_eq!(doc.imports[1].spec, ImportSpec::Named(vec![]));
+    }
+
+    #[test]
+    fn test_comments_around_import_clauses() {
+        let source = r#"import { A } from "./a.mon" // core types
+import { B } // before from
+from // between from and the path
+"./b.mon"
+import // after import
+* // after the asterisk
+as // after as
+ns from "./ns.mon" // trailing
+import defaults // after the default name
+from "./defaults.mon"
+// before the root object
+{ a: *defaults }"#;
+        let doc = parse_ok(source);
+        let paths: Vec<&str> = doc.imports.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["./a.mon", "./b.mon", "./ns.mon", "./defaults.mon"]);
+        assert_eq!(doc.imports[2].spec, ImportSpec::Namespace("ns".to_string()));
+        assert_eq!(
+            doc.imports[3].spec,
+            ImportSpec::Default("defaults".to_string())
+        );
+        assert!(doc.metadata.is_empty());
+
+        // An import ends at its path, before any trailing comment.
+        let first = &doc.imports[0];
+        assert_eq!(
+            &source[first.pos_start..first.pos_end],
+            r#"import { A } from "./a.mon""#
+        );
+
+        // A comment right after `from` does not stand in for the path.
+        let mut parser = Parser::new("import { A } from // \"./a.mon\"\n{}").unwrap();
+        let err = parser.parse_document().unwrap_err();
+        assert!(matches!(
+            err,
+            MonError::Parser(e) if matches!(*e, ParserError::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_default_import() {
+        let doc = parse_ok(
+            r#"import defaults from "./defaults.mon"
+{ a: *defaults }"#,
+        );
+        assert_eq!(
+            doc.imports[0].spec,
+            ImportSpec::Default("defaults".to_string())
+        );
+        assert_eq!(
+            doc.imports[0].to_string(),
+            r#"import defaults from "./defaults.mon""#
+        );
+    }
+
     #[test]
     fn test_struct_type_definition() {
         let doc = parse_ok(
@@ -1087,9 +2289,192 @@ User: #struct {
                 assert!(s.fields[0].default_value.is_none());
                 assert_eq!(s.fields[1].name, "age");
                 assert!(s.fields[1].default_value.is_some());
+                assert!(!s.open);
+            }
+            _ => panic!("Expected struct definition"),
+        }
+    }
+
+    #[test]
+    fn test_struct_field_alias() {
+        let doc = parse_ok(r#"{ User: #struct { user_name(String) as "userName", id(Number) } }"#);
+        let members = doc.root.kind.unwrap_object();
+        match members[0].clone().unwrap_type_definition().def_type {
+            TypeDef::Struct(s) => {
+                assert_eq!(s.fields[0].alias.as_deref(), Some("userName"));
+                assert_eq!(s.fields[1].alias, None);
+            }
+            _ => panic!("Expected struct definition"),
+        }
+
+        // The alias is a string, written before the default value
+        for source in [
+            "{ User: #struct { user_name(String) as userName } }",
+            r#"{ User: #struct { user_name(String) = "x" as "userName" } }"#,
+        ] {
+            let mut parser = Parser::new(source).unwrap();
+            assert!(parser.parse_document().is_err(), "source: {source}");
+        }
+    }
+
+    #[test]
+    fn test_field_modifiers() {
+        let doc = parse_ok(
+            r#"{ User: #struct { nick(String)? as "nickName", age(Number)?, role(String) as "r" = "user" } }"#,
+        );
+        let members = doc.root.kind.unwrap_object();
+        let TypeDef::Struct(s) = members[0].clone().unwrap_type_definition().def_type else {
+            panic!("Expected struct definition");
+        };
+        assert!(s.fields[0].optional);
+        assert_eq!(s.fields[0].alias.as_deref(), Some("nickName"));
+        assert!(s.fields[1].optional && s.fields[1].alias.is_none());
+        assert!(!s.fields[2].optional);
+        assert!(s.fields[2].default_value.is_some());
+
+        for (source, reason) in [
+            (
+                r#"{ U: #struct { nick(String)? = "x" } }"#,
+                "an optional field cannot have a default value",
+            ),
+            (
+                r#"{ U: #struct { nick(String) as "n"? } }"#,
+                "`?` must directly follow the field's type",
+            ),
+            (
+                r#"{ U: #struct { nick(String) = "x"? } }"#,
+                "`?` must directly follow the field's type",
+            ),
+            (
+                r#"{ U: #struct { nick(String) = "x" as "n" } }"#,
+                "`as \"key\"` must come before the default value",
+            ),
+        ] {
+            let err = Parser::new(source)
+                .and_then(|mut parser| parser.parse_document())
+                .unwrap_err();
+            let MonError::Parser(err) = err else {
+                panic!("Expected a parser error, got {err:?}");
+            };
+            let ParserError::InvalidFieldModifiers {
+                field, reason: r, ..
+            } = *err
+            else {
+                panic!("Expected InvalidFieldModifiers, got {err:?}");
+            };
+            assert_eq!((field.as_str(), r.as_str()), ("nick", reason));
+        }
+    }
+
+    #[test]
+    fn test_guarded_members() {
+        let doc = parse_ok(
+            r#"{ [@if(ENV == "prod")] replicas: 3, [@if(DEBUG)] [@if(ENV != "ci")] ...*debug }"#,
+        );
+        let members = doc.root.kind.unwrap_object();
+        let Member::Guarded(guard, member) = &members[0] else {
+            panic!("Expected a guarded member, got {:?}", members[0]);
+        };
+        assert_eq!(guard.variable, "ENV");
+        assert_eq!(guard.condition, GuardCondition::Equals("prod".to_string()));
+        assert_eq!((guard.pos_start, guard.pos_end), (2, 22));
+        assert!(matches!(&**member, Member::Pair(pair) if pair.key == "replicas"));
+
+        let Member::Guarded(guard, member) = &members[1] else {
+            panic!("Expected a guarded member, got {:?}", members[1]);
+        };
+        assert_eq!(guard.condition, GuardCondition::IsSet);
+        assert!(matches!(
+            &**member,
+            Member::Guarded(guard, _) if guard.condition == GuardCondition::NotEquals("ci".to_string())
+        ));
+        assert!(matches!(members[1].unguarded(), Member::Spread(name, _) if name == "debug"));
+
+        for source in [
+            "{ [@when(ENV)] a: 1 }",
+            "{ [@if(ENV = \"prod\")] a: 1 }",
+            "{ [@if(ENV == prod)] a: 1 }",
+            "{ [@if(ENV)] }",
+            "{ [if(ENV)] a: 1 }",
+        ] {
+            let mut parser = Parser::new(source).unwrap();
+            assert!(parser.parse_document().is_err(), "source: {source}");
+        }
+    }
+
+    #[test]
+    fn test_struct_extends() {
+        let doc = parse_ok("{ Admin: #struct extends User { level(Number) }, Plain: #struct {} }");
+        let members = doc.root.kind.unwrap_object();
+        match members[0].clone().unwrap_type_definition().def_type {
+            TypeDef::Struct(s) => {
+                assert_eq!(s.extends.as_deref(), Some("User"));
+                assert_eq!(s.fields.len(), 1);
+            }
+            _ => panic!("Expected struct definition"),
+        }
+        match members[1].clone().unwrap_type_definition().def_type {
+            TypeDef::Struct(s) => assert_eq!(s.extends, None),
+            _ => panic!("Expected struct definition"),
+        }
+
+        let doc = parse_ok("{ Admin: #struct extends schemas.User {} }");
+        match doc.root.kind.unwrap_object()[0]
+            .clone()
+            .unwrap_type_definition()
+            .def_type
+        {
+            TypeDef::Struct(s) => assert_eq!(s.extends.as_deref(), Some("schemas.User")),
+            _ => panic!("Expected struct definition"),
+        }
+    }
+
+    #[test]
+    fn test_struct_one_of() {
+        let doc = parse_ok(
+            "{ Auth: #struct { token(String), password(String), user(String) } one_of(token, password,) }",
+        );
+        let members = doc.root.kind.unwrap_object();
+        match members[0].clone().unwrap_type_definition().def_type {
+            TypeDef::Struct(s) => {
+                assert_eq!(
+                    s.one_of,
+                    vec![vec!["token".to_string(), "password".to_string()]]
+                );
             }
             _ => panic!("Expected struct definition"),
         }
+
+        // The fields of a base struct are only known to the resolver
+        parse_ok("{ Admin: #struct extends User { level(Number) } one_of(name, level) }");
+
+        // Groups list at least one field, and only fields of the struct
+        for source in [
+            "{ Auth: #struct { token(String) } one_of() }",
+            "{ Auth: #struct { token(String) } one_of(token, password) }",
+        ] {
+            let mut parser = Parser::new(source).unwrap();
+            assert!(parser.parse_document().is_err(), "source: {source}");
+        }
+    }
+
+    #[test]
+    fn test_open_struct_type_definition() {
+        for source in [
+            "{ User: #struct { name(String), ... } }",
+            "{ User: #struct { name(String), ..., } }",
+            "{ User: #struct { ... } }",
+        ] {
+            let members = parse_ok(source).root.kind.unwrap_object();
+            match members[0].clone().unwrap_type_definition().def_type {
+                TypeDef::Struct(s) => assert!(s.open, "source: {source}"),
+                _ => panic!("Expected struct definition"),
+            }
+        }
+
+        // The `...` must be the last entry.
+        let mut parser = Parser::new("{ User: #struct { ..., name(String) } }").unwrap();
+        assert!(parser.parse_document().is_err());
     }
 
     #[test]
@@ -1103,12 +2488,125 @@ Status: #enum { Active, Inactive, Pending }
         let td = members[0].clone().unwrap_type_definition();
         match td.def_type {
             TypeDef::Enum(e) => {
-                assert_eq!(e.variants, vec!["Active", "Inactive", "Pending"]);
+                let names: Vec<&str> = e.variants.iter().map(|(name, _)| name.as_str()).collect();
+                assert_eq!(names, vec!["Active", "Inactive", "Pending"]);
+                assert!(e.variants.iter().all(|(_, value)| value.is_none()));
             }
             _ => panic!("Expected enum definition"),
         }
     }
 
+    #[test]
+    fn test_alias_type_definition() {
+        let source = "{ Entry: #alias [String, Number], Port: #alias Number }";
+        let doc = parse_ok(source);
+        let formatted = doc.to_string();
+        let members = doc.root.kind.unwrap_object();
+        let td = members[0].clone().unwrap_type_definition();
+        let TypeDef::Alias(TypeSpec::Collection(types, span)) = &td.def_type else {
+            panic!("Expected a collection alias, got {:?}", td.def_type);
+        };
+        assert_eq!(types.len(), 2);
+        assert_eq!(
+            (span.offset(), span.len()),
+            (source.find('[').unwrap(), "[String, Number]".len())
+        );
+        let td = members[1].clone().unwrap_type_definition();
+        assert!(matches!(td.def_type, TypeDef::Alias(TypeSpec::Simple(ref n, _)) if n == "Number"));
+
+        // The formatter writes aliases back in the same form
+        assert_eq!(parse_ok(&formatted).to_string(), formatted);
+        assert!(formatted.contains("Entry: #alias [String, Number]"));
+    }
+
+    #[test]
+    fn test_enum_discriminants() {
+        let source = r#"{ Code: #enum { Ok = 200, Moved = "301", Unknown } }"#;
+        let doc = parse_ok(source);
+        let formatted = doc.to_string();
+        let members = doc.root.kind.unwrap_object();
+        let TypeDef::Enum(e) = members[0].clone().unwrap_type_definition().def_type else {
+            panic!("Expected enum definition");
+        };
+        assert!(e.has_variant("Unknown") && !e.has_variant("Teapot"));
+        assert_eq!(
+            e.discriminant("Ok").map(|v| &v.kind),
+            Some(&MonValueKind::Number(200.0))
+        );
+        assert_eq!(
+            e.discriminant("Moved").map(|v| &v.kind),
+            Some(&MonValueKind::String("301".into()))
+        );
+        assert_eq!(e.discriminant("Unknown"), None);
+        assert_eq!(parse_ok(&formatted).to_string(), formatted);
+        assert!(formatted.contains("Ok = 200"), "{formatted}");
+
+        let mut parser = Parser::new("{ Code: #enum { Ok = [1] } }").unwrap();
+        assert!(parser.parse_document().is_err());
+    }
+
+    #[test]
+    fn test_parser_limits() {
+        let parse = |source: &str, limits: ParserLimits| {
+            Parser::new_with_limits(source, "limits.mon".to_string(), limits)
+                .and_then(|mut parser| parser.parse_document())
+        };
+        let source = r#"{ a: [1, 2, 3], b: { c: "four", "quoted key": 5 } }"#;
+        // No limits by default
+        assert!(parse(source, ParserLimits::default()).is_ok());
+        assert!(parse(
+            source,
+            ParserLimits {
+                max_members: Some(4),
+                max_array_len: Some(3),
+                max_string_len: Some(10),
+            }
+        )
+        .is_ok());
+
+        for (limits, kind, at) in [
+            (
+                ParserLimits {
+                    max_members: Some(3),
+                    ..ParserLimits::default()
+                },
+                LimitKind::Members,
+                "\"quoted key\"",
+            ),
+            (
+                ParserLimits {
+                    max_array_len: Some(2),
+                    ..ParserLimits::default()
+                },
+                LimitKind::ArrayLength,
+                "3",
+            ),
+            (
+                ParserLimits {
+                    max_string_len: Some(3),
+                    ..ParserLimits::default()
+                },
+                LimitKind::StringLength,
+                "\"four\"",
+            ),
+        ] {
+            let Err(MonError::Parser(err)) = parse(source, limits) else {
+                panic!("Expected {kind} limit to be exceeded");
+            };
+            let ParserError::LimitExceeded {
+                kind: found, span, ..
+            } = *err
+            else {
+                panic!("Expected LimitExceeded, got {err:?}");
+            };
+            assert_eq!(found, kind);
+            assert_eq!(
+                (span.offset(), span.len()),
+                (source.find(at).unwrap(), at.len())
+            );
+        }
+    }
+
     #[test]
     fn test_validation_on_pair() {
         let doc = parse_ok(r#"{ key :: Number = 42 }"#);
@@ -1121,6 +2619,26 @@ Status: #enum { Active, Inactive, Pending }
         )
     }
 
+    #[test]
+    fn test_repeat_type_spec() {
+        let doc = parse_ok(r#"{ key :: [String; 3] = ["a", "b", "c"] }"#);
+        let pair = doc.root.kind.unwrap_object().remove(0).unwrap_pair();
+
+        assert_eq!(
+            pair.validation.unwrap(),
+            TypeSpec::Repeat(
+                Box::new(TypeSpec::Simple("String".into(), (10, 6).into())),
+                3,
+                (9, 11).into()
+            )
+        );
+
+        let mut parser = Parser::new(r#"{ key :: [String; 1.5] = [] }"#).unwrap();
+        assert!(parser.parse_document().is_err());
+        let mut parser = Parser::new(r#"{ key :: [String, Number; 2] = [] }"#).unwrap();
+        assert!(parser.parse_document().is_err());
+    }
+
     #[test]
     fn test_nested_objects_and_arrays() {
         let doc = parse_ok(r#"{ obj: { a: 1, b: 2 }, arr: [1, 2, 3] }"#);
@@ -1147,6 +2665,216 @@ Status: #enum { Active, Inactive, Pending }
         }
     }
 
+    #[test]
+    fn test_namespaced_enum_value() {
+        let doc = parse_ok(r#"{ status: $schemas.Status.Active }"#);
+        let pair = doc.root.kind.unwrap_object().remove(0).unwrap_pair();
+        match pair.value.kind {
+            MonValueKind::EnumValue {
+                enum_name,
+                variant_name,
+            } => {
+                assert_eq!(enum_name, "schemas.Status");
+                assert_eq!(variant_name, "Active");
+            }
+            _ => panic!("Expected enum value"),
+        }
+    }
+
+    #[test]
+    fn test_parse_documents() {
+        let mut parser = Parser::new("---\n{ a: 1 }\n---\n{ b: 2 }\n---\n{ c: 3 }\n").unwrap();
+        let documents = parser.parse_documents().unwrap();
+        assert_eq!(documents.len(), 3);
+        let pair = documents[1]
+            .root
+            .kind
+            .clone()
+            .unwrap_object()
+            .remove(0)
+            .unwrap_pair();
+        assert_eq!(pair.key, "b");
+    }
+
+    #[test]
+    fn test_parse_document_rejects_separator() {
+        let mut parser = Parser::new("{ a: 1 }\n---\n{ b: 2 }").unwrap();
+        assert!(parser.parse_document().is_err());
+    }
+
+    #[test]
+    fn test_display_is_indented_mon() {
+        let doc =
+            parse_ok(r#"import * as s from "./s.mon" { &a: 1, b :: [Number...] = [1, 2], c: {} }"#);
+        assert_eq!(
+            doc.to_string(),
+            "import * as s from \"./s.mon\"\n\n{\n    &a: 1,\n    b :: [Number...] = [\n        1,\n        2,\n    ],\n    c: {},\n}"
+        );
+    }
+
+    #[test]
+    fn test_to_mon_with_options() {
+        use crate::ast::{MonFormatOptions, QuoteStyle};
+
+        let source = r#"{ z: 1, a: [true], ...*base, c: 2, b: 3 }"#;
+        let doc = parse_ok(source);
+
+        // The defaults match `Display`
+        assert_eq!(doc.to_mon(&MonFormatOptions::default()), doc.to_string());
+
+        let options = MonFormatOptions {
+            indent: 2,
+            sort_keys: true,
+            trailing_comma: false,
+            quote_style: QuoteStyle::Always,
+        };
+        let formatted = doc.to_mon(&options);
+        // Keys are only sorted up to the spread, which keeps its place
+        assert_eq!(
+            formatted,
+            "{\n  \"a\": [\n    true\n  ],\n  \"z\": 1,\n  ...*base,\n  \"b\": 3,\n  \"c\": 2\n}"
+        );
+        assert_eq!(parse_ok(&formatted).to_mon(&options), formatted);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let source = r#"
+import { User, &defaults } from "./types.mon"
+import * as schemas from "mon:types/schemas"
+
+{
+    Status: #enum { Active, Inactive },
+    Profile: #struct {
+        name(String),
+        tags([String...]) = ["a", "b"],
+        status(Status) = $Status.Active,
+        user_name(String) as "userName" = "anon",
+    },
+    Open: #struct { id(Number), ... },
+    Auth: #struct { token(String), password(String) } one_of(token, password),
+    Admin: #struct extends schemas.User { level(Number) },
+    [@if(ENV == "prod")] [@if(REGION != "eu")] replicas: 3,
+    [@if(DEBUG)] ...*debug,
+    &base: { host: "local\"host\"\n", port: 8080, ratio: -0.5 },
+    "quoted-key": null,
+    limits: { timeout: 1.5s, max_size: 10MiB },
+    a.b.c: off,
+    config: { ...*base, port: 9000 },
+    list: [1, ...*items, &inline { x: true }],
+    user :: schemas.User = { status: $schemas.Status.Active, ref: *base.host },
+}"#;
+        let first = parse_ok(source).to_string();
+        let second = parse_ok(&first).to_string();
+        assert_eq!(first, second);
+
+        // Alias and spread names that are not identifiers stay quoted
+        let source =
+            r#"{ "my key": 1, b: *"my key", c: *"é", d: [...*"my list"], e: { ...*"é" } }"#;
+        let first = parse_ok(source).to_string();
+        assert!(first.contains(r#"*"my key""#), "{first}");
+        assert_eq!(parse_ok(&first).to_string(), first);
+    }
+
+    #[test]
+    fn test_comment_round_trip() {
+        use crate::ast::MonFormatOptions;
+
+        let parse_with_comments = |source: &str| {
+            Parser::new(source)
+                .unwrap()
+                .with_comments()
+                .parse_document()
+                .unwrap()
+        };
+        let source = r#"{
+    // The first key
+    a: 1, // keep me
+    b: { c: [
+        2, // two
+        /// three
+        3
+    ] }, ...*base, //! spread
+    d: "x" //
+}"#;
+        let formatted = parse_with_comments(source).to_string();
+        assert_eq!(
+            formatted,
+            r#"{
+    // The first key
+    a: 1, // keep me
+    b: {
+        c: [
+            2, // two
+            /// three
+            3,
+        ],
+    },
+    ...*base, //! spread
+    d: "x", //
+}"#
+        );
+        // Formatting again keeps every comment where it is.
+        assert_eq!(parse_with_comments(&formatted).to_string(), formatted);
+
+        // Without a trailing comma, the comment still ends the line.
+        let options = MonFormatOptions {
+            trailing_comma: false,
+            ..MonFormatOptions::default()
+        };
+        let doc = parse_with_comments("{ a: 1, // one\n b: 2 // two\n}");
+        let formatted = doc.to_mon(&options);
+        assert_eq!(formatted, "{\n    a: 1, // one\n    b: 2 // two\n}");
+        assert_eq!(parse_with_comments(&formatted).to_mon(&options), formatted);
+
+        // A single-line layout has no room for comments.
+        assert_eq!(format!("{}", doc.root), "{ a: 1, b: 2 }");
+    }
+
+    #[test]
+    fn test_number_literal_round_trip() {
+        let source = "{ big: 1.23e10, small: 5E-3, price: 0.50, &neg: -1e3, list: [1.0, 42] }";
+        let mut doc = Parser::new(source)
+            .unwrap()
+            .with_number_literals()
+            .parse_document()
+            .unwrap();
+        assert_eq!(
+            doc.number_literals[&source.find("1.23e10").unwrap()],
+            "1.23e10"
+        );
+        assert_eq!(
+            doc.to_string(),
+            "{\n    big: 1.23e10,\n    small: 5E-3,\n    price: 0.50,\n    &neg: -1e3,\n    list: [\n        1.0,\n        42,\n    ],\n}"
+        );
+
+        // A number that no longer has its parsed value is written as it is
+        let MonValueKind::Object(members) = &mut doc.root.kind else {
+            panic!("Expected an object");
+        };
+        let Member::Pair(big) = &mut members[0] else {
+            panic!("Expected a pair");
+        };
+        big.value.kind = MonValueKind::Number(7.0);
+        assert!(doc.to_string().starts_with("{\n    big: 7,\n"));
+
+        // Without `with_number_literals`, numbers are written from their value
+        assert!(parse_ok(source).to_string().contains("big: 12300000000,"));
+    }
+
+    #[test]
+    fn test_all_tokens_keeps_trivia() {
+        let parser = Parser::new("{ a: 1 } // done").unwrap();
+        let tokens = parser.all_tokens();
+        assert!(tokens.iter().any(|t| t.ttype == TokenType::Whitespace));
+        let comment = tokens
+            .iter()
+            .find(|t| matches!(t.ttype, TokenType::Comment(_)))
+            .unwrap();
+        assert_eq!((comment.pos_start, comment.pos_end), (9, 16));
+        assert_eq!(tokens.last().unwrap().ttype, TokenType::Eof);
+    }
+
     #[test]
     fn test_complex_document() {
         let doc = parse_ok(
@@ -1164,4 +2892,48 @@ import { &A, B } from "./types.mon"
         let members = doc.root.kind.unwrap_object();
         assert_eq!(members.len(), 4);
     }
+
+    #[test]
+    fn test_parse_document_streaming() {
+        struct Collect<'c>(&'c mut Vec<(String, MonValue)>);
+
+        impl DocumentSink for Collect<'_> {
+            fn on_element(
+                &mut self,
+                key: &str,
+                _validation: Option<&TypeSpec>,
+                element: MonValue,
+            ) -> Result<(), MonError> {
+                self.0.push((key.to_string(), element));
+                Ok(())
+            }
+        }
+
+        let mut elements = Vec::new();
+        let doc = {
+            let mut parser = Parser::new_with_name(
+                "{ a: 1, records: [1, [2], 3], &kept: [4], nested: { inner: [5] } }",
+                "test.mon".to_string(),
+            )
+            .unwrap();
+            parser
+                .parse_document_streaming(Collect(&mut elements))
+                .unwrap()
+        };
+
+        assert_eq!(elements.len(), 3);
+        assert!(elements.iter().all(|(key, _)| key == "records"));
+        assert!(matches!(elements[1].1.kind, MonValueKind::Array(_)));
+
+        let members = doc.root.kind.unwrap_object();
+        let array_len = |index: usize| match &members[index] {
+            Member::Pair(pair) => match &pair.value.kind {
+                MonValueKind::Array(elements) => elements.len(),
+                _ => panic!("Expected an array"),
+            },
+            _ => panic!("Expected a pair"),
+        };
+        assert_eq!(array_len(1), 0);
+        assert_eq!(array_len(2), 1);
+    }
 }
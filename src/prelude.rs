@@ -0,0 +1,41 @@
+//! # The `mon-core` Prelude
+//!
+//! Re-exports the types most programs working with MON need, so that a single glob import
+//! replaces paths into [`ast`](crate::ast), [`error`](crate::error) and
+//! [`serialization`](crate::serialization):
+//!
+//! ```rust
+//! use mon_core::prelude::*;
+//!
+//! # #[cfg(feature = "resolver")]
+//! # fn main() -> Result<(), MonError> {
+//! let result: AnalysisResult = mon_core::analyze("{ port: 8080 }", "config.mon")?;
+//! let Value::Object(data) = result.to_value() else {
+//!     unreachable!("the root of a document is an object");
+//! };
+//! assert_eq!(data["port"], Value::Number(8080.0));
+//!
+//! let MonValueKind::Object(members) = &result.document.root.kind else {
+//!     unreachable!();
+//! };
+//! assert!(matches!(&members[0], Member::Pair(Pair { key, .. }) if key == "port"));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "resolver"))]
+//! # fn main() {}
+//! ```
+//!
+#![cfg_attr(
+    feature = "resolver",
+    doc = "[`AnalysisResult`] is only available with the `resolver` feature."
+)]
+#![cfg_attr(
+    not(feature = "resolver"),
+    doc = "`AnalysisResult` is only available with the `resolver` feature."
+)]
+
+#[cfg(feature = "resolver")]
+pub use crate::api::AnalysisResult;
+pub use crate::ast::{Member, MonValue, MonValueKind, Pair, TypeSpec};
+pub use crate::error::MonError;
+pub use crate::serialization::Value;
@@ -68,15 +68,18 @@
 //! # }
 //! ```
 use crate::ast::{
-    ImportSpec, ImportStatement, Member, MonDocument, MonValue, MonValueKind,
-    SymbolTable as AstSymbolTable, TypeDef, TypeSpec,
+    ImportSpec, ImportStatement, Member, MonDocument, MonValue, MonValueKind, QuantityKind,
+    StructDef, SymbolTable as AstSymbolTable, TypeDef, TypeDefinition, TypeSpec,
 };
-use crate::error::{ResolverError, ValidationError};
+use crate::error::{MonError, MonWarning, ResolverError, ValidationError};
+use crate::lexer::LexerOptions;
+use crate::utils::{decode_bytes, source_span};
 use log::warn;
-use miette::NamedSource;
-use std::collections::HashMap;
+use miette::{NamedSource, SourceSpan};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Traverses a [`MonDocument`] to resolve imports, aliases, and spreads, and to validate data against schemas.
 ///
@@ -143,12 +146,99 @@ pub struct Resolver {
     resolving_stack: Vec<(PathBuf, Option<ImportStatement>)>,
     // Global symbol table for types
     pub symbol_table: AstSymbolTable,
+    // The types the document being resolved defines or imports by name, with the file and
+    // name span of each definition
+    scope_types: HashMap<String, (PathBuf, SourceSpan)>,
     // Global map for anchors
     pub anchors: HashMap<String, MonValue>,
+    // Import edges discovered so far, as (importing file, imported file)
+    pub dependencies: Vec<(PathBuf, PathBuf)>,
+    // Warnings collected while resolving
+    pub warnings: Vec<MonWarning>,
 
     builtin_schemas_path: PathBuf,
+    // Whether to suggest close type names for undefined types
+    strict: bool,
+    // Whether `null` keeps the other value when object keys collide
+    null_as_absent: bool,
+    options: ResolveOptions,
+    // Validation errors collected instead of returned, see `resolve_collect`
+    collected_errors: Option<Vec<ValidationError>>,
+    // Phase timings, only measured when set, see `analyze_with_metrics`
+    pub(crate) metrics: Option<AnalyzeMetrics>,
 }
 
+/// Options that change where a [`Resolver`] looks for imported modules.
+///
+/// # Example
+///
+/// ```rust
+/// use mon_core::resolver::{ResolveOptions, Resolver};
+///
+/// let mut resolver = Resolver::new();
+/// resolver.set_options(ResolveOptions::default().with_import_root("/project/shared"));
+/// assert_eq!(resolver.options().import_roots.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResolveOptions {
+    /// Directories that relative imports are looked up in, in order, when the module does not
+    /// exist next to the importing file, e.g. a project root or shared include directories.
+    pub import_roots: Vec<PathBuf>,
+    /// The variables that guards like `[@if(ENV == "prod")]` before object members are checked
+    /// against. A member whose guard does not hold is dropped.
+    pub env: HashMap<String, String>,
+    /// The options the document's imported modules are tokenized with, e.g. boolean aliases.
+    pub lexer: LexerOptions,
+}
+
+impl ResolveOptions {
+    /// Adds `root` to the end of [`ResolveOptions::import_roots`].
+    #[must_use]
+    pub fn with_import_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.import_roots.push(root.into());
+        self
+    }
+
+    /// Sets the variable `name` of [`ResolveOptions::env`] to `value`.
+    #[must_use]
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the [`ResolveOptions::lexer`] options.
+    #[must_use]
+    pub fn with_lexer_options(mut self, lexer: LexerOptions) -> Self {
+        self.lexer = lexer;
+        self
+    }
+}
+
+/// How long each phase of an analysis took, see [`analyze_with_metrics`](crate::api::analyze_with_metrics).
+///
+/// The times add up over the document and all modules it imports. `resolve_time` is the time
+/// spent resolving imports, anchors, aliases and spreads, without lexing, parsing and
+/// validating the imported modules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnalyzeMetrics {
+    /// Time spent turning source text into tokens.
+    pub lex_time: Duration,
+    /// Time spent building documents from the tokens.
+    pub parse_time: Duration,
+    /// Time spent resolving the parsed documents.
+    pub resolve_time: Duration,
+    /// Time spent validating values against their types.
+    pub validate_time: Duration,
+    /// The number of imported files read from disk.
+    pub files_read: usize,
+}
+
+/// The names of the built-in types understood by the validator.
+const BUILTIN_TYPES: &[&str] = &[
+    "String", "Number", "Int", "UInt", "Duration", "ByteSize", "Color", "Bytes", "Boolean", "Null",
+    "Object", "Array", "Any",
+];
+
 impl Resolver {
     /// Creates a new `Resolver` with a default search path for built-in schemas.
     ///
@@ -166,11 +256,60 @@ impl Resolver {
             resolved_documents: HashMap::new(),
             resolving_stack: Vec::new(),
             symbol_table: AstSymbolTable::new(),
+            scope_types: HashMap::new(),
             anchors: HashMap::new(),
+            dependencies: Vec::new(),
+            warnings: Vec::new(),
             builtin_schemas_path: path,
+            strict: false,
+            null_as_absent: false,
+            options: ResolveOptions::default(),
+            collected_errors: None,
+            metrics: None,
         }
     }
 
+    /// Enables or disables strict mode.
+    ///
+    /// In strict mode, a [`ValidationError::UndefinedType`] error suggests the closest built-in
+    /// or defined type name, which helps with typos such as `Strign`. Lenient mode is the default.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns whether the resolver is running in strict mode.
+    #[must_use]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Sets whether `null` means "unset" when object spreads are merged.
+    ///
+    /// By default, the later of two values for the same key wins, so a `null` from a spread
+    /// overrides a local value written before the spread, and a local `null` overrides a value
+    /// from a spread. With `treat_null_as_absent`, a `null` never replaces another value, in
+    /// either order. Two `null`s still give `null`.
+    pub fn set_treat_null_as_absent(&mut self, treat_null_as_absent: bool) {
+        self.null_as_absent = treat_null_as_absent;
+    }
+
+    /// Returns whether `null` is treated as absent when object spreads are merged.
+    #[must_use]
+    pub fn treats_null_as_absent(&self) -> bool {
+        self.null_as_absent
+    }
+
+    /// Sets the options used for all following resolutions, see [`ResolveOptions`].
+    pub fn set_options(&mut self, options: ResolveOptions) {
+        self.options = options;
+    }
+
+    /// Returns the options of this resolver.
+    #[must_use]
+    pub fn options(&self) -> &ResolveOptions {
+        &self.options
+    }
+
     /// Determines the default path for built-in schemas, used for resolving `mon:` URIs.
     ///
     /// The lookup order is as follows:
@@ -216,6 +355,9 @@ impl Resolver {
     /// - **mon-URIs**: Paths starting with `mon:` are resolved relative to the `builtin_schemas_path`.
     ///   For example, `mon:types/linter` becomes `{builtin_schemas_path}/types/linter.mon`.
     /// - **File Paths**: Relative paths are joined with the `current_dir`. Absolute paths are used as is.
+    ///   If no file exists there, each of the [`ResolveOptions::import_roots`] is tried in order.
+    ///   If none has the file either, the path next to the importing file is returned, so the
+    ///   error names the expected location.
     fn resolve_import_path(&self, import_path: &str, current_dir: &Path) -> PathBuf {
         // Handle mon: URI
         if let Some(builtin_path) = import_path.strip_prefix("mon:") {
@@ -227,7 +369,16 @@ impl Resolver {
         }
 
         // Handle relative/absolute paths normally
-        current_dir.join(import_path)
+        let path = current_dir.join(import_path);
+        if path.exists() || Path::new(import_path).is_absolute() {
+            return path;
+        }
+        self.options
+            .import_roots
+            .iter()
+            .map(|root| root.join(import_path))
+            .find(|candidate| candidate.exists())
+            .unwrap_or(path)
     }
     /// Recursively resolves a [`MonDocument`].
     ///
@@ -245,7 +396,10 @@ impl Resolver {
     /// # Errors
     ///
     /// This function can return a [`ResolverError`] if any of the following occurs:
-    /// - A module specified in an `import` statement cannot be found ([`ResolverError::ModuleNotFound`]).
+    /// - A module specified in an `import` statement cannot be found ([`ResolverError::ModuleNotFound`])
+    ///   or read ([`ResolverError::ModulePermissionDenied`], [`ResolverError::ModuleNotUtf8`],
+    ///   [`ResolverError::ModuleReadFailed`]).
+    /// - A module imports itself ([`ResolverError::SelfImport`]).
     /// - A circular dependency is detected between imported modules ([`ResolverError::CircularDependency`]).
     /// - An anchor, alias, or spread is used incorrectly.
     /// - A validation error occurs when checking data against a schema ([`ResolverError::Validation`]).
@@ -257,6 +411,148 @@ impl Resolver {
         file_path: PathBuf,
         causing_import: Option<ImportStatement>,
     ) -> Result<MonDocument, ResolverError> {
+        self.resolve_document(document, source_text, file_path, causing_import, None)
+    }
+
+    /// Resolves a [`MonDocument`] like [`Resolver::resolve`], and validates its root against
+    /// the type `root_type`, see [`Resolver::validate_root`].
+    ///
+    /// Unlike calling both one after the other, bare words in the root can name variants of
+    /// enum fields of `root_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ResolverError`] if resolution or validation fails.
+    pub fn resolve_as(
+        &mut self,
+        document: MonDocument,
+        source_text: &str,
+        file_path: PathBuf,
+        root_type: &str,
+    ) -> Result<MonDocument, ResolverError> {
+        self.resolve_document(document, source_text, file_path, None, Some(root_type))
+    }
+
+    /// Parses `source` and resolves it like [`Resolver::resolve`], in one step.
+    ///
+    /// `file_name` names the document in errors, and its relative imports are looked up next
+    /// to it. A relative `file_name` is taken to be in the current directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ResolverError`] if parsing or resolution fails. Errors from parsing `source`
+    /// are wrapped in [`ResolverError::WrappedParserError`], and a relative `file_name` needs
+    /// the current directory, see [`ResolverError::CurrentDirUnavailable`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use mon_core::resolver::Resolver;
+    ///
+    /// # fn main() -> Result<(), mon_core::error::ResolverError> {
+    /// let document = Resolver::new().resolve_str("{ &port: 8080, server: { port: *port } }", "app.mon")?;
+    /// assert!(document.root.kind.as_object().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_str(
+        &mut self,
+        source: &str,
+        file_name: &str,
+    ) -> Result<MonDocument, ResolverError> {
+        let document = crate::parser::Parser::new_with_options(
+            source,
+            file_name.to_string(),
+            self.options.lexer.clone(),
+        )?
+        .parse_document()?;
+        self.resolve(document, source, absolute_file_path(file_name)?, None)
+    }
+
+    /// Resolves a [`MonDocument`] like [`Resolver::resolve`], but keeps validating after a
+    /// validation error and returns every one found, in document order.
+    ///
+    /// A value that fails validation is left as it was, so errors nested inside it are only
+    /// reported once it has the right shape.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ResolverError`] if resolution fails for any other reason than validation,
+    /// e.g. a missing import or anchor.
+    pub fn resolve_collect(
+        &mut self,
+        document: MonDocument,
+        source_text: &str,
+        file_path: PathBuf,
+    ) -> Result<(MonDocument, Vec<ValidationError>), ResolverError> {
+        self.collected_errors = Some(Vec::new());
+        let result = self.resolve_document(document, source_text, file_path, None, None);
+        let errors = self.collected_errors.take().unwrap_or_default();
+        result.map(|document| (document, errors))
+    }
+
+    /// Starts timing a phase if metrics are measured, see [`Resolver::stop_timer`].
+    fn start_timer(&self) -> Option<Instant> {
+        self.metrics.is_some().then(Instant::now)
+    }
+
+    /// Adds the time since `start` to the `phase` of the metrics, if they are measured.
+    fn stop_timer(
+        &mut self,
+        start: Option<Instant>,
+        phase: impl FnOnce(&mut AnalyzeMetrics) -> &mut Duration,
+    ) {
+        if let (Some(metrics), Some(start)) = (&mut self.metrics, start) {
+            *phase(metrics) += start.elapsed();
+        }
+    }
+
+    /// Counts a module file read from disk, if metrics are measured.
+    fn count_file_read(&mut self) {
+        if let Some(metrics) = &mut self.metrics {
+            metrics.files_read += 1;
+        }
+    }
+
+    /// Records a validation error when collecting errors, see [`Resolver::resolve_collect`].
+    /// Other errors, and all errors when not collecting, are passed through.
+    fn collect_validation(
+        &mut self,
+        result: Result<(), ResolverError>,
+    ) -> Result<(), ResolverError> {
+        match (result, &mut self.collected_errors) {
+            (Err(ResolverError::Validation(error)), Some(errors)) => {
+                errors.push(error);
+                Ok(())
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn resolve_document(
+        &mut self,
+        mut document: MonDocument,
+        source_text: &str,
+        file_path: PathBuf,
+        causing_import: Option<ImportStatement>,
+        root_type: Option<&str>,
+    ) -> Result<MonDocument, ResolverError> {
+        // A file importing itself is the shortest cycle, report it on its own
+        if let Some(import) = &causing_import {
+            if self
+                .resolving_stack
+                .last()
+                .is_some_and(|(p, _)| p == &file_path)
+            {
+                return Err(ResolverError::SelfImport {
+                    path: import.path.clone(),
+                    src: NamedSource::new(file_path.to_string_lossy(), source_text.to_string())
+                        .into(),
+                    span: source_span(import.pos_start, import.pos_end),
+                });
+            }
+        }
+
         // Add the current file to the resolving stack to detect cycles
         if let Some((_, Some(existing_causing_import))) =
             self.resolving_stack.iter().find(|(p, _)| p == &file_path)
@@ -270,103 +566,29 @@ impl Resolver {
             return Err(ResolverError::CircularDependency {
                 cycle: format!("{} -> {}", cycle_str, file_path.to_string_lossy()),
                 src: NamedSource::new(file_path.to_string_lossy(), source_text.to_string()).into(),
-                span: (
+                span: source_span(
                     existing_causing_import.pos_start,
-                    existing_causing_import.pos_end - existing_causing_import.pos_start,
-                )
-                    .into(),
+                    existing_causing_import.pos_end,
+                ),
             });
         }
         self.resolving_stack
             .push((file_path.clone(), causing_import)); // Push with the provided causing_import
 
-        // 1. Process imports
-        let current_dir = file_path.parent().unwrap_or_else(|| Path::new("."));
-        let source_arc = Arc::new(source_text.to_string());
-        for import_statement in &document.imports {
-            let imported_path_str = import_statement.path.trim_matches('"');
+        // Members whose guard does not hold are not part of the document at all
+        apply_guards(&mut document.root, &self.options.env);
 
-            // for mon: ...
-            let absolute_imported_path = self.resolve_import_path(imported_path_str, current_dir);
-            if self
-                .resolved_documents
-                .contains_key(&absolute_imported_path)
-            {
-                continue;
-            }
-            let imported_source_text =
-                std::fs::read_to_string(&absolute_imported_path).map_err(|_| {
-                    ResolverError::ModuleNotFound {
-                        path: imported_path_str.to_string(),
-                        src: Arc::from(NamedSource::new(
-                            file_path.to_string_lossy(),
-                            source_arc.to_string(),
-                        )),
-                        span: (
-                            import_statement.pos_start,
-                            import_statement.pos_end - import_statement.pos_start,
-                        )
-                            .into(),
-                    }
-                })?;
-            // Parse without std_path - parser doesn't need it!
-            let mut parser = crate::parser::Parser::new_with_name(
-                &imported_source_text,
-                absolute_imported_path.to_string_lossy().to_string(),
-            )?;
+        // 1. Process imports
+        self.resolve_imports(&document.imports, source_text, &file_path)?;
 
-            let imported_document = parser.parse_document()?;
-            let resolved_imported_document = self.resolve(
-                imported_document,
-                &imported_source_text,
-                absolute_imported_path.clone(),
-                Some(import_statement.clone()),
-            )?;
-            self.resolved_documents
-                .insert(absolute_imported_path, resolved_imported_document);
-        }
-
-        // After resolving all imports, process named imports to populate the symbol table
-        for import_statement in &document.imports {
-            if let ImportSpec::Named(specifiers) = &import_statement.spec {
-                let imported_path_str = import_statement.path.trim_matches('"');
-                let absolute_imported_path = current_dir.join(imported_path_str);
-                if let Some(imported_doc) = self.resolved_documents.get(&absolute_imported_path) {
-                    if let MonValueKind::Object(members) = &imported_doc.root.kind {
-                        for specifier in specifiers {
-                            if !specifier.is_anchor {
-                                for member in members {
-                                    if let Member::TypeDefinition(td) = member {
-                                        if td.name == specifier.name {
-                                            self.symbol_table
-                                                .types
-                                                .insert(specifier.name.clone(), td.clone());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        // 2. Collect type definitions and anchors from the current document, with the fields
+        // of base structs added to the structs that extend them
+        if let MonValueKind::Object(members) = &mut document.root.kind {
+            self.add_base_fields(members, &document.imports, &file_path, source_text)?;
         }
-
-        // 2. Collect type definitions and anchors from the current document
         if let MonValueKind::Object(members) = &document.root.kind {
             for member in members {
-                match member {
-                    Member::TypeDefinition(type_def) => {
-                        self.symbol_table
-                            .types
-                            .insert(type_def.name.clone(), type_def.clone());
-                    }
-                    Member::Pair(pair) => {
-                        if let Some(anchor_name) = &pair.value.anchor {
-                            self.anchors.insert(anchor_name.clone(), pair.value.clone());
-                        }
-                    }
-                    _ => {}
-                }
+                self.collect_member_symbols(member, &file_path, source_text);
             }
         }
 
@@ -376,149 +598,872 @@ impl Resolver {
         // 4. Validate the resolved document
         // This will involve iterating through the resolved_root and applying validations
         // where `:: Type` is specified.
+        let validate_start = self.start_timer();
         let final_resolved_root =
             self.validate_document_root(resolved_root, &document.imports, &file_path, source_text)?;
 
-        let resolved_doc = MonDocument {
+        let mut resolved_doc = MonDocument {
             root: final_resolved_root,
             imports: document.imports, // Imports are already processed
+            metadata: document.metadata,
+            element_docs: document.element_docs,
+            number_literals: document.number_literals,
         };
+        if let Some(root_type) = root_type {
+            self.validate_root(&mut resolved_doc, root_type, &file_path, source_text)?;
+        }
+        self.stop_timer(validate_start, |metrics| &mut metrics.validate_time);
+        self.apply_discriminants(
+            &mut resolved_doc.root,
+            &resolved_doc.imports,
+            &file_path,
+            source_text,
+        )?;
+        // Bare words are only allowed where validation turned them into enum values
+        reject_bare_words(&resolved_doc.root, &file_path, source_text)?;
+        reject_non_finite_numbers(&resolved_doc.root, &file_path, source_text)?;
 
         // Remove the current file from the stack
         self.resolving_stack.pop();
 
         Ok(resolved_doc)
     }
-    // Helper function to recursively resolve aliases and spreads within a MonValue
-    /// Recursively resolves aliases and spreads within a [`MonValue`].
-    fn resolve_value(
+
+    /// Loads and resolves the modules imported by a document, and brings the types named in
+    /// `import { ... }` statements into scope.
+    pub(crate) fn resolve_imports(
         &mut self,
-        mut value: MonValue,
-        file_path: &PathBuf,
+        imports: &[ImportStatement],
         source_text: &str,
-    ) -> Result<MonValue, ResolverError> {
-        let alias_span = value.get_source_span();
+        file_path: &Path,
+    ) -> Result<(), ResolverError> {
+        let current_dir = file_path.parent().unwrap_or_else(|| Path::new("."));
+        let source_arc = Arc::new(source_text.to_string());
+        for import_statement in imports {
+            let imported_path_str = import_statement.path.trim_matches('"');
 
-        match &mut value.kind {
-            MonValueKind::Alias(alias_name) => {
-                // Resolve alias: find the anchored value and return a deep copy
-                let anchor_value = self.anchors.get(alias_name).ok_or_else(|| {
-                    // TODO: Get actual span for the alias
-                    ResolverError::AnchorNotFound {
-                        name: alias_name.clone(),
-                        src: Arc::from(NamedSource::new(
+            // for mon: ...
+            let absolute_imported_path = self.resolve_import_path(imported_path_str, current_dir);
+            if absolute_imported_path.is_dir() {
+                self.resolve_directory_import(
+                    &absolute_imported_path,
+                    import_statement,
+                    file_path,
+                    source_text,
+                )?;
+                continue;
+            }
+            self.record_dependency(file_path, &absolute_imported_path);
+            if self
+                .resolved_documents
+                .contains_key(&absolute_imported_path)
+            {
+                // The module was already resolved (e.g. by a previous document in the same
+                // file), so only its symbols need to be brought back into scope.
+                self.collect_cached_module_symbols(&absolute_imported_path);
+                continue;
+            }
+            let imported_source_text =
+                std::fs::read_to_string(&absolute_imported_path).map_err(|err| {
+                    module_read_error(
+                        &err,
+                        imported_path_str.to_string(),
+                        Arc::from(NamedSource::new(
                             file_path.to_string_lossy(),
-                            source_text.to_string(),
+                            source_arc.to_string(),
                         )),
-                        span: alias_span,
-                    }
+                        source_span(import_statement.pos_start, import_statement.pos_end),
+                    )
                 })?;
-                Ok(anchor_value.clone()) // Return a deep copy
-            }
-            MonValueKind::Object(members) => {
-                let mut resolved_members = Vec::new();
-                for member in members.drain(..) {
-                    match member {
-                        Member::Spread(spread_name) => {
-                            // Resolve object spread: merge members from anchored object
-                            let anchor_value = self.anchors.get(&spread_name).ok_or_else(|| {
-                                // TODO: Get actual span for the spread
-                                ResolverError::AnchorNotFound {
-                                    name: spread_name.clone(),
-                                    src: Arc::from(NamedSource::new(
-                                        file_path.to_string_lossy(),
-                                        source_text.to_string(),
-                                    )),
-                                    span: alias_span,
-                                }
-                            })?;
-                            if let MonValueKind::Object(spread_members) = &anchor_value.kind {
-                                let spread_members_clone = spread_members.clone();
-                                for spread_member in spread_members_clone {
-                                    // Recursively resolve spread members
-                                    resolved_members.push(self.resolve_value_member(
-                                        spread_member,
-                                        file_path,
-                                        source_text,
-                                    )?);
+            self.count_file_read();
+            self.resolve_module(
+                absolute_imported_path,
+                &imported_source_text,
+                import_statement,
+                file_path,
+                source_text,
+            )?;
+        }
+
+        // After resolving all imports, process named and default imports to populate the
+        // symbol table and anchors
+        self.scope_types.clear();
+        for import_statement in imports {
+            let imported_path_str = import_statement.path.trim_matches('"');
+            let absolute_imported_path = self.resolve_import_path(imported_path_str, current_dir);
+            let Some(imported_doc) = self.resolved_documents.get(&absolute_imported_path) else {
+                continue;
+            };
+            match &import_statement.spec {
+                ImportSpec::Default(name) => {
+                    // The module's data is its root object without the type definitions
+                    let mut root = imported_doc.root.clone();
+                    if let MonValueKind::Object(members) = &mut root.kind {
+                        members.retain(|member| matches!(member, Member::Pair(_)));
+                    }
+                    self.anchors.insert(name.clone(), root);
+                }
+                ImportSpec::Named(specifiers) => {
+                    let MonValueKind::Object(members) = &imported_doc.root.kind else {
+                        continue;
+                    };
+                    let mut module_anchors = HashMap::new();
+                    collect_anchors(&mut module_anchors, &imported_doc.root);
+                    let mut imported_types = Vec::new();
+                    for specifier in specifiers {
+                        if specifier.is_anchor {
+                            // Taken from the module itself, as the anchors of a cached module
+                            // are not brought back into scope
+                            if let Some(anchor) = module_anchors.get(&specifier.name) {
+                                self.anchors
+                                    .insert(specifier.local_name().to_string(), anchor.clone());
+                            }
+                        } else {
+                            for member in members {
+                                if let Member::TypeDefinition(td) = member {
+                                    if td.name == specifier.name {
+                                        imported_types
+                                            .push((specifier.local_name().to_string(), td.clone()));
+                                    }
                                 }
-                            } else {
-                                return Err(ResolverError::SpreadOnNonObject {
-                                    name: spread_name.clone(),
-                                    src: Arc::from(NamedSource::new(
-                                        file_path.to_string_lossy(),
-                                        source_text.to_string(),
-                                    )),
-                                    span: alias_span,
-                                });
                             }
                         }
-                        _ => {
-                            // Recursively resolve other members
-                            resolved_members.push(self.resolve_value_member(
-                                member,
-                                file_path,
-                                source_text,
-                            )?);
-                        }
                     }
-                }
-                // Handle key overriding for object spreads (local keys win)
-                let mut final_members_map: HashMap<String, Member> = HashMap::new();
-                for member in resolved_members {
-                    if let Member::Pair(pair) = member {
-                        final_members_map.insert(pair.key.clone(), Member::Pair(pair));
-                    } else {
-                        // Non-pair members (like TypeDefinition) are just added
-                        // This might need refinement depending on how TypeDefinitions are handled after resolution
-                        final_members_map.insert(format!("{member:?}"), member);
-                        // Dummy key for now
+                    let import_span =
+                        source_span(import_statement.pos_start, import_statement.pos_end);
+                    for (local_name, td) in imported_types {
+                        self.define_type(
+                            local_name,
+                            td,
+                            &absolute_imported_path,
+                            file_path,
+                            source_text,
+                            import_span,
+                        );
                     }
                 }
-                let final_members = final_members_map.into_values().collect();
-                Ok(MonValue {
-                    kind: MonValueKind::Object(final_members),
-                    anchor: value.anchor,
-                    pos_start: value.pos_start,
-                    pos_end: value.pos_end,
-                })
+                // Only the namespaces of the document being analyzed are kept, not those of
+                // the modules it imports
+                ImportSpec::Namespace(namespace) if self.resolving_stack.len() <= 1 => {
+                    let MonValueKind::Object(members) = &imported_doc.root.kind else {
+                        continue;
+                    };
+                    let types = members
+                        .iter()
+                        .filter_map(|member| match member {
+                            Member::TypeDefinition(td) => Some((td.name.clone(), td.clone())),
+                            _ => None,
+                        })
+                        .collect();
+                    self.symbol_table
+                        .namespaces
+                        .insert(namespace.clone(), types);
+                }
+                ImportSpec::Namespace(_) => {}
             }
-            MonValueKind::Array(elements) => {
+        }
+        Ok(())
+    }
+
+    /// Parses and resolves the imported module at `module_path` and caches the result.
+    ///
+    /// A module that does not parse is reported at its import in `file_path`, see
+    /// [`ResolverError::ImportedModuleInvalid`].
+    fn resolve_module(
+        &mut self,
+        module_path: PathBuf,
+        module_source: &str,
+        import_statement: &ImportStatement,
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        let invalid_module = |err: MonError| match err {
+            MonError::Parser(cause) => ResolverError::ImportedModuleInvalid {
+                path: import_statement.path.trim_matches('"').to_string(),
+                src: Arc::from(NamedSource::new(
+                    file_path.to_string_lossy(),
+                    source_text.to_string(),
+                )),
+                span: source_span(import_statement.pos_start, import_statement.pos_end),
+                cause: *cause,
+            },
+            MonError::Resolver(err) => *err,
+        };
+        // Parse without std_path - parser doesn't need it!
+        let lex_start = self.start_timer();
+        let mut parser = crate::parser::Parser::new_with_options(
+            module_source,
+            module_path.to_string_lossy().to_string(),
+            self.options.lexer.clone(),
+        )
+        .map_err(invalid_module)?;
+        self.stop_timer(lex_start, |metrics| &mut metrics.lex_time);
+        let parse_start = self.start_timer();
+        let imported_document = parser.parse_document().map_err(invalid_module)?;
+        self.stop_timer(parse_start, |metrics| &mut metrics.parse_time);
+        let resolved_imported_document = self.resolve(
+            imported_document,
+            module_source,
+            module_path.clone(),
+            Some(import_statement.clone()),
+        )?;
+        self.resolved_documents
+            .insert(module_path, resolved_imported_document);
+        Ok(())
+    }
+
+    /// Resolves the import of a directory, e.g. `import * as schemas from "./schemas/"`.
+    ///
+    /// Every `.mon` file in the directory is resolved, in name order, and their type definitions
+    /// are cached as one module under the directory's path. If two files define a type of the
+    /// same name, the later file's type wins and a [`MonWarning::TypeShadowed`] is recorded.
+    fn resolve_directory_import(
+        &mut self,
+        dir: &Path,
+        import_statement: &ImportStatement,
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        let read_error = |err: &std::io::Error, path: String| {
+            module_read_error(
+                err,
+                path,
+                Arc::from(NamedSource::new(
+                    file_path.to_string_lossy(),
+                    source_text.to_string(),
+                )),
+                source_span(import_statement.pos_start, import_statement.pos_end),
+            )
+        };
+        let mut module_paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .and_then(|entries| entries.map(|entry| Ok(entry?.path())).collect())
+            .map_err(|err| read_error(&err, import_statement.path.trim_matches('"').to_string()))?;
+        // A module in the directory that imports it does not import itself
+        module_paths.retain(|path| {
+            path.is_file() && path.extension().is_some_and(|ext| ext == "mon") && path != file_path
+        });
+        module_paths.sort();
+
+        let already_resolved = self.resolved_documents.contains_key(dir);
+        let mut types: Vec<(PathBuf, TypeDefinition)> = Vec::new();
+        for module_path in module_paths {
+            self.record_dependency(file_path, &module_path);
+            // A cached module is only read again if it shadows a type, for the warning
+            let mut module_source = None;
+            if self.resolved_documents.contains_key(&module_path) {
+                self.collect_cached_module_symbols(&module_path);
+            } else {
+                let source = std::fs::read_to_string(&module_path)
+                    .map_err(|err| read_error(&err, module_path.to_string_lossy().to_string()))?;
+                self.count_file_read();
+                self.resolve_module(
+                    module_path.clone(),
+                    &source,
+                    import_statement,
+                    file_path,
+                    source_text,
+                )?;
+                module_source = Some(source);
+            }
+            if already_resolved {
+                continue;
+            }
+
+            let Some(MonValueKind::Object(members)) = self
+                .resolved_documents
+                .get(&module_path)
+                .map(|document| &document.root.kind)
+            else {
+                continue;
+            };
+            let type_defs: Vec<TypeDefinition> = members
+                .iter()
+                .filter_map(|member| match member {
+                    Member::TypeDefinition(type_def) => Some(type_def.clone()),
+                    _ => None,
+                })
+                .collect();
+            for type_def in type_defs {
+                let Some(previous) = types.iter_mut().find(|(_, td)| td.name == type_def.name)
+                else {
+                    types.push((module_path.clone(), type_def));
+                    continue;
+                };
+                let source = match &module_source {
+                    Some(source) => source.clone(),
+                    None => {
+                        let source = std::fs::read_to_string(&module_path).map_err(|err| {
+                            read_error(&err, module_path.to_string_lossy().to_string())
+                        })?;
+                        self.count_file_read();
+                        module_source.insert(source).clone()
+                    }
+                };
+                let (original_file, original) =
+                    std::mem::replace(previous, (module_path.clone(), type_def.clone()));
+                self.warnings.push(MonWarning::TypeShadowed {
+                    name: type_def.name,
+                    original_file: original_file.to_string_lossy().to_string(),
+                    original_span: original.name_span,
+                    src: Arc::from(NamedSource::new(module_path.to_string_lossy(), source)),
+                    shadowing_span: type_def.name_span,
+                });
+            }
+        }
+
+        if !already_resolved {
+            let members = types
+                .into_iter()
+                .map(|(_, type_def)| Member::TypeDefinition(type_def))
+                .collect();
+            let module = MonDocument {
+                root: MonValue {
+                    kind: MonValueKind::Object(members),
+                    anchor: None,
+                    pos_start: 0,
+                    pos_end: 0,
+                },
+                imports: Vec::new(),
+                metadata: HashMap::new(),
+                element_docs: HashMap::new(),
+                number_literals: HashMap::new(),
+            };
+            self.resolved_documents.insert(dir.to_path_buf(), module);
+        }
+        Ok(())
+    }
+
+    /// Collects the type definition or the anchors declared by a member of a document's root object.
+    pub(crate) fn collect_member_symbols(
+        &mut self,
+        member: &Member,
+        file_path: &Path,
+        source_text: &str,
+    ) {
+        match member {
+            Member::TypeDefinition(type_def) => {
+                self.define_type(
+                    type_def.name.clone(),
+                    type_def.clone(),
+                    file_path,
+                    file_path,
+                    source_text,
+                    type_def.name_span,
+                );
+            }
+            Member::Pair(pair) => collect_anchors(&mut self.anchors, &pair.value),
+            _ => {}
+        }
+    }
+
+    /// Adds the fields of their base struct to the structs among `members` that extend one,
+    /// e.g. `Admin: #struct extends User { ... }`.
+    ///
+    /// A base is looked up among `members` first, then among the types in scope. Fields of the
+    /// extending struct replace base fields of the same name, and its `one_of` groups are added
+    /// to the base's. Their fields may be inherited, so they are checked here rather than by the
+    /// parser, as are the keys all local structs are serialized under.
+    pub(crate) fn add_base_fields(
+        &self,
+        members: &mut [Member],
+        imports: &[ImportStatement],
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        let local: HashMap<&str, &TypeDefinition> = members
+            .iter()
+            .filter_map(|member| match member {
+                Member::TypeDefinition(type_def) => Some((type_def.name.as_str(), type_def)),
+                _ => None,
+            })
+            .collect();
+        let mut extended = Vec::new();
+        for type_def in members.iter().filter_map(|member| match member {
+            Member::TypeDefinition(type_def) => Some(type_def),
+            _ => None,
+        }) {
+            let TypeDef::Struct(struct_def) = &type_def.def_type else {
+                continue;
+            };
+            if struct_def.extends.is_some() {
+                let mut chain = vec![type_def.name.clone()];
+                let merged = self.with_base_fields(
+                    struct_def,
+                    &local,
+                    &mut chain,
+                    imports,
+                    file_path,
+                    source_text,
+                )?;
+                check_one_of_fields(&type_def.name, &merged, file_path, source_text)?;
+                check_field_keys(&type_def.name, &merged, file_path, source_text)?;
+                extended.push((type_def.name.clone(), merged));
+            } else {
+                check_field_keys(&type_def.name, struct_def, file_path, source_text)?;
+            }
+        }
+        for member in members {
+            if let Member::TypeDefinition(type_def) = member {
+                if let Some((_, merged)) = extended.iter().find(|(name, _)| *name == type_def.name)
+                {
+                    type_def.def_type = TypeDef::Struct(merged.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `struct_def` with the fields of its base struct, see [`Resolver::add_base_fields`].
+    ///
+    /// `chain` holds the names of the local structs extending `struct_def`, to detect cycles.
+    fn with_base_fields(
+        &self,
+        struct_def: &StructDef,
+        local: &HashMap<&str, &TypeDefinition>,
+        chain: &mut Vec<String>,
+        imports: &[ImportStatement],
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<StructDef, ResolverError> {
+        let Some(base_name) = &struct_def.extends else {
+            return Ok(struct_def.clone());
+        };
+        let span: SourceSpan = source_span(struct_def.pos_start, struct_def.pos_end);
+        let src = || {
+            Arc::from(NamedSource::new(
+                file_path.to_string_lossy(),
+                source_text.to_string(),
+            ))
+        };
+        let name = chain.last().cloned().unwrap_or_default();
+
+        let base = if let Some(base) = local.get(base_name.as_str()) {
+            if chain.contains(base_name) {
+                return Err(ResolverError::CyclicInheritance {
+                    name,
+                    cycle: format!("{} -> {base_name}", chain.join(" -> ")),
+                    src: src(),
+                    span,
+                });
+            }
+            // Local bases may extend other structs in turn. Bases in scope already have the
+            // fields of theirs, from when their module was resolved.
+            chain.push(base_name.clone());
+            let base = match &base.def_type {
+                TypeDef::Struct(base) => Some(TypeDef::Struct(self.with_base_fields(
+                    base,
+                    local,
+                    chain,
+                    imports,
+                    file_path,
+                    source_text,
+                )?)),
+                def_type => Some(def_type.clone()),
+            };
+            chain.pop();
+            base
+        } else {
+            self.lookup_type_def(base_name, imports, span, file_path, source_text)?
+        };
+
+        let mut base = match base {
+            Some(TypeDef::Struct(base)) => base,
+            Some(TypeDef::Enum(_) | TypeDef::Alias(_)) => {
+                return Err(ResolverError::ExtendsNonStruct {
+                    name,
+                    base: base_name.clone(),
+                    src: src(),
+                    span,
+                })
+            }
+            None => {
+                return Err(ResolverError::Validation(ValidationError::UndefinedType {
+                    type_name: base_name.clone(),
+                    help: self.undefined_type_help(base_name),
+                    src: src(),
+                    span,
+                }))
+            }
+        };
+        for field in &struct_def.fields {
+            match base
+                .fields
+                .iter_mut()
+                .find(|base_field| base_field.name == field.name)
+            {
+                Some(base_field) => *base_field = field.clone(),
+                None => base.fields.push(field.clone()),
+            }
+        }
+        base.one_of.extend(struct_def.one_of.iter().cloned());
+        Ok(StructDef {
+            fields: base.fields,
+            one_of: base.one_of,
+            ..struct_def.clone()
+        })
+    }
+
+    /// Adds a type defined in `origin_file` to the symbol table under `name`, in the scope of the
+    /// document being resolved.
+    ///
+    /// If the document already defines or imports another type of that name, a
+    /// [`MonWarning::TypeShadowed`] is recorded, pointing at `span` in `file_path`: the
+    /// definition itself, or the import bringing it in.
+    fn define_type(
+        &mut self,
+        name: String,
+        type_def: TypeDefinition,
+        origin_file: &Path,
+        file_path: &Path,
+        source_text: &str,
+        span: SourceSpan,
+    ) {
+        let origin = (origin_file.to_path_buf(), type_def.name_span);
+        let previous = self.scope_types.insert(name.clone(), origin.clone());
+        if let Some((original_file, original_span)) = previous.filter(|p| *p != origin) {
+            self.warnings.push(MonWarning::TypeShadowed {
+                name: name.clone(),
+                original_file: original_file.to_string_lossy().to_string(),
+                original_span,
+                src: Arc::from(NamedSource::new(
+                    file_path.to_string_lossy(),
+                    source_text.to_string(),
+                )),
+                shadowing_span: span,
+            });
+        }
+        self.symbol_table.types.insert(name, type_def);
+    }
+
+    /// Resolves and validates one element of a root-level array while the document is still
+    /// being parsed, for [`analyze_streaming`](crate::api::analyze_streaming).
+    ///
+    /// `validation` is the type annotation of the array. The element is validated against the
+    /// element type of `[T...]` and `[T; N]`; other annotations need the whole array and are
+    /// not checked here.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn resolve_streamed_element(
+        &mut self,
+        element: MonValue,
+        validation: Option<&TypeSpec>,
+        key: &str,
+        index: usize,
+        imports: &[ImportStatement],
+        file_path: &PathBuf,
+        source_text: &str,
+    ) -> Result<MonValue, ResolverError> {
+        let mut element = element;
+        apply_guards(&mut element, &self.options.env);
+        let mut element = self.resolve_value(element, file_path, source_text)?;
+        let element_type = match validation {
+            Some(TypeSpec::Collection(types, _)) => match types.as_slice() {
+                [TypeSpec::Spread(element_type, _)] => Some(&**element_type),
+                _ => None,
+            },
+            Some(TypeSpec::Repeat(element_type, _, _)) => Some(&**element_type),
+            _ => None,
+        };
+        if let Some(element_type) = element_type {
+            self.validate_value(
+                &mut element,
+                element_type,
+                key,
+                &format!("{key}[{index}]"),
+                imports,
+                file_path,
+                source_text,
+            )?;
+        }
+        self.apply_discriminants(&mut element, imports, file_path, source_text)?;
+        reject_bare_words(&element, file_path, source_text)?;
+        reject_non_finite_numbers(&element, file_path, source_text)?;
+        Ok(element)
+    }
+
+    /// Replaces every enum value in `value` whose variant has a discriminant with that
+    /// discriminant, e.g. `$Status.Active` with `1` for `Status: #enum { Active = 1 }`.
+    ///
+    /// Fails with [`ValidationError::UndefinedEnumVariant`] for a variant its enum does not
+    /// have. Values of enums that are not defined are left as they are.
+    fn apply_discriminants(
+        &self,
+        value: &mut MonValue,
+        imports: &[ImportStatement],
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        let mut error = None;
+        value.walk_mut(&mut |value| {
+            let MonValueKind::EnumValue {
+                enum_name,
+                variant_name,
+            } = &value.kind
+            else {
+                return;
+            };
+            if error.is_some() {
+                return;
+            }
+            let enum_def = match self.lookup_type_def(
+                enum_name,
+                imports,
+                value.pos_span(),
+                file_path,
+                source_text,
+            ) {
+                Ok(Some(TypeDef::Enum(enum_def))) => enum_def,
+                Ok(_) => return,
+                Err(err) => {
+                    error = Some(err);
+                    return;
+                }
+            };
+            if !enum_def.has_variant(variant_name) {
+                error = Some(ResolverError::Validation(
+                    ValidationError::UndefinedEnumVariant {
+                        variant_name: variant_name.clone(),
+                        enum_name: enum_name.clone(),
+                        src: Arc::from(NamedSource::new(
+                            file_path.to_string_lossy(),
+                            source_text.to_string(),
+                        )),
+                        span: value.pos_span(),
+                    },
+                ));
+            } else if let Some(discriminant) = enum_def.discriminant(variant_name) {
+                value.kind = discriminant.kind.clone();
+            }
+        });
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Takes the symbol table and anchors collected so far, leaving the resolver with empty ones.
+    ///
+    /// Already resolved modules stay cached, so the resolver can be reused to resolve another
+    /// document independently while sharing the imported modules.
+    pub fn take_symbols(&mut self) -> (AstSymbolTable, HashMap<String, MonValue>) {
+        (
+            std::mem::take(&mut self.symbol_table),
+            std::mem::take(&mut self.anchors),
+        )
+    }
+
+    /// Records that `from` imports `to`, once per pair of files.
+    fn record_dependency(&mut self, from: &Path, to: &Path) {
+        if !self
+            .dependencies
+            .iter()
+            .any(|(f, t)| f.as_path() == from && t.as_path() == to)
+        {
+            self.dependencies
+                .push((from.to_path_buf(), to.to_path_buf()));
+        }
+    }
+
+    /// Re-collects the type definitions of a cached module and its imports.
+    ///
+    /// All of them come back, as validating against an imported type can need the types it
+    /// refers to. Anchors only come back through the import itself, like `import { &port }`
+    /// or a default import, which the second loop of [`Resolver::resolve_imports`] handles.
+    fn collect_cached_module_symbols(&mut self, module_path: &Path) {
+        let mut pending = vec![module_path.to_path_buf()];
+        let mut visited = Vec::new();
+
+        while let Some(path) = pending.pop() {
+            if visited.contains(&path) {
+                continue;
+            }
+            let Some(document) = self.resolved_documents.get(&path) else {
+                continue;
+            };
+
+            let module_dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let imported_paths: Vec<PathBuf> = document
+                .imports
+                .iter()
+                .map(|i| self.resolve_import_path(i.path.trim_matches('"'), module_dir))
+                .collect();
+
+            if let MonValueKind::Object(members) = &document.root.kind {
+                for member in members {
+                    if let Member::TypeDefinition(type_def) = member {
+                        self.symbol_table
+                            .types
+                            .insert(type_def.name.clone(), type_def.clone());
+                    }
+                }
+            }
+            for imported_path in imported_paths {
+                self.record_dependency(&path, &imported_path);
+                pending.push(imported_path);
+            }
+            visited.push(path);
+        }
+    }
+
+    // Helper function to recursively resolve aliases and spreads within a MonValue
+    /// Recursively resolves aliases and spreads within a [`MonValue`].
+    fn resolve_value(
+        &mut self,
+        mut value: MonValue,
+        file_path: &PathBuf,
+        source_text: &str,
+    ) -> Result<MonValue, ResolverError> {
+        let alias_span = value.get_source_span();
+
+        match &mut value.kind {
+            MonValueKind::Alias(alias_name) => {
+                // Resolve alias: find the anchored value and return a deep copy
+                let anchor_value = self.anchors.get(alias_name).ok_or_else(|| {
+                    // TODO: Get actual span for the alias
+                    ResolverError::AnchorNotFound {
+                        name: alias_name.clone(),
+                        src: Arc::from(NamedSource::new(
+                            file_path.to_string_lossy(),
+                            source_text.to_string(),
+                        )),
+                        span: alias_span,
+                    }
+                })?;
+                // A deep copy that is not itself the anchor
+                Ok(MonValue {
+                    anchor: None,
+                    ..anchor_value.clone()
+                })
+            }
+            MonValueKind::Object(members) => {
+                let mut resolved_members = Vec::new();
+                // (name, span, range in `resolved_members`) of each object spread
+                let mut spreads = Vec::new();
+                for member in members.drain(..) {
+                    match member {
+                        Member::Spread(spread_name, spread_span) => {
+                            // Resolve object spread: merge members from anchored object
+                            let anchor_value = self.anchors.get(&spread_name).ok_or_else(|| {
+                                ResolverError::SpreadSourceNotFound {
+                                    name: spread_name.clone(),
+                                    src: Arc::from(NamedSource::new(
+                                        file_path.to_string_lossy(),
+                                        source_text.to_string(),
+                                    )),
+                                    span: spread_span,
+                                }
+                            })?;
+                            if let MonValueKind::Object(spread_members) = &anchor_value.kind {
+                                let spread_members_clone = spread_members.clone();
+                                let start = resolved_members.len();
+                                for spread_member in spread_members_clone {
+                                    // Recursively resolve spread members, whose values are
+                                    // copies and not anchors themselves
+                                    let mut member = self.resolve_value_member(
+                                        spread_member,
+                                        file_path,
+                                        source_text,
+                                    )?;
+                                    if let Member::Pair(pair) = &mut member {
+                                        pair.value.anchor = None;
+                                    }
+                                    resolved_members.push(member);
+                                }
+                                spreads.push((
+                                    spread_name,
+                                    spread_span,
+                                    start..resolved_members.len(),
+                                ));
+                            } else {
+                                return Err(ResolverError::SpreadOnNonObject {
+                                    name: spread_name.clone(),
+                                    src: Arc::from(NamedSource::new(
+                                        file_path.to_string_lossy(),
+                                        source_text.to_string(),
+                                    )),
+                                    span: spread_span,
+                                });
+                            }
+                        }
+                        _ => {
+                            // Recursively resolve other members
+                            resolved_members.push(self.resolve_value_member(
+                                member,
+                                file_path,
+                                source_text,
+                            )?);
+                        }
+                    }
+                }
+                for (name, span, range) in spreads {
+                    if is_redundant_spread(&resolved_members, range, self.null_as_absent) {
+                        self.warnings.push(MonWarning::RedundantSpread {
+                            name,
+                            src: Arc::from(NamedSource::new(
+                                file_path.to_string_lossy(),
+                                source_text.to_string(),
+                            )),
+                            span,
+                        });
+                    }
+                }
+                // Handle key overriding for object spreads (local keys win)
+                let mut final_members_map: HashMap<String, Member> = HashMap::new();
+                for member in resolved_members {
+                    if let Member::Pair(pair) = member {
+                        if self.null_as_absent
+                            && matches!(pair.value.kind, MonValueKind::Null)
+                            && final_members_map.contains_key(&pair.key)
+                        {
+                            continue;
+                        }
+                        final_members_map.insert(pair.key.clone(), Member::Pair(pair));
+                    } else {
+                        // Non-pair members (like TypeDefinition) are just added
+                        // This might need refinement depending on how TypeDefinitions are handled after resolution
+                        final_members_map.insert(format!("{member:?}"), member);
+                        // Dummy key for now
+                    }
+                }
+                let final_members = final_members_map.into_values().collect();
+                Ok(MonValue {
+                    kind: MonValueKind::Object(final_members),
+                    anchor: value.anchor,
+                    pos_start: value.pos_start,
+                    pos_end: value.pos_end,
+                })
+            }
+            MonValueKind::Array(elements) => {
                 let mut resolved_elements = Vec::new();
                 for element in elements.drain(..) {
+                    let spread_span = element.get_source_span();
                     match element.kind {
                         MonValueKind::ArraySpread(spread_name) => {
                             // Resolve array spread: concatenate elements from anchored array
                             let anchor_value = self.anchors.get(&spread_name).ok_or_else(|| {
-                                // TODO: Get actual span for the spread
-                                ResolverError::AnchorNotFound {
+                                ResolverError::SpreadSourceNotFound {
                                     name: spread_name.clone(),
                                     src: Arc::from(NamedSource::new(
                                         file_path.to_string_lossy(),
                                         source_text.to_string(),
                                     )),
-                                    span: alias_span,
+                                    span: spread_span,
                                 }
                             })?;
                             if let MonValueKind::Array(spread_elements) = &anchor_value.kind {
                                 let spread_elements_clone = spread_elements.clone();
                                 for spread_element in spread_elements_clone {
-                                    // Recursively resolve spread elements
-                                    resolved_elements.push(self.resolve_value(
-                                        spread_element,
-                                        file_path,
-                                        source_text,
-                                    )?);
+                                    // Recursively resolve spread elements, which are copies
+                                    // and not anchors themselves
+                                    let mut element =
+                                        self.resolve_value(spread_element, file_path, source_text)?;
+                                    element.anchor = None;
+                                    resolved_elements.push(element);
                                 }
                             } else {
-                                // TODO: Get actual span for the spread
                                 return Err(ResolverError::SpreadOnNonArray {
                                     name: spread_name.clone(),
                                     src: Arc::from(NamedSource::new(
                                         file_path.to_string_lossy(),
                                         source_text.to_string(),
                                     )),
-                                    span: alias_span,
+                                    span: spread_span,
                                 });
                             }
                         }
@@ -573,14 +1518,16 @@ impl Resolver {
                 if let Member::Pair(pair) = member {
                     if let Some(type_spec) = &pair.validation {
                         // Perform validation for this pair
-                        self.validate_value(
+                        let result = self.validate_value(
                             &mut pair.value,
                             type_spec,
                             &pair.key,
+                            &pair.key,
                             imports, // Pass the imports here
                             file_path,
                             source_text,
-                        )?;
+                        );
+                        self.collect_validation(result)?;
                     }
                 }
             }
@@ -588,12 +1535,43 @@ impl Resolver {
         Ok(root_value)
     }
 
+    /// Validates the root of a resolved document against the type named `type_name`, as if the
+    /// whole document were annotated with `:: type_name`.
+    ///
+    /// The type is looked up in the types collected so far, so it can come from a schema
+    /// document resolved earlier with the same resolver. Field paths in validation errors
+    /// start at the root, e.g. `server.port`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ResolverError`] if the type is undefined or the root does not match it.
+    pub fn validate_root(
+        &mut self,
+        document: &mut MonDocument,
+        type_name: &str,
+        file_path: &PathBuf,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        let type_spec = TypeSpec::Simple(type_name.to_string(), (0, 0).into());
+        self.validate_value(
+            &mut document.root,
+            &type_spec,
+            type_name,
+            "",
+            &document.imports,
+            file_path,
+            source_text,
+        )
+    }
+
     /// Recursively validates a [`MonValue`] against a [`TypeSpec`].
+    #[allow(clippy::too_many_arguments)]
     fn validate_value(
         &mut self,
         value: &mut MonValue,
         type_spec: &TypeSpec,
         field_name: &str,            // For error reporting
+        field_path: &str,            // Dotted path from the root, e.g. `user.profile.name`
         imports: &[ImportStatement], // Change this parameter
         file_path: &PathBuf,
         source_text: &str,
@@ -606,13 +1584,14 @@ impl Resolver {
                         if !matches!(value.kind, MonValueKind::String(_)) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "String".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
                             }));
                         }
                     }
@@ -620,27 +1599,134 @@ impl Resolver {
                         if !matches!(value.kind, MonValueKind::Number(_)) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "Number".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
+                            }));
+                        }
+                    }
+                    "Int" | "UInt" => {
+                        // Whole numbers only; `UInt` additionally rejects negative values
+                        let is_valid = match value.kind {
+                            MonValueKind::Number(n) => {
+                                n.is_finite()
+                                    && n.fract() == 0.0
+                                    && (type_name == "Int" || n >= 0.0)
+                            }
+                            _ => false,
+                        };
+                        if !is_valid {
+                            return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                                field_name: field_name.to_string(),
+                                path: field_path.to_string(),
+                                expected_type: type_name.clone(),
+                                found_type: format!("{:?}", value.kind),
+                                src: Arc::from(NamedSource::new(
+                                    file_path.to_string_lossy(),
+                                    source_text.to_string(),
+                                )),
+                                span: value.pos_span(),
                             }));
                         }
                     }
+                    "Duration" | "ByteSize" => {
+                        // Quantities of the right kind are normalized to seconds or bytes
+                        let Some(normalized) = normalized_quantity(&value.kind, type_name) else {
+                            return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                                field_name: field_name.to_string(),
+                                path: field_path.to_string(),
+                                expected_type: type_name.clone(),
+                                found_type: format!("{:?}", value.kind),
+                                src: Arc::from(NamedSource::new(
+                                    file_path.to_string_lossy(),
+                                    source_text.to_string(),
+                                )),
+                                span: value.pos_span(),
+                            }));
+                        };
+                        value.kind = MonValueKind::Number(normalized);
+                    }
+                    "Color" => {
+                        let MonValueKind::String(color) = &value.kind else {
+                            return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                                field_name: field_name.to_string(),
+                                path: field_path.to_string(),
+                                expected_type: "Color".to_string(),
+                                found_type: format!("{:?}", value.kind),
+                                src: Arc::from(NamedSource::new(
+                                    file_path.to_string_lossy(),
+                                    source_text.to_string(),
+                                )),
+                                span: value.pos_span(),
+                            }));
+                        };
+                        if !is_hex_color(color) {
+                            return Err(ResolverError::Validation(ValidationError::InvalidColor {
+                                path: field_path.to_string(),
+                                value: color.clone(),
+                                src: Arc::from(NamedSource::new(
+                                    file_path.to_string_lossy(),
+                                    source_text.to_string(),
+                                )),
+                                span: value.pos_span(),
+                            }));
+                        }
+                    }
+                    "Bytes" => {
+                        // Hex or base64 text is decoded; already decoded bytes stay as they are
+                        let decoded = match &value.kind {
+                            MonValueKind::String(text) => match decode_bytes(text) {
+                                Some(decoded) => decoded,
+                                None => {
+                                    return Err(ResolverError::Validation(
+                                        ValidationError::InvalidBytes {
+                                            path: field_path.to_string(),
+                                            value: text.clone(),
+                                            src: Arc::from(NamedSource::new(
+                                                file_path.to_string_lossy(),
+                                                source_text.to_string(),
+                                            )),
+                                            span: value.pos_span(),
+                                        },
+                                    ));
+                                }
+                            },
+                            MonValueKind::Bytes(bytes) => bytes.clone(),
+                            _ => {
+                                return Err(ResolverError::Validation(
+                                    ValidationError::TypeMismatch {
+                                        field_name: field_name.to_string(),
+                                        path: field_path.to_string(),
+                                        expected_type: "Bytes".to_string(),
+                                        found_type: format!("{:?}", value.kind),
+                                        src: Arc::from(NamedSource::new(
+                                            file_path.to_string_lossy(),
+                                            source_text.to_string(),
+                                        )),
+                                        span: value.pos_span(),
+                                    },
+                                ));
+                            }
+                        };
+                        value.kind = MonValueKind::Bytes(decoded);
+                    }
                     "Boolean" => {
                         if !matches!(value.kind, MonValueKind::Boolean(_)) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "Boolean".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
                             }));
                         }
                     }
@@ -648,13 +1734,14 @@ impl Resolver {
                         if !matches!(value.kind, MonValueKind::Null) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "Null".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
                             }));
                         }
                     }
@@ -662,13 +1749,14 @@ impl Resolver {
                         if !matches!(value.kind, MonValueKind::Object(_)) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "Object".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
                             }));
                         }
                     }
@@ -676,160 +1764,179 @@ impl Resolver {
                         if !matches!(value.kind, MonValueKind::Array(_)) {
                             return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                                 field_name: field_name.to_string(),
+                                path: field_path.to_string(),
                                 expected_type: "Array".to_string(),
                                 found_type: format!("{:?}", value.kind),
                                 src: Arc::from(NamedSource::new(
                                     file_path.to_string_lossy(),
                                     source_text.to_string(),
                                 )),
-                                span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                span: value.pos_span(),
                             }));
                         }
                     }
                     "Any" => { /* Always valid, like you :D */ }
                     _ => {
                         // User-defined type (Struct or Enum)
-                        let (namespace, type_name_part) =
-                            if let Some((ns, tn)) = type_name.split_once('.') {
-                                (Some(ns), tn)
-                            } else {
-                                (None, type_name.as_str())
-                            };
-
-                        let type_def = if let Some(namespace) = namespace {
-                            // Find the import statement for this namespace
-                            let import_statement = imports
-                                .iter()
-                                .find(|i| {
-                                    if let ImportSpec::Namespace(ns) = &i.spec {
-                                        ns == namespace
-                                    } else {
-                                        false
-                                    }
-                                })
-                                .ok_or_else(|| {
-                                    ResolverError::Validation(ValidationError::UndefinedType {
-                                        type_name: type_name.clone(),
-                                        src: Arc::from(NamedSource::new(
-                                            file_path.to_string_lossy(),
-                                            source_text.to_string(),
-                                        )),
-                                        span: (value.pos_start, value.pos_end - value.pos_start)
-                                            .into(),
-                                    })
-                                })?;
-
-                            let imported_path_str = import_statement.path.trim_matches('"');
-                            let parent_dir = file_path.parent().ok_or_else(|| {
-                                // This case is unlikely but good to handle.
-                                // It means the file path is something like "/" or "C:\"
-                                ResolverError::ModuleNotFound {
-                                    path: import_statement.path.clone(),
-                                    src: Arc::from(NamedSource::new(
-                                        file_path.to_string_lossy(),
-                                        source_text.to_string(),
-                                    )),
-                                    span: (
-                                        import_statement.pos_start,
-                                        import_statement.pos_end - import_statement.pos_start,
-                                    )
-                                        .into(),
-                                }
-                            })?;
-                            let absolute_imported_path = parent_dir.join(imported_path_str);
-
-                            let imported_doc = self
-                                .resolved_documents
-                                .get(&absolute_imported_path)
-                                .ok_or_else(|| {
-                                    // This indicates a logic error in the resolver, as the document
-                                    // should have been resolved and stored during the initial import pass.
-                                    ResolverError::ModuleNotFound {
-                                        path: absolute_imported_path.to_string_lossy().to_string(),
-                                        src: Arc::from(NamedSource::new(
-                                            file_path.to_string_lossy(),
-                                            source_text.to_string(),
-                                        )),
-                                        span: (value.pos_start, value.pos_end - value.pos_start)
-                                            .into(),
-                                    }
-                                })?;
-
-                            if let MonValueKind::Object(members) = &imported_doc.root.kind {
-                                members.iter().find_map(|m| {
-                                    if let Member::TypeDefinition(td) = m {
-                                        if td.name == type_name_part {
-                                            return Some(td.def_type.clone());
-                                        }
-                                    }
-                                    None
-                                })
-                            } else {
-                                None
-                            }
-                        } else {
-                            self.symbol_table
-                                .types
-                                .get(type_name_part)
-                                .map(|td| td.def_type.clone())
-                        };
+                        let type_def = self.lookup_type_def(
+                            type_name,
+                            imports,
+                            value.pos_span(),
+                            file_path,
+                            source_text,
+                        )?;
 
                         if let Some(type_def) = type_def {
                             match type_def {
                                 TypeDef::Struct(struct_def) => {
                                     // Validate against struct
                                     if let MonValueKind::Object(value_members) = &mut value.kind {
-                                        let mut value_map: HashMap<String, &mut MonValue> =
+                                        let mut value_map: HashMap<String, &mut crate::ast::Pair> =
                                             HashMap::new();
                                         for member in value_members.iter_mut() {
                                             if let Member::Pair(pair) = member {
-                                                value_map.insert(pair.key.clone(), &mut pair.value);
+                                                value_map.insert(pair.key.clone(), pair);
                                             }
                                         }
 
+                                        for group in &struct_def.one_of {
+                                            let given: Vec<String> = group
+                                                .iter()
+                                                .filter(|name| value_map.contains_key(*name))
+                                                .cloned()
+                                                .collect();
+                                            if given.len() != 1 {
+                                                self.collect_validation(Err(
+                                                    ResolverError::Validation(
+                                                        ValidationError::MutualExclusion {
+                                                            fields: group.clone(),
+                                                            given,
+                                                            struct_name: type_name.clone(),
+                                                            src: Arc::from(NamedSource::new(
+                                                                file_path.to_string_lossy(),
+                                                                source_text.to_string(),
+                                                            )),
+                                                            span: source_span(
+                                                                value.pos_start,
+                                                                value.pos_end,
+                                                            ),
+                                                        },
+                                                    ),
+                                                ))?;
+                                            }
+                                        }
+
+                                        // A default would break the group of a field that
+                                        // was given, so the other fields of that group
+                                        // get none
+                                        let given_groups: HashSet<&String> = struct_def
+                                            .one_of
+                                            .iter()
+                                            .filter(|group| {
+                                                group
+                                                    .iter()
+                                                    .any(|name| value_map.contains_key(name))
+                                            })
+                                            .flatten()
+                                            .collect();
+
                                         let mut new_members = Vec::new();
                                         for field_def in &struct_def.fields {
-                                            if let Some(field_value) =
+                                            if let Some(field_pair) =
                                                 value_map.get_mut(&field_def.name)
                                             {
+                                                field_pair
+                                                    .serialized_key
+                                                    .clone_from(&field_def.alias);
                                                 // Field exists, validate its type
-                                                self.validate_value(
-                                                    field_value,
+                                                let result = self.validate_value(
+                                                    &mut field_pair.value,
                                                     &field_def.type_spec,
                                                     &field_def.name,
+                                                    &if field_path.is_empty() {
+                                                        field_def.name.clone()
+                                                    } else {
+                                                        format!("{field_path}.{}", field_def.name)
+                                                    },
                                                     imports, // Pass the imports here
                                                     file_path,
                                                     source_text,
-                                                )?;
+                                                );
+                                                self.collect_validation(result)?;
                                             } else {
-                                                // Field missing
-                                                if field_def.default_value.is_none() {
-                                                    return Err(ResolverError::Validation(
-                                                        ValidationError::MissingField {
-                                                            field_name: field_def.name.clone(),
-                                                            struct_name: type_name.clone(),
-                                                            src: Arc::from(NamedSource::new(
-                                                                file_path.to_string_lossy(),
-                                                                source_text.to_string(),
-                                                            )),
-                                                            span: (
-                                                                value.pos_start,
-                                                                value.pos_end - value.pos_start,
-                                                            )
-                                                                .into(),
-                                                        },
-                                                    ));
+                                                // Field missing. Fields of a `one_of` group
+                                                // are checked together below.
+                                                if field_def.default_value.is_none()
+                                                    && !field_def.optional
+                                                    && !struct_def
+                                                        .one_of
+                                                        .iter()
+                                                        .flatten()
+                                                        .any(|name| name == &field_def.name)
+                                                {
+                                                    self.collect_validation(Err(
+                                                        ResolverError::Validation(
+                                                            ValidationError::MissingField {
+                                                                field_name: field_def.name.clone(),
+                                                                struct_name: type_name.clone(),
+                                                                src: Arc::from(NamedSource::new(
+                                                                    file_path.to_string_lossy(),
+                                                                    source_text.to_string(),
+                                                                )),
+                                                                span: source_span(
+                                                                    value.pos_start,
+                                                                    value.pos_end,
+                                                                ),
+                                                            },
+                                                        ),
+                                                    ))?;
                                                 }
                                                 // Field is missing, but has a default value.
-                                                // We need to insert it into the object.
+                                                // We need to insert it into the object. Alias
+                                                // resolution already ran, so resolve the default
+                                                // here in case it references an anchor.
                                                 if let Some(default_value) =
-                                                    &field_def.default_value
+                                                    field_def.default_value.as_ref().filter(|_| {
+                                                        !given_groups.contains(&field_def.name)
+                                                    })
                                                 {
+                                                    let mut default_value = self.resolve_value(
+                                                        default_value.clone(),
+                                                        file_path,
+                                                        source_text,
+                                                    )?;
+                                                    // Defaults are validated like given values,
+                                                    // so bare words become enum values,
+                                                    // quantities are normalized, and struct-typed
+                                                    // defaults, also inside arrays, get the
+                                                    // defaults of their own missing fields. A
+                                                    // `null` default marks a field that may be
+                                                    // left out, whatever its type.
+                                                    if default_value.kind != MonValueKind::Null {
+                                                        self.validate_value(
+                                                            &mut default_value,
+                                                            &field_def.type_spec,
+                                                            &field_def.name,
+                                                            &if field_path.is_empty() {
+                                                                field_def.name.clone()
+                                                            } else {
+                                                                format!(
+                                                                    "{field_path}.{}",
+                                                                    field_def.name
+                                                                )
+                                                            },
+                                                            imports,
+                                                            file_path,
+                                                            source_text,
+                                                        )?;
+                                                    }
                                                     new_members.push(Member::Pair(
                                                         crate::ast::Pair {
                                                             key: field_def.name.clone(),
-                                                            value: default_value.clone(),
+                                                            key_span: (0, 0).into(),
+                                                            value: default_value,
                                                             validation: None,
+                                                            serialized_key: field_def.alias.clone(),
                                                         },
                                                     ));
                                                 }
@@ -837,29 +1944,31 @@ impl Resolver {
                                         }
                                         value_members.extend(new_members);
 
-                                        // Check for extra fields
+                                        // Check for extra fields, unless the struct is open
                                         for member in value_members.iter() {
                                             if let Member::Pair(pair) = member {
-                                                if !struct_def
-                                                    .fields
-                                                    .iter()
-                                                    .any(|f| f.name == pair.key)
+                                                if !struct_def.open
+                                                    && !struct_def
+                                                        .fields
+                                                        .iter()
+                                                        .any(|f| f.name == pair.key)
                                                 {
-                                                    return Err(ResolverError::Validation(
-                                                        ValidationError::UnexpectedField {
-                                                            field_name: pair.key.clone(),
-                                                            struct_name: type_name.clone(),
-                                                            src: Arc::from(NamedSource::new(
-                                                                file_path.to_string_lossy(),
-                                                                source_text.to_string(),
-                                                            )),
-                                                            span: (
-                                                                value.pos_start,
-                                                                value.pos_end - value.pos_start,
-                                                            )
-                                                                .into(),
-                                                        },
-                                                    ));
+                                                    self.collect_validation(Err(
+                                                        ResolverError::Validation(
+                                                            ValidationError::UnexpectedField {
+                                                                field_name: pair.key.clone(),
+                                                                struct_name: type_name.clone(),
+                                                                src: Arc::from(NamedSource::new(
+                                                                    file_path.to_string_lossy(),
+                                                                    source_text.to_string(),
+                                                                )),
+                                                                span: source_span(
+                                                                    value.pos_start,
+                                                                    value.pos_end,
+                                                                ),
+                                                            },
+                                                        ),
+                                                    ))?;
                                                 }
                                             }
                                         }
@@ -867,47 +1976,63 @@ impl Resolver {
                                         return Err(ResolverError::Validation(
                                             ValidationError::TypeMismatch {
                                                 field_name: field_name.to_string(),
+                                                path: field_path.to_string(),
                                                 expected_type: type_name.clone(),
                                                 found_type: format!("{:?}", value.kind),
                                                 src: Arc::from(NamedSource::new(
                                                     file_path.to_string_lossy(),
                                                     source_text.to_string(),
                                                 )),
-                                                span: (
-                                                    value.pos_start,
-                                                    value.pos_end - value.pos_start,
-                                                )
-                                                    .into(),
+                                                span: value.pos_span(),
                                             },
                                         ));
                                     }
                                 }
                                 TypeDef::Enum(enum_def) => {
+                                    // A bare word names a variant of the expected enum,
+                                    // e.g. `status :: Status = Active`
+                                    if let MonValueKind::BareWord(word) = &value.kind {
+                                        value.kind = MonValueKind::EnumValue {
+                                            enum_name: type_name.clone(),
+                                            variant_name: word.clone(),
+                                        };
+                                    }
                                     // Validate against enum
                                     if let MonValueKind::EnumValue {
                                         enum_name,
                                         variant_name,
                                     } = &value.kind
                                     {
-                                        if enum_name != type_name {
+                                        // The value may name the enum differently than the
+                                        // type spec (e.g. through a namespace import), so compare
+                                        // the definitions they point to.
+                                        let same_enum = enum_name == type_name
+                                            || matches!(
+                                                self.lookup_type_def(
+                                                    enum_name,
+                                                    imports,
+                                                    value.pos_span(),
+                                                    file_path,
+                                                    source_text,
+                                                )?,
+                                                Some(TypeDef::Enum(ref def)) if *def == enum_def
+                                            );
+                                        if !same_enum {
                                             return Err(ResolverError::Validation(
                                                 ValidationError::TypeMismatch {
                                                     field_name: field_name.to_string(),
+                                                    path: field_path.to_string(),
                                                     expected_type: format!("enum {}", type_name),
                                                     found_type: format!("enum {}", enum_name),
                                                     src: Arc::from(NamedSource::new(
                                                         file_path.to_string_lossy(),
                                                         source_text.to_string(),
                                                     )),
-                                                    span: (
-                                                        value.pos_start,
-                                                        value.pos_end - value.pos_start,
-                                                    )
-                                                        .into(),
+                                                    span: value.pos_span(),
                                                 },
                                             ));
                                         }
-                                        if !enum_def.variants.contains(variant_name) {
+                                        if !enum_def.has_variant(variant_name) {
                                             return Err(ResolverError::Validation(
                                                 ValidationError::UndefinedEnumVariant {
                                                     variant_name: variant_name.clone(),
@@ -916,11 +2041,7 @@ impl Resolver {
                                                         file_path.to_string_lossy(),
                                                         source_text.to_string(),
                                                     )),
-                                                    span: (
-                                                        value.pos_start,
-                                                        value.pos_end - value.pos_start,
-                                                    )
-                                                        .into(),
+                                                    span: value.pos_span(),
                                                 },
                                             ));
                                         }
@@ -928,31 +2049,47 @@ impl Resolver {
                                         return Err(ResolverError::Validation(
                                             ValidationError::TypeMismatch {
                                                 field_name: field_name.to_string(),
+                                                path: field_path.to_string(),
                                                 expected_type: format!("enum {}", type_name),
                                                 found_type: format!("{:?}", value.kind),
                                                 src: Arc::from(NamedSource::new(
                                                     file_path.to_string_lossy(),
                                                     source_text.to_string(),
                                                 )),
-                                                span: (
-                                                    value.pos_start,
-                                                    value.pos_end - value.pos_start,
-                                                )
-                                                    .into(),
+                                                span: value.pos_span(),
                                             },
                                         ));
                                     }
                                 }
+                                TypeDef::Alias(_) => {
+                                    let target = self.expand_alias(
+                                        type_spec,
+                                        imports,
+                                        value.pos_span(),
+                                        file_path,
+                                        source_text,
+                                    )?;
+                                    return self.validate_value(
+                                        value,
+                                        &target,
+                                        field_name,
+                                        field_path,
+                                        imports,
+                                        file_path,
+                                        source_text,
+                                    );
+                                }
                             }
                         } else {
                             return Err(ResolverError::Validation(
                                 ValidationError::UndefinedType {
                                     type_name: type_name.clone(),
+                                    help: self.undefined_type_help(type_name),
                                     src: Arc::from(NamedSource::new(
                                         file_path.to_string_lossy(),
                                         source_text.to_string(),
                                     )),
-                                    span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                                    span: value.pos_span(),
                                 },
                             ));
                         }
@@ -961,11 +2098,14 @@ impl Resolver {
             }
             TypeSpec::Collection(collection_types, _) => {
                 // Handle array validation
+                let array_span = value.pos_span();
                 if let MonValueKind::Array(elements) = &mut value.kind {
                     self.validate_collection(
                         elements,
+                        array_span,
                         collection_types,
                         field_name,
+                        field_path,
                         imports, // Pass the imports here
                         file_path,
                         source_text,
@@ -973,207 +2113,329 @@ impl Resolver {
                 } else {
                     return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                         field_name: field_name.to_string(),
+                        path: field_path.to_string(),
                         expected_type: "Array".to_string(),
                         found_type: format!("{:?}", value.kind),
                         src: Arc::from(NamedSource::new(
                             file_path.to_string_lossy(),
                             source_text.to_string(),
                         )),
-                        span: (value.pos_start, value.pos_end - value.pos_start).into(),
+                        span: value.pos_span(),
                     }));
                 }
             }
-            TypeSpec::Spread(_, _) => {
-                // Spread types are handled during parsing/resolution, not validation directly
-                return Ok(());
-            }
-        }
-        Ok(())
-    }
-
-    /// Validates the elements of an array against a slice of collection `TypeSpec`s.
-    fn validate_collection(
-        &mut self,
-        elements: &mut [MonValue],
-        collection_types: &[TypeSpec],
-        field_name: &str,
-        imports: &[ImportStatement], // Change this parameter
-        file_path: &PathBuf,
-        source_text: &str,
-    ) -> Result<(), ResolverError> {
-        // Case 1: [T] - Exactly one element of type T
-        if collection_types.len() == 1 && !matches!(collection_types[0], TypeSpec::Spread(_, _)) {
-            self.validate_value(
-                &mut elements[0],
-                &collection_types[0],
-                field_name,
-                imports, // Pass the imports here
-                file_path,
-                source_text,
-            )?;
-            return Ok(());
-        }
-
-        // Case 2: [T...] - Zero or more elements of type T
-        if collection_types.len() == 1 && matches!(collection_types[0], TypeSpec::Spread(_, _)) {
-            if let TypeSpec::Spread(inner_type, _) = &collection_types[0] {
-                for element in elements {
+            TypeSpec::Repeat(element_type, count, _) => {
+                let MonValueKind::Array(elements) = &mut value.kind else {
+                    return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                        field_name: field_name.to_string(),
+                        path: field_path.to_string(),
+                        expected_type: "Array".to_string(),
+                        found_type: format!("{:?}", value.kind),
+                        src: Arc::from(NamedSource::new(
+                            file_path.to_string_lossy(),
+                            source_text.to_string(),
+                        )),
+                        span: value.pos_span(),
+                    }));
+                };
+                if elements.len() != *count {
+                    return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                        field_name: field_name.to_string(),
+                        path: field_path.to_string(),
+                        expected_type: format!("array with exactly {count} elements"),
+                        found_type: format!("array with {} elements", elements.len()),
+                        src: Arc::from(NamedSource::new(
+                            file_path.to_string_lossy(),
+                            source_text.to_string(),
+                        )),
+                        span: value.pos_span(),
+                    }));
+                }
+                for (i, element) in elements.iter_mut().enumerate() {
                     self.validate_value(
                         element,
-                        inner_type,
+                        element_type,
                         field_name,
+                        &format!("{field_path}[{i}]"),
                         imports,
                         file_path,
                         source_text,
                     )?;
                 }
+            }
+            TypeSpec::Spread(_, _) => {
+                // Spread types are handled during parsing/resolution, not validation directly
                 return Ok(());
             }
         }
+        Ok(())
+    }
 
-        // Case 3: Tuple-like [T1, T2, ...]
-        let has_spread = collection_types
-            .iter()
-            .any(|t| matches!(t, TypeSpec::Spread(_, _)));
-        if !has_spread {
-            if elements.len() != collection_types.len() {
-                // TODO: Better error for wrong number of elements
-                return Err(ResolverError::Validation(ValidationError::TypeMismatch {
-                    field_name: field_name.to_string(),
-                    expected_type: format!("tuple with {} elements", collection_types.len()),
-                    found_type: format!("tuple with {} elements", elements.len()),
+    /// Follows `type_spec` through `#alias` definitions until it is something other than the
+    /// name of an alias, e.g. `Port` with `Port: #alias Number` becomes `Number`.
+    fn expand_alias(
+        &self,
+        type_spec: &TypeSpec,
+        imports: &[ImportStatement],
+        span: SourceSpan,
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<TypeSpec, ResolverError> {
+        let mut type_spec = type_spec.clone();
+        let mut chain: Vec<String> = Vec::new();
+        while let TypeSpec::Simple(name, _) = &type_spec {
+            let Some(TypeDef::Alias(target)) =
+                self.lookup_type_def(name, imports, span, file_path, source_text)?
+            else {
+                break;
+            };
+            if chain.contains(name) {
+                return Err(ResolverError::CyclicAlias {
+                    name: name.clone(),
+                    cycle: format!("{} -> {name}", chain.join(" -> ")),
                     src: Arc::from(NamedSource::new(
                         file_path.to_string_lossy(),
                         source_text.to_string(),
                     )),
-                    span: (
-                        elements.first().map_or(0, |e| e.pos_start),
-                        elements.last().map_or(0, |e| e.pos_end)
-                            - elements.first().map_or(0, |e| e.pos_start),
-                    )
-                        .into(),
-                }));
+                    span,
+                });
             }
-            for (i, element) in elements.iter_mut().enumerate() {
-                self.validate_value(
-                    element,
-                    &collection_types[i],
-                    field_name,
-                    imports, // Pass the imports here
-                    file_path,
-                    source_text,
-                )?;
+            chain.push(name.clone());
+            type_spec = target;
+        }
+        Ok(type_spec)
+    }
+
+    /// Looks up a user-defined type by name, following namespace imports for `ns.Type` names.
+    ///
+    /// Returns `Ok(None)` if the type is not defined, and an error if the namespace itself is unknown.
+    fn lookup_type_def(
+        &self,
+        type_name: &str,
+        imports: &[ImportStatement],
+        span: SourceSpan,
+        file_path: &Path,
+        source_text: &str,
+    ) -> Result<Option<TypeDef>, ResolverError> {
+        let (namespace, type_name_part) = if let Some((ns, tn)) = type_name.split_once('.') {
+            (Some(ns), tn)
+        } else {
+            (None, type_name)
+        };
+
+        let Some(namespace) = namespace else {
+            return Ok(self
+                .symbol_table
+                .types
+                .get(type_name_part)
+                .map(|td| td.def_type.clone()));
+        };
+
+        // Find the import statement for this namespace
+        let import_statement = imports
+            .iter()
+            .find(|i| {
+                if let ImportSpec::Namespace(ns) = &i.spec {
+                    ns == namespace
+                } else {
+                    false
+                }
+            })
+            .ok_or_else(|| {
+                ResolverError::Validation(ValidationError::UndefinedType {
+                    type_name: type_name.to_string(),
+                    help: self.undefined_type_help(type_name),
+                    src: Arc::from(NamedSource::new(
+                        file_path.to_string_lossy(),
+                        source_text.to_string(),
+                    )),
+                    span,
+                })
+            })?;
+
+        let imported_path_str = import_statement.path.trim_matches('"');
+        let parent_dir = file_path.parent().ok_or_else(|| {
+            // This case is unlikely but good to handle.
+            // It means the file path is something like "/" or "C:\"
+            ResolverError::ModuleNotFound {
+                path: import_statement.path.clone(),
+                src: Arc::from(NamedSource::new(
+                    file_path.to_string_lossy(),
+                    source_text.to_string(),
+                )),
+                span: source_span(import_statement.pos_start, import_statement.pos_end),
             }
-            return Ok(());
+        })?;
+        let absolute_imported_path = self.resolve_import_path(imported_path_str, parent_dir);
+
+        let imported_doc = self
+            .resolved_documents
+            .get(&absolute_imported_path)
+            .ok_or_else(|| {
+                // This indicates a logic error in the resolver, as the document
+                // should have been resolved and stored during the initial import pass.
+                ResolverError::ModuleNotFound {
+                    path: absolute_imported_path.to_string_lossy().to_string(),
+                    src: Arc::from(NamedSource::new(
+                        file_path.to_string_lossy(),
+                        source_text.to_string(),
+                    )),
+                    span,
+                }
+            })?;
+
+        if let MonValueKind::Object(members) = &imported_doc.root.kind {
+            Ok(members.iter().find_map(|m| {
+                if let Member::TypeDefinition(td) = m {
+                    if td.name == type_name_part {
+                        return Some(td.def_type.clone());
+                    }
+                }
+                None
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Builds the help text for an undefined type, suggesting the closest known type in strict mode.
+    fn undefined_type_help(&self, type_name: &str) -> String {
+        let help = "Ensure the type is in scope or imported correctly.".to_string();
+        if !self.strict {
+            return help;
         }
 
-        // Case 4: [T1, T2...] - One or more elements, first is T1, rest are T2
-        if collection_types.len() == 2
-            && !matches!(collection_types[0], TypeSpec::Spread(_, _))
-            && matches!(collection_types[1], TypeSpec::Spread(_, _))
-        {
-            if elements.is_empty() {
-                return Err(ResolverError::Validation(ValidationError::TypeMismatch {
-                    field_name: field_name.to_string(),
-                    expected_type: "array with at least 1 element".to_string(),
-                    found_type: "empty array".to_string(),
-                    src: Arc::from(NamedSource::new(
-                        file_path.to_string_lossy(),
-                        source_text.to_string(),
-                    )),
-                    span: (
-                        elements.first().map_or(0, |e| e.pos_start),
-                        elements.last().map_or(0, |e| e.pos_end)
-                            - elements.first().map_or(0, |e| e.pos_start),
-                    )
-                        .into(),
-                }));
-            }
-            self.validate_value(
-                &mut elements[0],
-                &collection_types[0],
-                field_name,
-                imports, // Pass the imports here
-                file_path,
-                source_text,
-            )?;
-            if let TypeSpec::Spread(inner_type, _) = &collection_types[1] {
-                for element in &mut elements[1..] {
-                    self.validate_value(
-                        element,
-                        inner_type,
-                        field_name,
-                        imports,
-                        file_path,
-                        source_text,
-                    )?;
-                }
-            }
-            return Ok(());
+        let max_distance = std::cmp::max(1, type_name.chars().count() / 3);
+        let suggestion = BUILTIN_TYPES
+            .iter()
+            .copied()
+            .chain(self.symbol_table.types.keys().map(String::as_str))
+            .map(|candidate| (levenshtein(type_name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            // Break ties by name so the suggestion does not depend on hash map order
+            .min();
+
+        match suggestion {
+            Some((_, candidate)) => format!("{help} Did you mean '{candidate}'?"),
+            None => help,
         }
+    }
 
-        // Case 5: [T1..., T2] - One or more elements, last is T2, rest are T1
-        if collection_types.len() == 2
-            && matches!(collection_types[0], TypeSpec::Spread(_, _))
-            && !matches!(collection_types[1], TypeSpec::Spread(_, _))
-        {
-            if elements.is_empty() {
-                return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+    /// Validates the elements of an array against a slice of collection `TypeSpec`s.
+    ///
+    /// The specs are split around an optional spread: the fixed specs before it match the
+    /// leading elements, the fixed specs after it match the trailing elements (counted from
+    /// the end), and the spread absorbs everything in between. Without a spread, the array
+    /// must have exactly one element per spec.
+    #[allow(clippy::too_many_arguments)]
+    fn validate_collection(
+        &mut self,
+        elements: &mut [MonValue],
+        array_span: SourceSpan,
+        collection_types: &[TypeSpec],
+        field_name: &str,
+        field_path: &str,
+        imports: &[ImportStatement], // Change this parameter
+        file_path: &PathBuf,
+        source_text: &str,
+    ) -> Result<(), ResolverError> {
+        // Errors about the elements as a whole point at them, or at the brackets when there
+        // are none.
+        let elements_span = match (elements.first(), elements.last()) {
+            (Some(first), Some(last)) => source_span(first.pos_start, last.pos_end),
+            _ => array_span,
+        };
+
+        let mut spread_positions = collection_types
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| matches!(t, TypeSpec::Spread(_, _)))
+            .map(|(i, _)| i);
+        let spread_position = spread_positions.next();
+
+        // More than one spread makes the split ambiguous, e.g. `[A..., B...]`. The parser
+        // rejects such specs, but they can still be built by hand.
+        if spread_positions.next().is_some() {
+            return Err(ResolverError::Validation(
+                ValidationError::UnimplementedCollectionValidation {
                     field_name: field_name.to_string(),
-                    expected_type: "array with at least 1 element".to_string(),
-                    found_type: "empty array".to_string(),
                     src: Arc::from(NamedSource::new(
                         file_path.to_string_lossy(),
                         source_text.to_string(),
                     )),
-                    span: (
-                        elements.first().map_or(0, |e| e.pos_start),
-                        elements.last().map_or(0, |e| e.pos_end)
-                            - elements.first().map_or(0, |e| e.pos_start),
-                    )
-                        .into(),
-                }));
-            }
-            let (head, last) = elements.split_at_mut(elements.len() - 1);
-            self.validate_value(
-                last.first_mut().unwrap(), // Get the last element
-                &collection_types[1],
-                field_name,
-                imports, // Pass the imports here
-                file_path,
-                source_text,
-            )?;
-            if let TypeSpec::Spread(inner_type, _) = &collection_types[0] {
-                for element in head {
-                    self.validate_value(
-                        element,
-                        inner_type,
-                        field_name,
-                        imports,
-                        file_path,
-                        source_text,
-                    )?;
-                }
-            }
-            return Ok(());
+                    span: elements_span,
+                },
+            ));
         }
 
-        // If none of the specific cases match, it's an unimplemented complex collection type
-        Err(ResolverError::Validation(
-            ValidationError::UnimplementedCollectionValidation {
+        let (leading, spread, trailing) = match spread_position {
+            Some(i) => (
+                &collection_types[..i],
+                Some(&collection_types[i]),
+                &collection_types[i + 1..],
+            ),
+            None => (collection_types, None, &collection_types[..0]),
+        };
+        let fixed_count = leading.len() + trailing.len();
+
+        if spread.is_none() && elements.len() != fixed_count {
+            // TODO: Better error for wrong number of elements
+            return Err(ResolverError::Validation(ValidationError::TypeMismatch {
                 field_name: field_name.to_string(),
+                path: field_path.to_string(),
+                expected_type: format!("tuple with {fixed_count} elements"),
+                found_type: format!("tuple with {} elements", elements.len()),
                 src: Arc::from(NamedSource::new(
                     file_path.to_string_lossy(),
                     source_text.to_string(),
                 )),
-                span: (
-                    elements.first().map_or(0, |e| e.pos_start),
-                    elements.last().map_or(0, |e| e.pos_end)
-                        - elements.first().map_or(0, |e| e.pos_start),
-                )
-                    .into(),
-            },
-        ))
+                span: elements_span,
+            }));
+        }
+        if elements.len() < fixed_count {
+            return Err(ResolverError::Validation(ValidationError::TypeMismatch {
+                field_name: field_name.to_string(),
+                path: field_path.to_string(),
+                expected_type: format!(
+                    "array with at least {fixed_count} element{}",
+                    if fixed_count == 1 { "" } else { "s" }
+                ),
+                found_type: if elements.is_empty() {
+                    "empty array".to_string()
+                } else {
+                    format!("array with {} elements", elements.len())
+                },
+                src: Arc::from(NamedSource::new(
+                    file_path.to_string_lossy(),
+                    source_text.to_string(),
+                )),
+                span: elements_span,
+            }));
+        }
+
+        let rest_end = elements.len() - trailing.len();
+        for (i, element) in elements.iter_mut().enumerate() {
+            let element_type = if i < leading.len() {
+                &leading[i]
+            } else if i >= rest_end {
+                &trailing[i - rest_end]
+            } else if let Some(TypeSpec::Spread(inner_type, _)) = spread {
+                inner_type
+            } else {
+                unreachable!("elements between the fixed specs only exist with a spread")
+            };
+            let result = self.validate_value(
+                element,
+                element_type,
+                field_name,
+                &format!("{field_path}[{i}]"),
+                imports,
+                file_path,
+                source_text,
+            );
+            self.collect_validation(result)?;
+        }
+        Ok(())
     }
 }
 
@@ -1183,6 +2445,281 @@ impl Default for Resolver {
     }
 }
 
+/// Replaces the guarded members in `value` and the values nested inside it by their member
+/// if the guard holds for `env`, and drops them otherwise.
+fn apply_guards(value: &mut MonValue, env: &HashMap<String, String>) {
+    value.walk_mut(&mut |value| {
+        if let MonValueKind::Object(members) = &mut value.kind {
+            *members = std::mem::take(members)
+                .into_iter()
+                .filter_map(|member| unwrap_guards(member, env))
+                .collect();
+        }
+    });
+}
+
+/// Returns the member a guarded `member` applies, if its guards hold for `env`, or `member`
+/// itself if it has no guard. Members nested in its value are left as they are.
+pub(crate) fn unwrap_guards(member: Member, env: &HashMap<String, String>) -> Option<Member> {
+    match member {
+        Member::Guarded(guard, member) if guard.holds(env) => unwrap_guards(*member, env),
+        Member::Guarded(..) => None,
+        member => Some(member),
+    }
+}
+
+/// Registers the anchor of `value` and of every value nested inside it.
+///
+/// Anchors can be declared on a key (`&name: value`) or inline on any value
+/// (`key: &name { ... }`, `[&name [1, 2], ...]`), so the whole tree is walked.
+fn collect_anchors(anchors: &mut HashMap<String, MonValue>, value: &MonValue) {
+    if let Some(anchor_name) = &value.anchor {
+        anchors.insert(anchor_name.clone(), value.clone());
+    }
+    match &value.kind {
+        MonValueKind::Object(members) => {
+            for member in members {
+                if let Member::Pair(pair) = member {
+                    collect_anchors(anchors, &pair.value);
+                }
+            }
+        }
+        MonValueKind::Array(elements) => {
+            for element in elements {
+                collect_anchors(anchors, element);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fails with [`ResolverError::UnknownOneOfField`] if a `one_of` group of the struct `name`
+/// lists a field the struct does not have.
+fn check_one_of_fields(
+    name: &str,
+    struct_def: &StructDef,
+    file_path: &Path,
+    source_text: &str,
+) -> Result<(), ResolverError> {
+    let unknown = struct_def
+        .one_of
+        .iter()
+        .flatten()
+        .find(|field| !struct_def.fields.iter().any(|f| &f.name == *field));
+    match unknown {
+        Some(field) => Err(ResolverError::UnknownOneOfField {
+            struct_name: name.to_string(),
+            field: field.clone(),
+            src: Arc::from(NamedSource::new(
+                file_path.to_string_lossy(),
+                source_text.to_string(),
+            )),
+            span: source_span(struct_def.pos_start, struct_def.pos_end),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fails with [`ResolverError::DuplicateFieldKey`] if two fields of the struct `name` are
+/// serialized under the same key, their alias or else their name.
+fn check_field_keys(
+    name: &str,
+    struct_def: &StructDef,
+    file_path: &Path,
+    source_text: &str,
+) -> Result<(), ResolverError> {
+    let mut keys = HashSet::new();
+    let duplicate = struct_def
+        .fields
+        .iter()
+        .map(|field| field.alias.as_deref().unwrap_or(&field.name))
+        .find(|key| !keys.insert(*key));
+    match duplicate {
+        Some(key) => Err(ResolverError::DuplicateFieldKey {
+            struct_name: name.to_string(),
+            key: key.to_string(),
+            src: Arc::from(NamedSource::new(
+                file_path.to_string_lossy(),
+                source_text.to_string(),
+            )),
+            span: source_span(struct_def.pos_start, struct_def.pos_end),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fails with [`ResolverError::UnexpectedBareWord`] for the first bare word left in `value`.
+fn reject_bare_words(
+    value: &MonValue,
+    file_path: &Path,
+    source_text: &str,
+) -> Result<(), ResolverError> {
+    let Some(bare_word) = find_value(value, &|v| matches!(v.kind, MonValueKind::BareWord(_)))
+    else {
+        return Ok(());
+    };
+    let MonValueKind::BareWord(word) = &bare_word.kind else {
+        unreachable!("only bare words are searched for");
+    };
+    Err(ResolverError::UnexpectedBareWord {
+        word: word.clone(),
+        src: Arc::from(NamedSource::new(
+            file_path.to_string_lossy(),
+            source_text.to_string(),
+        )),
+        span: bare_word.get_source_span(),
+    })
+}
+
+/// Fails with [`ValidationError::NonFiniteNumber`] for the first infinite or NaN number in
+/// `value`, which JSON cannot represent.
+fn reject_non_finite_numbers(
+    value: &MonValue,
+    file_path: &Path,
+    source_text: &str,
+) -> Result<(), ResolverError> {
+    let Some(number) = find_value(
+        value,
+        &|v| matches!(v.kind, MonValueKind::Number(n) if !n.is_finite()),
+    ) else {
+        return Ok(());
+    };
+    Err(ResolverError::Validation(
+        ValidationError::NonFiniteNumber {
+            src: Arc::from(NamedSource::new(
+                file_path.to_string_lossy(),
+                source_text.to_string(),
+            )),
+            span: number.pos_span(),
+        },
+    ))
+}
+
+/// Returns the first value in `value`, itself included, for which `predicate` holds, in
+/// document order.
+fn find_value<'a>(
+    value: &'a MonValue,
+    predicate: &impl Fn(&MonValue) -> bool,
+) -> Option<&'a MonValue> {
+    if predicate(value) {
+        return Some(value);
+    }
+    match &value.kind {
+        MonValueKind::Object(members) => members.iter().find_map(|member| match member {
+            Member::Pair(pair) => find_value(&pair.value, predicate),
+            _ => None,
+        }),
+        MonValueKind::Array(elements) => elements
+            .iter()
+            .find_map(|element| find_value(element, predicate)),
+        _ => None,
+    }
+}
+
+/// Turns a possibly relative file name into an absolute path, used for resolving imports.
+pub(crate) fn absolute_file_path(file_name: &str) -> Result<PathBuf, ResolverError> {
+    let path = PathBuf::from(file_name);
+    if path.is_relative() {
+        let current_dir =
+            std::env::current_dir().map_err(|err| ResolverError::CurrentDirUnavailable {
+                reason: err.to_string(),
+            })?;
+        Ok(current_dir.join(path))
+    } else {
+        Ok(path)
+    }
+}
+
+/// Turns a failure to read an imported module into the error matching its cause.
+pub(crate) fn module_read_error(
+    err: &std::io::Error,
+    path: String,
+    src: Arc<NamedSource<String>>,
+    span: SourceSpan,
+) -> ResolverError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => ResolverError::ModuleNotFound { path, src, span },
+        std::io::ErrorKind::PermissionDenied => {
+            ResolverError::ModulePermissionDenied { path, src, span }
+        }
+        std::io::ErrorKind::InvalidData => ResolverError::ModuleNotUtf8 { path, src, span },
+        _ => ResolverError::ModuleReadFailed {
+            path,
+            reason: err.to_string(),
+            src,
+            span,
+        },
+    }
+}
+
+/// Returns `true` if `color` is a `#RGB` or `#RRGGBB` hex color.
+fn is_hex_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|digits| {
+        matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Returns `true` if the spread that produced `members[range]` copied at least one key and
+/// every one of them is overridden by a later member. With `null_as_absent`, a later `null`
+/// does not override.
+fn is_redundant_spread(
+    members: &[Member],
+    range: std::ops::Range<usize>,
+    null_as_absent: bool,
+) -> bool {
+    let pair_key = |member: &Member| match member {
+        Member::Pair(pair) => Some(pair.key.clone()),
+        _ => None,
+    };
+    let later_keys: HashSet<String> = members[range.end..]
+        .iter()
+        .filter(|member| {
+            !(null_as_absent
+                && matches!(member, Member::Pair(pair) if matches!(pair.value.kind, MonValueKind::Null)))
+        })
+        .filter_map(pair_key)
+        .collect();
+    let mut spread_keys = members[range].iter().filter_map(pair_key).peekable();
+    spread_keys.peek().is_some() && spread_keys.all(|key| later_keys.contains(&key))
+}
+
+/// Converts a quantity to the base unit of the built-in type `type_name`, i.e. a `Duration`
+/// to seconds or a `ByteSize` to bytes.
+///
+/// Returns `None` if the type is neither, or the value is not a quantity of that kind.
+fn normalized_quantity(kind: &MonValueKind, type_name: &str) -> Option<f64> {
+    let expected_kind = match type_name {
+        "Duration" => QuantityKind::Duration,
+        "ByteSize" => QuantityKind::ByteSize,
+        _ => return None,
+    };
+    let MonValueKind::Quantity { value, unit } = kind else {
+        return None;
+    };
+    match QuantityKind::of_unit(unit)? {
+        (kind, scale) if kind == expected_kind => Some(value * scale),
+        _ => None,
+    }
+}
+
+/// Computes the Levenshtein edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parser::Parser;
@@ -1242,11 +2779,65 @@ mod tests {
 
         if let crate::ast::Member::Pair(p) = alias_member {
             assert_eq!(p.value.kind, crate::ast::MonValueKind::Number(123.0));
+            // The copy is not the anchor itself
+            assert_eq!(p.value.anchor, None);
         } else {
             panic!("Expected a pair member");
         }
     }
 
+    #[test]
+    fn test_copies_are_not_anchors() {
+        let doc = resolve_ok(
+            "{ &x: 5, value: *x, &base: { &port: 80 }, copy: { ...*base }, &list: [&one 1], more: [...*list] }",
+            "test.mon",
+        );
+        let value = |key: &str| {
+            doc.root
+                .kind
+                .as_object()
+                .unwrap()
+                .iter()
+                .find_map(|member| match member {
+                    Member::Pair(pair) if pair.key == key => Some(&pair.value),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(value("x").anchor.as_deref(), Some("x"));
+        assert_eq!(value("value").anchor, None);
+        assert_eq!(value("copy").kind.as_object().unwrap().len(), 1);
+        let Member::Pair(port) = &value("copy").kind.as_object().unwrap()[0] else {
+            panic!("Expected a pair");
+        };
+        assert_eq!(port.value.anchor, None);
+        assert_eq!(value("more").kind.as_array().unwrap()[0].anchor, None);
+        // The definitions keep their anchors
+        assert_eq!(
+            value("list").kind.as_array().unwrap()[0].anchor.as_deref(),
+            Some("one")
+        );
+    }
+
+    #[test]
+    fn test_resolved_values_compare_structurally() {
+        let doc = resolve_ok(
+            "{ &x: 5, &base: { a: [1, *x] }, y: *x, z: { ...*base } }",
+            "test.mon",
+        );
+        let expected = Parser::new("{ x: 5, base: { a: [1, 5] }, y: 5, z: { a: [1, 5] } }")
+            .unwrap()
+            .parse_document()
+            .unwrap();
+        assert!(doc.root.structurally_eq(&expected.root));
+
+        let different = Parser::new("{ x: 5, base: { a: [1, 5] }, y: 6, z: { a: [1, 5] } }")
+            .unwrap()
+            .parse_document()
+            .unwrap();
+        assert!(!doc.root.structurally_eq(&different.root));
+    }
+
     #[test]
     fn test_object_spread_resolution() {
         let source = r#"{ 
@@ -1334,67 +2925,292 @@ mod tests {
             } else {
                 panic!("Expected debug to be a pair");
             }
-        } else {
-            panic!("Expected app_config to be a pair member");
+        } else {
+            panic!("Expected app_config to be a pair member");
+        }
+    }
+
+    #[test]
+    fn test_array_spread_resolution() {
+        let source = r#"{
+        &base_tags: ["tag1", "tag2"],
+        item_tags: [
+            "start",
+            ...*base_tags,
+            "end",
+        ]
+    }"#;
+        let doc = resolve_ok(source, "test.mon");
+
+        let root_object = match doc.root.kind {
+            crate::ast::MonValueKind::Object(members) => members,
+            _ => panic!("Expected an object"),
+        };
+
+        let item_tags_member = root_object
+            .iter()
+            .find(|m| {
+                if let crate::ast::Member::Pair(p) = m {
+                    p.key == "item_tags"
+                } else {
+                    false
+                }
+            })
+            .unwrap();
+
+        if let crate::ast::Member::Pair(p) = item_tags_member {
+            let item_tags_array = match &p.value.kind {
+                crate::ast::MonValueKind::Array(elements) => elements,
+                _ => panic!("Expected item_tags to be an array"),
+            };
+
+            assert_eq!(item_tags_array.len(), 4);
+            assert_eq!(
+                item_tags_array[0].kind,
+                crate::ast::MonValueKind::String("start".to_string())
+            );
+            assert_eq!(
+                item_tags_array[1].kind,
+                crate::ast::MonValueKind::String("tag1".to_string())
+            );
+            assert_eq!(
+                item_tags_array[2].kind,
+                crate::ast::MonValueKind::String("tag2".to_string())
+            );
+            assert_eq!(
+                item_tags_array[3].kind,
+                crate::ast::MonValueKind::String("end".to_string())
+            );
+        } else {
+            panic!("Expected item_tags to be a pair member");
+        }
+    }
+
+    #[test]
+    fn test_repeat_collection_validation() {
+        let source = r###"
+        {
+            Triple: #struct { values([String; 3]) },
+            ok :: Triple = { values: ["a", "b", "c"] },
+        }
+    "###;
+        resolve_ok(source, "test_repeat.mon");
+
+        let source = r###"
+        {
+            Triple: #struct { values([String; 3]) },
+            bad :: Triple = { values: ["a", "b"] },
+        }
+    "###;
+        match resolve_err(source, "test_repeat.mon") {
+            ResolverError::Validation(ValidationError::TypeMismatch {
+                expected_type,
+                found_type,
+                ..
+            }) => {
+                assert_eq!(expected_type, "array with exactly 3 elements");
+                assert_eq!(found_type, "array with 2 elements");
+            }
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
+        }
+
+        let source = r###"
+        {
+            Triple: #struct { values([String; 3]) },
+            bad :: Triple = { values: ["a", 2, "c"] },
+        }
+    "###;
+        match resolve_err(source, "test_repeat.mon") {
+            ResolverError::Validation(ValidationError::TypeMismatch { path, .. }) => {
+                assert_eq!(path, "bad.values[1]");
+            }
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_any_in_collections() {
+        let ok = [
+            ("[Any, String]", r#"[{ a: 1 }, "x"]"#),
+            ("[Any, String]", r#"[null, "x"]"#),
+            ("[Any...]", "[]"),
+            ("[Any...]", r#"[1, "x", [true], { a: null }]"#),
+            ("[String, Any...]", r#"["msg"]"#),
+            ("[String, Any...]", r#"["msg", 1, [2], { c: 3 }]"#),
+            ("[Any..., Number]", r#"["x", true, 3]"#),
+            ("[Any; 2]", "[[1], { a: 1 }]"),
+            ("Any", "[1, 2]"),
+        ];
+        for (type_spec, value) in ok {
+            let source = format!("{{ v :: {type_spec} = {value} }}");
+            resolve_ok(&source, "test_any.mon");
+        }
+
+        let bad = [
+            ("[Any, String]", r#"[1, 2]"#, "v[1]"),
+            ("[String, Any...]", r#"[1, "x"]"#, "v[0]"),
+            ("[Any..., Number]", r#"[1, "x"]"#, "v[1]"),
+        ];
+        for (type_spec, value, expected_path) in bad {
+            let source = format!("{{ v :: {type_spec} = {value} }}");
+            match resolve_err(&source, "test_any.mon") {
+                ResolverError::Validation(ValidationError::TypeMismatch { path, .. }) => {
+                    assert_eq!(path, expected_path, "{type_spec} = {value}");
+                }
+                err => panic!("Expected TypeMismatch error, but got {err:?}"),
+            }
+        }
+
+        for (type_spec, value) in [("[Any, String]", r#"["x"]"#), ("[Any; 2]", "[1]")] {
+            let source = format!("{{ v :: {type_spec} = {value} }}");
+            assert!(matches!(
+                resolve_err(&source, "test_any.mon"),
+                ResolverError::Validation(ValidationError::TypeMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_int_and_uint_validation() {
+        let source = r###"
+        {
+            Counter: #struct { count(Int), limit(UInt) },
+            ok :: Counter = { count: -3, limit: 0 },
+        }
+    "###;
+        resolve_ok(source, "test_int.mon");
+
+        for (bad, expected) in [
+            ("{ count: 1.5, limit: 1 }", "Int"),
+            ("{ count: 1, limit: -1 }", "UInt"),
+            ("{ count: \"1\", limit: 1 }", "Int"),
+        ] {
+            let source = format!(
+                "{{ Counter: #struct {{ count(Int), limit(UInt) }}, bad :: Counter = {bad} }}"
+            );
+            match resolve_err(&source, "test_int.mon") {
+                ResolverError::Validation(ValidationError::TypeMismatch {
+                    expected_type, ..
+                }) => assert_eq!(expected_type, expected),
+                err => panic!("Expected TypeMismatch error, but got {err:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_color_validation() {
+        resolve_ok(
+            r##"{ Theme: #struct { fg(Color), bg(Color) }, t :: Theme = { fg: "#1E90ff", bg: "#000" } }"##,
+            "test_color.mon",
+        );
+
+        for bad in ["\"1e90ff\"", "\"#12345\"", "\"#gggggg\"", "\"red\""] {
+            let source =
+                format!("{{ Theme: #struct {{ fg(Color) }}, t :: Theme = {{ fg: {bad} }} }}");
+            match resolve_err(&source, "test_color.mon") {
+                ResolverError::Validation(ValidationError::InvalidColor {
+                    path, value, ..
+                }) => {
+                    assert_eq!(path, "t.fg");
+                    assert_eq!(format!("{value:?}"), bad);
+                }
+                err => panic!("Expected InvalidColor error, but got {err:?}"),
+            }
+        }
+
+        match resolve_err("{ c :: Color = 255 }", "test_color.mon") {
+            ResolverError::Validation(ValidationError::TypeMismatch { expected_type, .. }) => {
+                assert_eq!(expected_type, "Color");
+            }
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
         }
     }
 
     #[test]
-    fn test_array_spread_resolution() {
-        let source = r#"{
-        &base_tags: ["tag1", "tag2"],
-        item_tags: [
-            "start",
-            ...*base_tags,
-            "end",
-        ]
-    }"#;
-        let doc = resolve_ok(source, "test.mon");
-
-        let root_object = match doc.root.kind {
-            crate::ast::MonValueKind::Object(members) => members,
-            _ => panic!("Expected an object"),
+    fn test_bytes_validation() {
+        let doc = resolve_ok(
+            r#"{ Tls: #struct { key(Bytes), cert(Bytes) }, tls :: Tls = { key: "0x00ff", cert: "aGk=" } }"#,
+            "test_bytes.mon",
+        );
+        let MonValueKind::Object(members) = &doc.root.kind else {
+            panic!("Expected an object");
         };
-
-        let item_tags_member = root_object
+        let tls = members
             .iter()
-            .find(|m| {
-                if let crate::ast::Member::Pair(p) = m {
-                    p.key == "item_tags"
-                } else {
-                    false
-                }
+            .find_map(|member| match member {
+                Member::Pair(pair) if pair.key == "tls" => Some(&pair.value),
+                _ => None,
             })
             .unwrap();
+        let MonValueKind::Object(fields) = &tls.kind else {
+            panic!("Expected an object");
+        };
+        let decoded = |key: &str| {
+            fields.iter().find_map(|member| match member {
+                Member::Pair(pair) if pair.key == key => Some(pair.value.kind.clone()),
+                _ => None,
+            })
+        };
+        assert_eq!(decoded("key"), Some(MonValueKind::Bytes(vec![0x00, 0xff])));
+        assert_eq!(decoded("cert"), Some(MonValueKind::Bytes(b"hi".to_vec())));
 
-        if let crate::ast::Member::Pair(p) = item_tags_member {
-            let item_tags_array = match &p.value.kind {
-                crate::ast::MonValueKind::Array(elements) => elements,
-                _ => panic!("Expected item_tags to be an array"),
-            };
+        match resolve_err(r#"{ key :: Bytes = "0xabc" }"#, "test_bytes.mon") {
+            ResolverError::Validation(ValidationError::InvalidBytes { path, value, .. }) => {
+                assert_eq!(path, "key");
+                assert_eq!(value, "0xabc");
+            }
+            err => panic!("Expected InvalidBytes error, but got {err:?}"),
+        }
+        match resolve_err("{ key :: Bytes = 12 }", "test_bytes.mon") {
+            ResolverError::Validation(ValidationError::TypeMismatch { expected_type, .. }) => {
+                assert_eq!(expected_type, "Bytes");
+            }
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
+        }
+    }
 
-            assert_eq!(item_tags_array.len(), 4);
-            assert_eq!(
-                item_tags_array[0].kind,
-                crate::ast::MonValueKind::String("start".to_string())
-            );
-            assert_eq!(
-                item_tags_array[1].kind,
-                crate::ast::MonValueKind::String("tag1".to_string())
-            );
-            assert_eq!(
-                item_tags_array[2].kind,
-                crate::ast::MonValueKind::String("tag2".to_string())
-            );
-            assert_eq!(
-                item_tags_array[3].kind,
-                crate::ast::MonValueKind::String("end".to_string())
-            );
-        } else {
-            panic!("Expected item_tags to be a pair member");
+    #[test]
+    fn test_missing_spread_source_points_at_spread() {
+        for source in ["{ a: { x: 1, ...*missing } }", "{ a: [1, ...*missing, 2] }"] {
+            match resolve_err(source, "test.mon") {
+                ResolverError::SpreadSourceNotFound { name, span, .. } => {
+                    assert_eq!(name, "missing");
+                    assert_eq!(span.offset(), source.find("...*missing").unwrap());
+                    assert_eq!(span.len(), "...*missing".len());
+                }
+                err => panic!("Expected SpreadSourceNotFound error, but got {err:?}"),
+            }
         }
     }
 
+    #[test]
+    fn test_inline_anchors_are_registered() {
+        let source = r#"{
+        defaults: &base { retries: 3 },
+        groups: [&first_group ["a", "b"], ["c"]],
+        service: { ...*base, name: "api" },
+        all: [...*first_group, "z"],
+    }"#;
+        let doc = resolve_ok(source, "test.mon");
+        let value = crate::serialization::to_value(&doc.root);
+
+        let crate::serialization::Value::Object(root) = value else {
+            panic!("Expected an object");
+        };
+        let crate::serialization::Value::Object(service) = &root["service"] else {
+            panic!("Expected service to be an object");
+        };
+        assert_eq!(service["retries"], crate::serialization::Value::Number(3.0));
+        assert_eq!(
+            root["all"],
+            crate::serialization::Value::Array(vec![
+                crate::serialization::Value::String("a".to_string()),
+                crate::serialization::Value::String("b".to_string()),
+                crate::serialization::Value::String("z".to_string()),
+            ])
+        );
+    }
+
     #[test]
     fn test_struct_validation_with_defaults_and_collections_ok() {
         let source = r###"
@@ -1497,6 +3313,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_struct_default_value_alias_is_resolved() {
+        let source = r###"
+        {
+            &default_roles: ["viewer", "editor"],
+            User: #struct {
+                name(String),
+                roles([String...]) = *default_roles,
+            },
+            user :: User = { name: "Alice" },
+        }
+    "###;
+        let doc = resolve_ok(source, "test_default_alias.mon");
+        let crate::ast::MonValueKind::Object(members) = doc.root.kind else {
+            panic!("Expected an object");
+        };
+        let user = members
+            .iter()
+            .find_map(|m| match m {
+                crate::ast::Member::Pair(p) if p.key == "user" => Some(&p.value),
+                _ => None,
+            })
+            .unwrap();
+        let crate::ast::MonValueKind::Object(user_members) = &user.kind else {
+            panic!("Expected user to be an object");
+        };
+        let roles = user_members
+            .iter()
+            .find_map(|m| match m {
+                crate::ast::Member::Pair(p) if p.key == "roles" => Some(&p.value),
+                _ => None,
+            })
+            .unwrap();
+        match &roles.kind {
+            crate::ast::MonValueKind::Array(values) => assert_eq!(values.len(), 2),
+            other => panic!("Expected the default to be resolved to an array, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_struct_validation_missing_required_field() {
         let source = r###"
@@ -1538,27 +3393,570 @@ mod tests {
                 assert_eq!(expected_type, "Number");
                 assert!(found_type.contains("String"));
             }
-            _ => panic!("Expected TypeMismatch error for id, but got {err:?}"),
+            _ => panic!("Expected TypeMismatch error for id, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_struct_validation_unexpected_field() {
+        let source = r###"
+        {
+            User: #struct { id(Number), name(String) },
+            invalid_user :: User = { id: 5, name: "David", age: 30 },
+        }
+    "###;
+        let err = resolve_err(source, "test_validation.mon");
+        match err {
+            crate::error::ResolverError::Validation(
+                crate::error::ValidationError::UnexpectedField { field_name, .. },
+            ) => {
+                assert_eq!(field_name, "age");
+            }
+            _ => panic!("Expected UnexpectedField error, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_spread_of_wrong_kind_points_at_spread() {
+        for (source, spread, code) in [
+            (
+                "{ &n: 1, list: [0, ...*n, 2] }",
+                "...*n",
+                "resolver::spread_on_non_array",
+            ),
+            (
+                "{ &n: [1], obj: { a: 0, ...*n } }",
+                "...*n",
+                "resolver::spread_on_non_object",
+            ),
+        ] {
+            let err = resolve_err(source, "test_spreads.mon");
+            assert_eq!(err.error_code(), code);
+            let (ResolverError::SpreadOnNonArray { span, .. }
+            | ResolverError::SpreadOnNonObject { span, .. }) = err
+            else {
+                panic!("Expected a spread error, got {err:?}");
+            };
+            assert_eq!(
+                (span.offset(), span.len()),
+                (source.find(spread).unwrap(), spread.len()),
+                "source: {source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_array_mismatch_points_at_brackets() {
+        for source in [
+            "{ User: #struct { permissions([String, Number]) }, u :: User = { permissions: [] } }",
+            "{ User: #struct { tags([String, String...]) }, u :: User = { tags: [] } }",
+        ] {
+            let err = resolve_err(source, "test_validation.mon");
+            let ResolverError::Validation(ValidationError::TypeMismatch { span, .. }) = err else {
+                panic!("Expected TypeMismatch, got {err:?}");
+            };
+            assert_eq!(
+                (span.offset(), span.len()),
+                (source.find("[]").unwrap(), 2),
+                "source: {source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_finite_number_is_rejected() {
+        // A literal too large for an `f64` is already rejected by the lexer
+        let source = "{ ok: 1e300, sizes: [1, 1e400] }";
+        let err = Parser::new_with_name(source, "test_numbers.mon".into())
+            .and_then(|mut parser| parser.parse_document())
+            .unwrap_err();
+        assert!(
+            matches!(&err, MonError::Parser(e) if e.error_code() == "lexer::invalid_token"),
+            "{err:?}"
+        );
+
+        // A quantity can still overflow when it is normalized to seconds
+        let source = "{ timeout :: Duration = 1e307h }";
+        let err = resolve_err(source, "test_numbers.mon");
+        let ResolverError::Validation(ValidationError::NonFiniteNumber { span, .. }) = err else {
+            panic!("Expected NonFiniteNumber, got {err:?}");
+        };
+        assert_eq!(
+            (span.offset(), span.len()),
+            (source.find("1e307h").unwrap(), "1e307h".len())
+        );
+        resolve_ok("{ zero: -0.0, big: 1e300 }", "test_numbers.mon");
+    }
+
+    #[test]
+    fn test_guarded_members() {
+        let source = r#"{
+            replicas: 1,
+            [@if(ENV == "prod")] replicas: 3,
+            [@if(ENV != "prod")] &debug: { verbose: true },
+            [@if(DEBUG)] ...*debug,
+            nested: { [@if(ENV == "prod")] [@if(REGION == "eu")] region: "eu" },
+            list: [{ [@if(ENV == "prod")] tier: "gold" }],
+        }"#;
+        let resolve = |options: ResolveOptions| {
+            let mut resolver = Resolver::new();
+            resolver.set_options(options);
+            let document = resolver.resolve_str(source, "guards.mon").unwrap();
+            serde_json::to_value(crate::serialization::to_value(&document.root)).unwrap()
+        };
+
+        let json = resolve(
+            ResolveOptions::default()
+                .with_env("ENV", "prod")
+                .with_env("REGION", "eu"),
+        );
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "replicas": 3.0,
+                "nested": { "region": "eu" },
+                "list": [{ "tier": "gold" }],
+            })
+        );
+
+        let json = resolve(ResolveOptions::default().with_env("DEBUG", ""));
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "replicas": 1.0,
+                "debug": { "verbose": true },
+                "verbose": true,
+                "nested": {},
+                "list": [{}],
+            })
+        );
+
+        // An anchor under a guard that does not hold is not defined
+        let mut resolver = Resolver::new();
+        resolver.set_options(
+            ResolveOptions::default()
+                .with_env("ENV", "prod")
+                .with_env("DEBUG", "1"),
+        );
+        let err = resolver.resolve_str(source, "guards.mon").unwrap_err();
+        assert_eq!(err.error_code(), "resolver::spread_source_not_found");
+    }
+
+    #[test]
+    fn test_struct_extends() {
+        // The base may be defined after the struct extending it, and extend another in turn
+        let source = r#"{
+            Admin: #struct extends User { level(Number), name(String) = "admin" },
+            User: #struct extends Entity { name(String) },
+            Entity: #struct { id(Number) },
+            admin :: Admin = { id: 1, level: 3 },
+        }"#;
+        let doc = resolve_ok(source, "test_extends.mon");
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["admin"],
+            serde_json::json!({ "id": 1.0, "level": 3.0, "name": "admin" })
+        );
+        let admin = doc
+            .root
+            .kind
+            .as_object()
+            .unwrap()
+            .iter()
+            .find_map(|member| match member {
+                Member::TypeDefinition(type_def) if type_def.name == "Admin" => {
+                    Some(type_def.clone())
+                }
+                _ => None,
+            })
+            .unwrap();
+        let TypeDef::Struct(admin) = admin.def_type else {
+            panic!("Expected struct definition");
+        };
+        let names: Vec<&str> = admin.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "name", "level"]);
+
+        // Base fields are required and validated like the struct's own
+        let err = resolve_err(
+            r#"{ User: #struct { name(String) }, Admin: #struct extends User { level(Number) }, admin :: Admin = { level: 3 } }"#,
+            "test_extends.mon",
+        );
+        assert_eq!(err.error_code(), "validation::missing_field");
+
+        for (source, code) in [
+            (
+                "{ A: #struct extends B {}, B: #struct extends C {}, C: #struct extends A {} }",
+                "resolver::cyclic_inheritance",
+            ),
+            (
+                "{ A: #struct extends A {} }",
+                "resolver::cyclic_inheritance",
+            ),
+            (
+                "{ Status: #enum { On }, A: #struct extends Status {} }",
+                "resolver::extends_non_struct",
+            ),
+            (
+                "{ A: #struct extends Missing {} }",
+                "validation::undefined_type",
+            ),
+        ] {
+            let err = resolve_err(source, "test_extends.mon");
+            assert_eq!(err.error_code(), code, "source: {source}");
+        }
+    }
+
+    #[test]
+    fn test_struct_extends_imported_base() {
+        let dir = TempDir::new().unwrap();
+        create_test_file(
+            dir.path(),
+            "schemas.mon",
+            "{ Entity: #struct { id(Number) }, User: #struct extends Entity { name(String) } }",
+        );
+        let main_path = create_test_file(
+            dir.path(),
+            "main.mon",
+            r#"import * as schemas from "./schemas.mon"
+            {
+                Admin: #struct extends schemas.User { level(Number) },
+                admin :: Admin = { id: 1, name: "root", level: 3 },
+            }"#,
+        );
+        let source = fs::read_to_string(&main_path).unwrap();
+        let doc = Resolver::new()
+            .resolve_str(&source, &main_path.to_string_lossy())
+            .unwrap();
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["admin"],
+            serde_json::json!({ "id": 1.0, "name": "root", "level": 3.0 })
+        );
+    }
+
+    #[test]
+    fn test_type_alias_validation() {
+        let schema = "Port: #alias Number, Entry: #alias [String, Port], Mode: #alias Status, \
+                      Status: #enum { On, Off }";
+        let doc = resolve_ok(
+            &format!(
+                r#"{{ {schema}, port :: Port = 80, entry :: Entry = ["web", 8080], mode :: Mode = On }}"#
+            ),
+            "test_validation.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(json["entry"], serde_json::json!(["web", 8080.0]));
+        let MonValueKind::Object(members) = &doc.root.kind else {
+            panic!("Expected an object root");
+        };
+        let mode = members
+            .iter()
+            .find_map(|m| match m {
+                Member::Pair(pair) if pair.key == "mode" => Some(&pair.value.kind),
+                _ => None,
+            })
+            .unwrap();
+        assert!(
+            matches!(mode, MonValueKind::EnumValue { enum_name, variant_name } if enum_name == "Status" && variant_name == "On"),
+            "mode: {mode:?}"
+        );
+
+        let err = resolve_err(
+            &format!(r#"{{ {schema}, entry :: Entry = ["web", "8080"] }}"#),
+            "test_validation.mon",
+        );
+        assert_eq!(err.error_code(), "validation::type_mismatch");
+
+        let err = resolve_err(
+            "{ A: #alias B, B: #alias A, value :: A = 1 }",
+            "test_validation.mon",
+        );
+        let ResolverError::CyclicAlias { name, cycle, .. } = err else {
+            panic!("Expected CyclicAlias, got {err:?}");
+        };
+        assert_eq!((name.as_str(), cycle.as_str()), ("A", "A -> B -> A"));
+    }
+
+    #[test]
+    fn test_optional_struct_fields() {
+        let schema = "User: #struct { name(String), nick(String)? }";
+        let doc = resolve_ok(
+            &format!(
+                r#"{{ {schema}, a :: User = {{ name: "a" }}, b :: User = {{ name: "b", nick: "bee" }} }}"#
+            ),
+            "test_validation.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(json["a"], serde_json::json!({ "name": "a" }));
+        assert_eq!(json["b"], serde_json::json!({ "name": "b", "nick": "bee" }));
+
+        // An optional field that is given must still match its type
+        let err = resolve_err(
+            &format!(r#"{{ {schema}, c :: User = {{ name: "c", nick: 1 }} }}"#),
+            "test_validation.mon",
+        );
+        assert_eq!(err.error_code(), "validation::type_mismatch");
+    }
+
+    #[test]
+    fn test_enum_discriminants() {
+        let schema = r#"Code: #enum { Ok = 200, Moved = "301", Gone = -1, Unknown }"#;
+        let doc = resolve_ok(
+            &format!(
+                "{{ {schema}, ok: $Code.Ok, list :: [Code...] = [Moved, Gone], unknown: $Code.Unknown }}"
+            ),
+            "test_enums.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(json["ok"], serde_json::json!(200.0));
+        assert_eq!(json["list"], serde_json::json!(["301", -1.0]));
+        // A variant without a discriminant stays an enum value
+        let MonValueKind::Object(members) = &doc.root.kind else {
+            panic!("Expected an object root");
+        };
+        assert!(members.iter().any(|m| matches!(
+            m,
+            Member::Pair(pair) if pair.key == "unknown"
+                && matches!(&pair.value.kind, MonValueKind::EnumValue { variant_name, .. } if variant_name == "Unknown")
+        )));
+
+        let source = format!("{{ {schema}, bad: $Code.Teapot }}");
+        let err = resolve_err(&source, "test_enums.mon");
+        let ResolverError::Validation(ValidationError::UndefinedEnumVariant {
+            variant_name,
+            span,
+            ..
+        }) = err
+        else {
+            panic!("Expected UndefinedEnumVariant, got {err:?}");
+        };
+        assert_eq!(variant_name, "Teapot");
+        assert_eq!(span.offset(), source.find("$Code.Teapot").unwrap());
+    }
+
+    #[test]
+    fn test_struct_defaults_are_fully_resolved() {
+        let dir = TempDir::new().unwrap();
+        create_test_file(
+            dir.path(),
+            "schema.mon",
+            r#"{
+                Inner: #struct { x(Number), y(Number) = 2 },
+                Conf: #struct {
+                    config(Object) = { ...*base, extra: 1 },
+                    inner(Inner) = { ...*inner_base },
+                },
+            }"#,
+        );
+        // The anchors the defaults spread are defined in the document that uses the struct
+        let main_path = create_test_file(
+            dir.path(),
+            "main.mon",
+            r#"import { Conf } from "./schema.mon"
+            {
+                &base: { a: 1, nested: { b: [1, 2] } },
+                &inner_base: { x: 5 },
+                c :: Conf = {},
+            }"#,
+        );
+        let source = fs::read_to_string(&main_path).unwrap();
+        let doc = Resolver::new()
+            .resolve_str(&source, &main_path.to_string_lossy())
+            .unwrap();
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["c"],
+            serde_json::json!({
+                "config": { "a": 1.0, "nested": { "b": [1.0, 2.0] }, "extra": 1.0 },
+                "inner": { "x": 5.0, "y": 2.0 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_array_defaults_are_validated() {
+        let schema = r#"Inner: #struct { x(Number), y(Number) = 2 },
+            Conf: #struct { items([Inner...]) = [{ x: 1 }, { x: 3, y: 4 }] }"#;
+        let doc = resolve_ok(
+            &format!("{{ {schema}, c :: Conf = {{}} }}"),
+            "test_defaults.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["c"]["items"],
+            serde_json::json!([{ "x": 1.0, "y": 2.0 }, { "x": 3.0, "y": 4.0 }])
+        );
+
+        // A default of the wrong type is an error where it is used
+        let err = resolve_err(
+            r#"{ Conf: #struct { port(Number) = "80" }, c :: Conf = {} }"#,
+            "test_defaults.mon",
+        );
+        assert_eq!(err.error_code(), "validation::type_mismatch");
+    }
+
+    #[test]
+    fn test_struct_one_of_validation() {
+        let schema = "Auth: #struct { token(String), password(String), user(String) } one_of(token, password)";
+        let doc = resolve_ok(
+            &format!(r#"{{ {schema}, auth :: Auth = {{ token: "t", user: "u" }} }}"#),
+            "test_validation.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["auth"],
+            serde_json::json!({ "token": "t", "user": "u" })
+        );
+
+        for (value, expected_given) in [
+            (r#"{ user: "u" }"#, vec![]),
+            (
+                r#"{ token: "t", password: "p", user: "u" }"#,
+                vec!["token", "password"],
+            ),
+        ] {
+            let err = resolve_err(
+                &format!("{{ {schema}, auth :: Auth = {value} }}"),
+                "test_validation.mon",
+            );
+            match err {
+                crate::error::ResolverError::Validation(
+                    crate::error::ValidationError::MutualExclusion { fields, given, .. },
+                ) => {
+                    assert_eq!(fields, vec!["token", "password"]);
+                    assert_eq!(given, expected_given);
+                }
+                _ => panic!("Expected MutualExclusion error, but got {err:?}"),
+            }
+        }
+
+        // Fields outside the group are still required
+        let err = resolve_err(
+            &format!(r#"{{ {schema}, auth :: Auth = {{ token: "t" }} }}"#),
+            "test_validation.mon",
+        );
+        assert!(matches!(
+            err,
+            crate::error::ResolverError::Validation(
+                crate::error::ValidationError::MissingField { .. }
+            )
+        ));
+
+        // A default is not added next to another field of its group
+        let schema = r#"Auth: #struct { token(String), password(String) = "changeme" } one_of(token, password)"#;
+        let doc = resolve_ok(
+            &format!(r#"{{ {schema}, auth :: Auth = {{ token: "t" }} }}"#),
+            "test_validation.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(json["auth"], serde_json::json!({ "token": "t" }));
+    }
+
+    #[test]
+    fn test_one_of_with_inherited_fields() {
+        let schema = r#"User: #struct { name(String), email(String) },
+            Admin: #struct extends User { level(Number) } one_of(email, level)"#;
+        let doc = resolve_ok(
+            &format!(r#"{{ {schema}, admin :: Admin = {{ name: "a", level: 1 }} }}"#),
+            "test_validation.mon",
+        );
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["admin"],
+            serde_json::json!({ "name": "a", "level": 1.0 })
+        );
+
+        let err = resolve_err(
+            &format!(r#"{{ {schema}, admin :: Admin = {{ name: "a", email: "e", level: 1 }} }}"#),
+            "test_validation.mon",
+        );
+        assert_eq!(err.error_code(), "validation::mutual_exclusion");
+
+        // A field neither struct has is still rejected
+        let err = resolve_err(
+            r#"{ User: #struct { name(String) }, Admin: #struct extends User {} one_of(name, role) }"#,
+            "test_validation.mon",
+        );
+        match err {
+            ResolverError::UnknownOneOfField {
+                struct_name, field, ..
+            } => {
+                assert_eq!(struct_name, "Admin");
+                assert_eq!(field, "role");
+            }
+            _ => panic!("Expected UnknownOneOfField error, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_field_keys() {
+        for (source, struct_name, key) in [
+            (
+                r#"{ User: #struct { id(Number) as "key", name(String) as "key" } }"#,
+                "User",
+                "key",
+            ),
+            (
+                r#"{ User: #struct { user_name(String) as "name", name(String) } }"#,
+                "User",
+                "name",
+            ),
+            (
+                r#"{ User: #struct { user_name(String) as "name" },
+                    Admin: #struct extends User { name(String) } }"#,
+                "Admin",
+                "name",
+            ),
+        ] {
+            match resolve_err(source, "test_validation.mon") {
+                ResolverError::DuplicateFieldKey {
+                    struct_name: name,
+                    key: duplicate,
+                    ..
+                } => {
+                    assert_eq!(name, struct_name);
+                    assert_eq!(duplicate, key);
+                }
+                err => panic!("Expected DuplicateFieldKey error, but got {err:?}"),
+            }
         }
+
+        // A field overriding an inherited one is serialized under its own key
+        resolve_ok(
+            r#"{ User: #struct { name(String) as "n" }, Admin: #struct extends User { name(String) } }"#,
+            "test_validation.mon",
+        );
     }
 
     #[test]
-    fn test_struct_validation_unexpected_field() {
+    fn test_open_struct_allows_unexpected_fields() {
         let source = r###"
         {
-            User: #struct { id(Number), name(String) },
-            invalid_user :: User = { id: 5, name: "David", age: 30 },
+            User: #struct { id(Number), name(String) = "anonymous", ... },
+            user :: User = { id: 5, age: 30 },
+        }
+    "###;
+        let doc = resolve_ok(source, "test_validation.mon");
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["user"],
+            serde_json::json!({ "id": 5.0, "name": "anonymous", "age": 30.0 })
+        );
+
+        // Declared fields are still required and validated.
+        let source = r###"
+        {
+            User: #struct { id(Number), ... },
+            user :: User = { id: "five", age: 30 },
         }
     "###;
         let err = resolve_err(source, "test_validation.mon");
-        match err {
+        assert!(matches!(
+            err,
             crate::error::ResolverError::Validation(
-                crate::error::ValidationError::UnexpectedField { field_name, .. },
-            ) => {
-                assert_eq!(field_name, "age");
-            }
-            _ => panic!("Expected UnexpectedField error, but got {err:?}"),
-        }
+                crate::error::ValidationError::TypeMismatch { .. }
+            )
+        ));
     }
 
     #[test]
@@ -1574,12 +3972,14 @@ mod tests {
             crate::error::ResolverError::Validation(
                 crate::error::ValidationError::TypeMismatch {
                     field_name,
+                    path,
                     expected_type,
                     found_type,
                     ..
                 },
             ) => {
                 assert_eq!(field_name, "roles");
+                assert_eq!(path, "invalid_user.roles[1]");
                 assert_eq!(expected_type, "String");
                 assert!(found_type.contains("Number"));
             }
@@ -1693,6 +4093,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_collection_validation_leading_and_trailing_fixed_types() {
+        let source = r###"
+        {
+            Row: #struct { cells([String, Number, Boolean, String..., Number]) },
+            ok1 :: Row = { cells: ["a", 1, true, 2] },
+            ok2 :: Row = { cells: ["a", 1, true, "x", "y", 2] },
+        }
+    "###;
+        resolve_ok(source, "test_collection.mon");
+
+        let source = r###"
+        {
+            Row: #struct { cells([String, Number, Boolean, String..., Number]) },
+            bad :: Row = { cells: ["a", 1, true, "x", 2, 3] },
+        }
+    "###;
+        match resolve_err(source, "test_collection.mon") {
+            crate::error::ResolverError::Validation(
+                crate::error::ValidationError::TypeMismatch { path, .. },
+            ) => assert_eq!(path, "bad.cells[4]"),
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
+        }
+
+        let source = r###"
+        {
+            Row: #struct { cells([String, Number, Boolean, String...]) },
+            short :: Row = { cells: ["a", 1] },
+        }
+    "###;
+        match resolve_err(source, "test_collection.mon") {
+            crate::error::ResolverError::Validation(
+                crate::error::ValidationError::TypeMismatch {
+                    expected_type,
+                    found_type,
+                    ..
+                },
+            ) => {
+                assert_eq!(expected_type, "array with at least 3 elements");
+                assert_eq!(found_type, "array with 2 elements");
+            }
+            err => panic!("Expected TypeMismatch error, but got {err:?}"),
+        }
+    }
+
     #[test]
     fn test_nested_struct_validation_ok() {
         let source = r###"
@@ -1749,12 +4194,14 @@ mod tests {
             crate::error::ResolverError::Validation(
                 crate::error::ValidationError::TypeMismatch {
                     field_name,
+                    path,
                     expected_type,
                     found_type,
                     ..
                 },
             ) => {
                 assert_eq!(field_name, "username");
+                assert_eq!(path, "user2.profile.username");
                 assert_eq!(expected_type, "String");
                 assert!(found_type.contains("Number"));
             }
@@ -1823,6 +4270,174 @@ mod tests {
         let resolved = resolver.resolve_import_path("../shared/types.mon", current_dir);
         assert_eq!(resolved, PathBuf::from("/project/src/../shared/types.mon"));
     }
+
+    #[test]
+    fn test_resolve_str() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(temp_dir.path(), "shared.mon", "{ &port: 8080 }");
+        let main_path = temp_dir.path().join("main.mon");
+        let source = r#"import { &port } from "./shared.mon"
+            { server: { port: *port } }"#;
+
+        let mut resolver = Resolver::new();
+        let document = resolver
+            .resolve_str(source, &main_path.to_string_lossy())
+            .unwrap();
+        let expected = Parser::new("{ server: { port: 8080 } }")
+            .unwrap()
+            .parse_document()
+            .unwrap();
+        assert!(document.root.structurally_eq(&expected.root));
+        assert_eq!(
+            resolver.dependencies,
+            [(main_path, temp_dir.path().join("shared.mon"))]
+        );
+
+        match Resolver::new().resolve_str("{ a: }", "broken.mon") {
+            Err(ResolverError::WrappedParserError(_)) => {}
+            other => panic!("Expected a wrapped parser error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_directory_import() {
+        let temp_dir = TempDir::new().unwrap();
+        let schemas = temp_dir.path().join("schemas");
+        fs::create_dir_all(schemas.join("nested")).unwrap();
+        create_test_file(
+            &schemas,
+            "a_user.mon",
+            "{ User: #struct { name(String) }, Role: #enum { Admin, Guest } }",
+        );
+        create_test_file(&schemas, "b_user.mon", "{ User: #struct { id(Number) } }");
+        create_test_file(&schemas, "notes.txt", "not MON");
+        create_test_file(
+            &schemas.join("nested"),
+            "skipped.mon",
+            "{ Skipped: #enum { A } }",
+        );
+        let main_source = r#"import * as schemas from "./schemas/"
+            { u :: schemas.User = { id: 1 }, r :: schemas.Role = Admin }"#;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_source);
+
+        let mut parser =
+            Parser::new_with_name(main_source, main_path.to_string_lossy().to_string()).unwrap();
+        let document = parser.parse_document().unwrap();
+        let mut resolver = Resolver::new();
+        resolver
+            .resolve(document, main_source, main_path.clone(), None)
+            .unwrap();
+
+        // Each `.mon` file directly in the directory is a dependency
+        assert_eq!(
+            resolver.dependencies,
+            [
+                (main_path.clone(), schemas.join("a_user.mon")),
+                (main_path.clone(), schemas.join("b_user.mon")),
+            ]
+        );
+        // The later file's `User` wins, with a warning pointing at it
+        match resolver.warnings.as_slice() {
+            [MonWarning::TypeShadowed {
+                name,
+                original_file,
+                ..
+            }] => {
+                assert_eq!(name, "User");
+                assert!(original_file.ends_with("a_user.mon"));
+            }
+            warnings => panic!("Expected one TypeShadowed warning, got {warnings:?}"),
+        }
+
+        // Nested directories are not searched, and an empty directory has no types
+        fs::create_dir(temp_dir.path().join("empty.mon")).unwrap();
+        let source = r#"import * as schemas from "./schemas"
+            import * as empty from "./empty.mon"
+            { s :: schemas.Skipped = A }"#;
+        let path = create_test_file(temp_dir.path(), "other.mon", source);
+        let document = Parser::new_with_name(source, path.to_string_lossy().to_string())
+            .unwrap()
+            .parse_document()
+            .unwrap();
+        match Resolver::new().resolve(document, source, path, None) {
+            Err(ResolverError::Validation(ValidationError::UndefinedType {
+                type_name, ..
+            })) => {
+                assert_eq!(type_name, "schemas.Skipped");
+            }
+            other => panic!("Expected UndefinedType error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_import_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let app_dir = project.join("apps/web");
+        let shared = project.join("shared");
+        let vendor = project.join("vendor");
+        for dir in [&app_dir, &shared.join("schemas"), &vendor.join("schemas")] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        create_test_file(
+            &shared.join("schemas"),
+            "user.mon",
+            "{ User: #struct { name(String) } }",
+        );
+        create_test_file(
+            &vendor.join("schemas"),
+            "user.mon",
+            "{ User: #struct { id(Number) } }",
+        );
+        create_test_file(&vendor.join("schemas"), "extra.mon", "{ &extra: 1 }");
+        let main_source = r#"import { User } from "schemas/user.mon"
+            import { &extra } from "schemas/extra.mon"
+            { u :: User = { name: "ann" }, e: *extra }"#;
+        let main_path = create_test_file(&app_dir, "main.mon", main_source);
+
+        let resolve = |resolver: &mut Resolver| {
+            let mut parser =
+                Parser::new_with_name(main_source, main_path.to_string_lossy().to_string())
+                    .unwrap();
+            let document = parser.parse_document().unwrap();
+            resolver.resolve(document, main_source, main_path.clone(), None)
+        };
+
+        // Roots are tried in order, after the importing file's directory
+        let mut resolver = Resolver::new();
+        resolver.set_options(
+            ResolveOptions::default()
+                .with_import_root(&shared)
+                .with_import_root(&vendor),
+        );
+        resolve(&mut resolver).unwrap();
+        assert!(resolver
+            .dependencies
+            .contains(&(main_path.clone(), shared.join("schemas/user.mon"))));
+        assert!(resolver
+            .dependencies
+            .contains(&(main_path.clone(), vendor.join("schemas/extra.mon"))));
+
+        // A module next to the importing file wins over the roots
+        fs::create_dir_all(app_dir.join("schemas")).unwrap();
+        create_test_file(&app_dir.join("schemas"), "extra.mon", "{ &extra: 2 }");
+        let mut resolver = Resolver::new();
+        resolver.set_options(ResolveOptions::default().with_import_root(&shared));
+        resolve(&mut resolver).unwrap();
+        assert!(resolver
+            .dependencies
+            .contains(&(main_path.clone(), app_dir.join("schemas/extra.mon"))));
+
+        // Without roots, the error names the path next to the importing file
+        fs::remove_file(app_dir.join("schemas/extra.mon")).unwrap();
+        match resolve(&mut Resolver::new()) {
+            Err(ResolverError::ModuleNotFound { path, .. }) => {
+                assert_eq!(path, "schemas/user.mon");
+            }
+            other => panic!("Expected ModuleNotFound error, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_absolute_path_preserved() {
         let resolver = Resolver::new();
@@ -1935,6 +4550,40 @@ mod tests {
         let result = resolver.resolve(doc, main_content, main_path, None);
         assert!(matches!(result, Err(ResolverError::ModuleNotFound { .. })))
     }
+
+    #[test]
+    fn test_unreadable_module_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("binary.mon"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let resolve_import = |path: &str| {
+            let main_content = format!("import * as m from \"{path}\"\n{{}}");
+            let main_path = create_test_file(temp_dir.path(), "main.mon", &main_content);
+            let mut parser = crate::parser::Parser::new_with_name(
+                &main_content,
+                main_path.to_string_lossy().to_string(),
+            )
+            .unwrap();
+            let doc = parser.parse_document().unwrap();
+            Resolver::new()
+                .resolve(doc, &main_content, main_path, None)
+                .err()
+                .unwrap()
+        };
+
+        assert!(matches!(
+            resolve_import("./missing.mon"),
+            ResolverError::ModuleNotFound { .. }
+        ));
+        match resolve_import("./binary.mon") {
+            ResolverError::ModuleNotUtf8 { path, span, .. } => {
+                assert_eq!(path, "./binary.mon");
+                assert_eq!(span, (0, 33).into());
+            }
+            err => panic!("Expected ModuleNotUtf8 error, but got {err:?}"),
+        }
+    }
+
     #[test]
     fn test_default_builtin_path() {
         // Test that default_builtin_path doesn't panic
@@ -1964,6 +4613,176 @@ mod tests {
 
         assert_eq!(path, PathBuf::from("/custom/builtin/path"));
     }
+
+    #[test]
+    fn test_namespaced_enum_value_validation() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(
+            temp_dir.path(),
+            "schemas.mon",
+            r#"{ Status: #enum { Active, Inactive } }"#,
+        );
+        let main_content = r###"
+            import * as schemas from "./schemas.mon"
+
+            {
+                ok :: schemas.Status = $schemas.Status.Active,
+                bad :: schemas.Status = $schemas.Status.Deleted,
+            }
+        "###;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_content);
+        let mut parser = crate::parser::Parser::new_with_name(
+            main_content,
+            main_path.to_string_lossy().to_string(),
+        )
+        .unwrap();
+        let doc = parser.parse_document().unwrap();
+        let mut resolver = Resolver::new();
+        let result = resolver.resolve(doc, main_content, main_path, None);
+        match result {
+            Err(ResolverError::Validation(ValidationError::UndefinedEnumVariant {
+                variant_name,
+                enum_name,
+                ..
+            })) => {
+                assert_eq!(variant_name, "Deleted");
+                assert_eq!(enum_name, "schemas.Status");
+            }
+            other => panic!("Expected UndefinedEnumVariant, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_bare_word_enum_values() {
+        let source = r#"{
+            Status: #enum { Active, Inactive },
+            Job: #struct { status(Status) = Inactive },
+            status :: Status = Active,
+            history :: [Status...] = [Active, Inactive],
+            job :: Job = {},
+        }"#;
+        let doc = resolve_ok(source, "test.mon");
+        let members = doc.root.kind.as_object().unwrap();
+        let value_of = |key: &str| {
+            members
+                .iter()
+                .find_map(|member| match member {
+                    Member::Pair(pair) if pair.key == key => Some(&pair.value.kind),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let variant = |name: &str| MonValueKind::EnumValue {
+            enum_name: "Status".to_string(),
+            variant_name: name.to_string(),
+        };
+
+        assert_eq!(value_of("status"), &variant("Active"));
+        let history = value_of("history").as_array().unwrap();
+        assert_eq!(history[1].kind, variant("Inactive"));
+        let Member::Pair(job_status) = &value_of("job").as_object().unwrap()[0] else {
+            panic!("Expected the default status to be inserted");
+        };
+        assert_eq!(job_status.value.kind, variant("Inactive"));
+
+        // Outside of an enum validation, bare words are errors.
+        let err = resolve_err("{ status: Active }", "test.mon");
+        match err {
+            ResolverError::UnexpectedBareWord { word, span, .. } => {
+                assert_eq!(word, "Active");
+                assert_eq!(span, (10, 6).into());
+            }
+            _ => panic!("Expected UnexpectedBareWord error, but got {err:?}"),
+        }
+        let err = resolve_err("{ name :: String = Active }", "test.mon");
+        assert!(matches!(
+            err,
+            ResolverError::Validation(ValidationError::TypeMismatch { .. })
+        ));
+        let err = resolve_err(
+            "{ Status: #enum { Active }, status :: Status = Deleted }",
+            "test.mon",
+        );
+        assert!(matches!(
+            err,
+            ResolverError::Validation(ValidationError::UndefinedEnumVariant { .. })
+        ));
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("String", "String"), 0);
+        assert_eq!(levenshtein("Strign", "String"), 2);
+        assert_eq!(levenshtein("Numbr", "Number"), 1);
+        assert_eq!(levenshtein("", "Any"), 3);
+    }
+
+    fn undefined_type_help(source: &str, strict: bool) -> String {
+        let mut parser = Parser::new_with_name(source, "test.mon".to_string()).unwrap();
+        let document = parser.parse_document().unwrap();
+        let mut resolver = Resolver::new();
+        resolver.set_strict(strict);
+        match resolver.resolve(document, source, PathBuf::from("test.mon"), None) {
+            Err(ResolverError::Validation(ValidationError::UndefinedType { help, .. })) => help,
+            other => panic!("Expected UndefinedType error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_treat_null_as_absent() {
+        let source = r#"{
+            &partial: { host: null, port: 8080, tls: null },
+            a: { host: "example.com", ...*partial },
+            b: { ...*partial, port: null, tls: true },
+        }"#;
+        let resolve = |null_as_absent: bool| {
+            let mut parser = Parser::new_with_name(source, "test.mon".to_string()).unwrap();
+            let document = parser.parse_document().unwrap();
+            let mut resolver = Resolver::new();
+            resolver.set_treat_null_as_absent(null_as_absent);
+            assert_eq!(resolver.treats_null_as_absent(), null_as_absent);
+            let doc = resolver
+                .resolve(document, source, PathBuf::from("test.mon"), None)
+                .unwrap();
+            crate::serialization::to_value(&doc.root)
+        };
+
+        // By default the later value wins, even if it is `null`
+        let value = serde_json::to_value(resolve(false)).unwrap();
+        assert_eq!(value["a"]["host"], serde_json::Value::Null);
+        assert_eq!(value["b"]["port"], serde_json::Value::Null);
+
+        let value = serde_json::to_value(resolve(true)).unwrap();
+        assert_eq!(value["a"]["host"], "example.com");
+        assert_eq!(value["a"]["port"], 8080.0);
+        assert_eq!(value["a"]["tls"], serde_json::Value::Null);
+        assert_eq!(value["b"]["host"], serde_json::Value::Null);
+        assert_eq!(value["b"]["port"], 8080.0);
+        assert_eq!(value["b"]["tls"], true);
+    }
+
+    #[test]
+    fn test_strict_mode_suggests_builtin_type() {
+        let help = undefined_type_help(r#"{ name :: Strign = "x" }"#, true);
+        assert!(help.contains("Did you mean 'String'?"), "{help}");
+    }
+
+    #[test]
+    fn test_strict_mode_suggests_defined_type() {
+        let source = r#"{
+            Profile: #struct { name(String) },
+            p :: Profil = { name: "x" },
+        }"#;
+        let help = undefined_type_help(source, true);
+        assert!(help.contains("Did you mean 'Profile'?"), "{help}");
+    }
+
+    #[test]
+    fn test_lenient_mode_has_no_suggestion() {
+        let help = undefined_type_help(r#"{ name :: Strign = "x" }"#, false);
+        assert!(!help.contains("Did you mean"), "{help}");
+    }
+
     #[test]
     fn test_circular_dependency_with_mon_imports() {
         let temp_dir = TempDir::new().unwrap();
@@ -1995,4 +4814,156 @@ mod tests {
             Err(ResolverError::CircularDependency { .. })
         ));
     }
+
+    #[test]
+    fn test_self_import() {
+        let source = fs::read_to_string("tests/bad/self_import.mon").unwrap();
+        let err = resolve_err(&source, "tests/bad/self_import.mon");
+        match err {
+            ResolverError::SelfImport { path, span, .. } => {
+                assert_eq!(path, "./self_import.mon");
+                assert_eq!(span, (0, 39).into());
+            }
+            _ => panic!("Expected SelfImport error, but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_commented_imports_resolve() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(
+            temp_dir.path(),
+            "types.mon",
+            r#"{
+    // The port type
+    Port: #struct { number(Number) }, // trailing
+}"#,
+        );
+        let main_content = r#"
+            import { Port } // the port type
+            from // relative to this file
+            "./types.mon" // core types
+
+            { port :: Port = { number: 8080 } }
+        "#;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_content);
+        let doc = resolve_ok(main_content, &main_path.to_string_lossy());
+
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(json["port"], serde_json::json!({ "number": 8080.0 }));
+    }
+
+    #[test]
+    fn test_default_import() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(
+            temp_dir.path(),
+            "defaults.mon",
+            r#"{ Server: #struct { host(String), port(Number) }, host: "localhost", port: 80 }"#,
+        );
+        let main_content = r#"
+            import defaults from "./defaults.mon"
+            import { Server } from "./defaults.mon"
+
+            {
+                server :: Server = { ...*defaults, port: 8080 },
+                copy: *defaults,
+            }
+        "#;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_content);
+        let doc = resolve_ok(main_content, &main_path.to_string_lossy());
+
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["server"],
+            serde_json::json!({ "host": "localhost", "port": 8080.0 })
+        );
+        assert_eq!(
+            json["copy"],
+            serde_json::json!({ "host": "localhost", "port": 80.0 })
+        );
+
+        // Only the data is bound, not the type definitions.
+        let MonValueKind::Object(members) = &doc.root.kind else {
+            panic!("Expected an object");
+        };
+        let copy = members
+            .iter()
+            .find_map(|member| match member {
+                Member::Pair(pair) if pair.key == "copy" => Some(&pair.value),
+                _ => None,
+            })
+            .unwrap();
+        assert!(
+            matches!(&copy.kind, MonValueKind::Object(m) if m.iter().all(|m| matches!(m, Member::Pair(_))))
+        );
+    }
+
+    #[test]
+    fn test_duration_and_byte_size_validation() {
+        let source = r###"
+        {
+            Limits: #struct { timeout(Duration), max_size(ByteSize) = 1KiB },
+            limits :: Limits = { timeout: 250ms },
+            raw: 10MB,
+        }
+    "###;
+        let doc = resolve_ok(source, "test.mon");
+        let json = serde_json::to_value(crate::serialization::to_value(&doc.root)).unwrap();
+        assert_eq!(
+            json["limits"],
+            serde_json::json!({ "timeout": 0.25, "max_size": 1024.0 })
+        );
+        // Without a validation, a quantity keeps its literal form.
+        assert_eq!(json["raw"], "10MB");
+
+        for (value, type_name) in [
+            ("10MB", "Duration"),
+            ("30s", "ByteSize"),
+            ("30", "Duration"),
+        ] {
+            let source = format!("{{ x :: {type_name} = {value} }}");
+            match resolve_err(&source, "test.mon") {
+                ResolverError::Validation(ValidationError::TypeMismatch {
+                    expected_type, ..
+                }) => assert_eq!(expected_type, type_name),
+                err => panic!("Expected TypeMismatch error, but got {err:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_named_import_aliases() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(
+            temp_dir.path(),
+            "accounts.mon",
+            r#"{ User: #struct { id(Number) }, &defaults: { id: 0 } }"#,
+        );
+        create_test_file(
+            temp_dir.path(),
+            "people.mon",
+            r#"{ User: #struct { name(String) } }"#,
+        );
+        let main_content = r#"
+            import { User as Account, &defaults as account_defaults } from "./accounts.mon"
+            import { User } from "./people.mon"
+
+            {
+                account :: Account = { ...*account_defaults },
+                person :: User = { name: "Alice" },
+            }
+        "#;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_content);
+        resolve_ok(main_content, &main_path.to_string_lossy());
+
+        // The unrenamed import keeps its name regardless of the import order.
+        let main_content = r#"
+            import { User } from "./people.mon"
+            import { User as Account } from "./accounts.mon"
+            { account :: Account = { id: 1 }, person :: User = { name: "Bob" } }
+        "#;
+        let main_path = create_test_file(temp_dir.path(), "main.mon", main_content);
+        resolve_ok(main_content, &main_path.to_string_lossy());
+    }
 }
@@ -9,7 +9,7 @@
 //! The process is straightforward:
 //!
 //! 1.  A fully resolved and validated [`MonValue`](crate::ast::MonValue) from the AST is passed
-//!     to the internal `to_value` function.
+//!     to the [`to_value`] function.
 //! 2.  The function recursively traverses the `MonValue`, converting it into a tree of
 //!     [`Value`] enums.
 //! 3.  During this conversion, language-specific AST nodes that are not part of the data model—such
@@ -20,14 +20,16 @@
 //!
 //! ## Use Cases
 //!
-//! This module is used internally by [`AnalysisResult`](crate::api::AnalysisResult) to provide
-//! the `to_json()` and `to_yaml()` methods. Direct interaction with this module is typically not
-//! necessary for end-users, as the public API in the [`api`](crate::api) module provides a more
-//! convenient interface.
+//! This module is used internally by `AnalysisResult` to provide the `to_json()` and
+//! `to_yaml()` methods. Direct interaction with this module is typically not necessary for
+//! end-users, as the public API in the `api` module provides a more convenient interface.
+//! Without the `resolver` feature, [`to_value`] is the way to serialize a parsed document.
 //!
 //! ```rust
+//! # #[cfg(feature = "resolver")]
 //! use mon_core::api::analyze;
 //!
+//! # #[cfg(feature = "resolver")]
 //! # fn main() -> Result<(), mon_core::error::MonError> {
 //! let source = "{ b: 2, a: 1 }";
 //!
@@ -39,11 +41,19 @@
 //! assert_eq!(json, "{\n  \"a\": 1.0,\n  \"b\": 2.0\n}");
 //! # Ok(())
 //! # }
+//! # #[cfg(not(feature = "resolver"))]
+//! # fn main() {}
 //! ```
 use crate::ast::{Member, MonValue, MonValueKind};
+use crate::error::ValueConversionError;
+use crate::utils::encode_base64;
 use serde::Serialize;
 use std::collections::BTreeMap;
 
+/// The largest integer up to which an `f64` holds every integer exactly, 2^53. Whole numbers
+/// up to this size are written as integers.
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum Value {
@@ -55,11 +65,322 @@ pub enum Value {
     Object(BTreeMap<String, Value>),
 }
 
-pub(crate) fn to_value(mon_value: &MonValue) -> Value {
+impl Value {
+    /// Deep-merges `overlay` on top of `base`, e.g. to apply environment overrides to a base config.
+    ///
+    /// The rules mirror object spreads, where local keys win over spread keys:
+    ///
+    /// - If both values are objects, they are merged key by key. Keys only in `base` are kept,
+    ///   keys only in `overlay` are added, and keys in both are merged recursively.
+    /// - In every other case, including arrays, `overlay` replaces `base`.
+    ///
+    /// Unlike a spread, the merge is deep: nested objects are merged instead of replaced.
+    #[must_use]
+    pub fn merge(base: Value, overlay: Value) -> Value {
+        match (base, overlay) {
+            (Value::Object(mut base_map), Value::Object(overlay_map)) => {
+                for (key, overlay_value) in overlay_map {
+                    let merged = match base_map.remove(&key) {
+                        Some(base_value) => Value::merge(base_value, overlay_value),
+                        None => overlay_value,
+                    };
+                    base_map.insert(key, merged);
+                }
+                Value::Object(base_map)
+            }
+            (_, overlay) => overlay,
+        }
+    }
+
+    /// Returns a hash of the value that is stable across runs, platforms and crate versions.
+    ///
+    /// Objects are hashed in key order, so the hash only depends on the data: two documents
+    /// that differ only in formatting, comments or key order hash equal. `0.0` and `-0.0`
+    /// hash equal as well.
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        self.feed(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Writes the value as JSON in a fixed format, so that equal data always gives the same
+    /// bytes, e.g. to keep resolved configs in version control with clean diffs.
+    ///
+    /// Object keys are sorted and every array element and object member gets its own line,
+    /// indented by two spaces per level. Whole numbers up to 2^53 are written as integers,
+    /// `-0` as `0` and other numbers in their shortest form that reads back exactly, e.g.
+    /// `0.1` or `1e300`. Non-ASCII characters are written as they are. The text ends with a
+    /// newline.
+    #[must_use]
+    pub fn to_canonical_json(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String, depth: usize) {
+        let newline = |out: &mut String, depth: usize| {
+            out.push('\n');
+            out.extend(std::iter::repeat_n("  ", depth));
+        };
+        match self {
+            Value::String(s) => write_json_string(out, s),
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_EXACT_INTEGER => {
+                out.push_str(&(*n as i64).to_string());
+            }
+            Value::Number(n) if n.is_finite() => out.push_str(&format!("{n:?}")),
+            // JSON has no infinity or NaN; the resolver rejects them anyway
+            Value::Number(_) | Value::Null => out.push_str("null"),
+            Value::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Array(elements) if elements.is_empty() => out.push_str("[]"),
+            Value::Array(elements) => {
+                out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, depth + 1);
+                    element.write_canonical(out, depth + 1);
+                }
+                newline(out, depth);
+                out.push(']');
+            }
+            Value::Object(map) if map.is_empty() => out.push_str("{}"),
+            Value::Object(map) => {
+                out.push('{');
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, depth + 1);
+                    write_json_string(out, key);
+                    out.push_str(": ");
+                    value.write_canonical(out, depth + 1);
+                }
+                newline(out, depth);
+                out.push('}');
+            }
+        }
+    }
+
+    fn feed(&self, hasher: &mut Fnv1a) {
+        // Each value starts with a tag and containers with their length, so that
+        // e.g. `["a", "b"]` and `["ab"]` do not collide.
+        match self {
+            Value::String(s) => {
+                hasher.write(&[0]);
+                hasher.write_str(s);
+            }
+            Value::Number(n) => {
+                hasher.write(&[1]);
+                let n = if *n == 0.0 { 0.0 } else { *n };
+                hasher.write(&n.to_bits().to_le_bytes());
+            }
+            Value::Boolean(b) => hasher.write(&[2, u8::from(*b)]),
+            Value::Null => hasher.write(&[3]),
+            Value::Array(elements) => {
+                hasher.write(&[4]);
+                hasher.write(&(elements.len() as u64).to_le_bytes());
+                for element in elements {
+                    element.feed(hasher);
+                }
+            }
+            Value::Object(map) => {
+                hasher.write(&[5]);
+                hasher.write(&(map.len() as u64).to_le_bytes());
+                for (key, value) in map {
+                    hasher.write_str(key);
+                    value.feed(hasher);
+                }
+            }
+        }
+    }
+}
+
+/// Writes `s` as a quoted JSON string, escaping quotes, backslashes and control characters.
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// The 64-bit FNV-1a hash. Unlike `std`'s `DefaultHasher`, its output is specified and
+/// therefore safe to persist.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Serializes a [`Value`] like its `Serialize` impl, but whole numbers as integers, e.g.
+/// `port: 8080` instead of `port: 8080.0` in YAML.
+///
+/// Only numbers that an `f64` holds exactly, up to 2^53, are written as integers.
+#[cfg(feature = "resolver")]
+pub(crate) struct WholeNumbers<'a>(pub(crate) &'a Value);
+
+#[cfg(feature = "resolver")]
+impl Serialize for WholeNumbers<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_EXACT_INTEGER => {
+                serializer.serialize_i64(*n as i64)
+            }
+            Value::Array(elements) => serializer.collect_seq(elements.iter().map(WholeNumbers)),
+            Value::Object(map) => {
+                serializer.collect_map(map.iter().map(|(key, value)| (key, WholeNumbers(value))))
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Converts the value into a `serde_json` value without going through a JSON string.
+///
+/// Whole numbers up to 2^53 become JSON integers, like in [`Value::to_canonical_json`].
+/// Infinite numbers and NaN, which JSON cannot represent, become `null`.
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::String(s) => serde_json::Value::String(s),
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_EXACT_INTEGER => {
+                serde_json::Value::from(n as i64)
+            }
+            Value::Number(n) => serde_json::Number::from_f64(n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Value::Boolean(b) => serde_json::Value::Bool(b),
+            Value::Null => serde_json::Value::Null,
+            Value::Array(elements) => elements.into_iter().map(Self::from).collect(),
+            Value::Object(map) => map
+                .into_iter()
+                .map(|(key, value)| (key, Self::from(value)))
+                .collect(),
+        }
+    }
+}
+
+/// Converts a `serde_json` value into a [`Value`].
+///
+/// # Errors
+///
+/// Fails with [`ValueConversionError::InexactNumber`] for an integer that an `f64` cannot hold
+/// exactly, i.e. one that changes when converted to an `f64` and back.
+impl TryFrom<serde_json::Value> for Value {
+    type Error = ValueConversionError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Number(n) => {
+                // Compared as 128-bit integers, since casting back to 64 bits saturates and
+                // would turn e.g. `i64::MAX as f64`, which is 2^63, back into `i64::MAX`
+                let exact = match (n.as_i64(), n.as_u64()) {
+                    (Some(i), _) => i as f64 as i128 == i128::from(i),
+                    (None, Some(u)) => u as f64 as u128 == u128::from(u),
+                    (None, None) => true,
+                };
+                match n.as_f64() {
+                    Some(f) if exact => Value::Number(f),
+                    _ => {
+                        return Err(ValueConversionError::InexactNumber {
+                            number: n.to_string(),
+                        })
+                    }
+                }
+            }
+            serde_json::Value::Bool(b) => Value::Boolean(b),
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Array(elements) => Value::Array(
+                elements
+                    .into_iter()
+                    .map(Value::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| Ok((key, Value::try_from(value)?)))
+                    .collect::<Result<_, ValueConversionError>>()?,
+            ),
+        })
+    }
+}
+
+/// How [`to_value_with`] writes the binary data of a `Bytes` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BytesEncoding {
+    /// A standard base64 string, e.g. `"aGk="`.
+    #[default]
+    Base64,
+    /// An array with a number per byte, e.g. `[104, 105]`.
+    Array,
+}
+
+/// Converts a [`MonValue`] into a serializable [`Value`].
+///
+/// The value should already be resolved. Aliases, spreads and enum values that are still
+/// present are converted to [`Value::Null`]. Bytes are written as base64 strings.
+#[must_use]
+pub fn to_value(mon_value: &MonValue) -> Value {
+    to_value_with(mon_value, BytesEncoding::Base64)
+}
+
+/// Converts a [`MonValue`] into a serializable [`Value`] like [`to_value`], writing bytes
+/// as `bytes` says.
+#[must_use]
+pub fn to_value_with(mon_value: &MonValue, bytes: BytesEncoding) -> Value {
+    let to_value = |value| to_value_with(value, bytes);
     match &mon_value.kind {
         MonValueKind::String(s) => Value::String(s.clone()),
         MonValueKind::Number(n) => Value::Number(*n),
         MonValueKind::Boolean(b) => Value::Boolean(*b),
+        // Quantities that were not normalized by a `Duration` or `ByteSize` validation
+        // keep their literal form, e.g. `"30s"`.
+        MonValueKind::Quantity { value, unit } => Value::String(format!("{value}{unit}")),
+        MonValueKind::Bytes(data) => match bytes {
+            BytesEncoding::Base64 => Value::String(encode_base64(data)),
+            BytesEncoding::Array => Value::Array(
+                data.iter()
+                    .map(|byte| Value::Number(f64::from(*byte)))
+                    .collect(),
+            ),
+        },
         MonValueKind::Array(arr) => Value::Array(arr.iter().map(to_value).collect()),
         MonValueKind::Object(obj) => {
             let mut map = BTreeMap::new();
@@ -67,7 +388,7 @@ pub(crate) fn to_value(mon_value: &MonValue) -> Value {
                 if let Member::Pair(pair) = member {
                     // We only include pairs in the final JSON output.
                     // Type definitions, anchors, etc., are not part of the data.
-                    map.insert(pair.key.clone(), to_value(&pair.value));
+                    map.insert(pair.output_key().to_string(), to_value(&pair.value));
                 }
             }
             Value::Object(map)
@@ -77,6 +398,7 @@ pub(crate) fn to_value(mon_value: &MonValue) -> Value {
         MonValueKind::Null
         | MonValueKind::Alias(_)
         | MonValueKind::EnumValue { .. }
+        | MonValueKind::BareWord(_)
         | MonValueKind::ArraySpread(_) => Value::Null, // Or panic, depending on desired strictness.
     }
 }
@@ -149,8 +471,10 @@ mod tests {
     fn test_object_conversion() {
         let pair = Pair {
             key: "test".to_string(),
+            key_span: (0, 0).into(),
             value: make_value(MonValueKind::String("value".to_string())),
             validation: None,
+            serialized_key: None,
         };
         let obj = vec![Member::Pair(pair)];
         let mon_val = make_value(MonValueKind::Object(obj));
@@ -165,10 +489,15 @@ mod tests {
     fn test_object_excludes_non_pair_members() {
         let pair = Pair {
             key: "data".to_string(),
+            key_span: (0, 0).into(),
             value: make_value(MonValueKind::Number(123.0)),
             validation: None,
+            serialized_key: None,
         };
-        let obj = vec![Member::Pair(pair), Member::Spread("ignored".to_string())];
+        let obj = vec![
+            Member::Pair(pair),
+            Member::Spread("ignored".to_string(), (0, 0).into()),
+        ];
         let mon_val = make_value(MonValueKind::Object(obj));
         let result = to_value(&mon_val);
 
@@ -198,18 +527,66 @@ mod tests {
         assert_eq!(to_value(&mon_val), Value::Null);
     }
 
+    #[test]
+    fn test_merge() {
+        let object = |pairs: Vec<(&str, Value)>| {
+            Value::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+        };
+
+        let base = object(vec![
+            ("name", Value::String("app".to_string())),
+            (
+                "server",
+                object(vec![
+                    ("host", Value::String("localhost".to_string())),
+                    ("port", Value::Number(80.0)),
+                ]),
+            ),
+            ("tags", Value::Array(vec![Value::String("a".to_string())])),
+        ]);
+        let overlay = object(vec![
+            ("server", object(vec![("port", Value::Number(443.0))])),
+            ("tags", Value::Array(vec![])),
+            ("debug", Value::Boolean(true)),
+        ]);
+
+        assert_eq!(
+            Value::merge(base, overlay),
+            object(vec![
+                ("name", Value::String("app".to_string())),
+                (
+                    "server",
+                    object(vec![
+                        ("host", Value::String("localhost".to_string())),
+                        ("port", Value::Number(443.0)),
+                    ]),
+                ),
+                ("tags", Value::Array(vec![])),
+                ("debug", Value::Boolean(true)),
+            ])
+        );
+
+        // A scalar overlay replaces an object, and the other way around.
+        assert_eq!(Value::merge(object(vec![]), Value::Null), Value::Null);
+        assert_eq!(Value::merge(Value::Null, object(vec![])), object(vec![]));
+    }
+
     #[test]
     fn test_nested_object() {
         let inner_pair = Pair {
             key: "inner".to_string(),
+            key_span: (0, 0).into(),
             value: make_value(MonValueKind::Number(42.0)),
             validation: None,
+            serialized_key: None,
         };
         let inner_obj = vec![Member::Pair(inner_pair)];
         let outer_pair = Pair {
             key: "outer".to_string(),
+            key_span: (0, 0).into(),
             value: make_value(MonValueKind::Object(inner_obj)),
             validation: None,
+            serialized_key: None,
         };
         let outer_obj = vec![Member::Pair(outer_pair)];
         let mon_val = make_value(MonValueKind::Object(outer_obj));
@@ -223,4 +600,51 @@ mod tests {
 
         assert_eq!(result, Value::Object(outer_map));
     }
+
+    #[test]
+    fn test_serde_json_conversions() {
+        let mut map = BTreeMap::new();
+        map.insert("port".to_string(), Value::Number(8080.0));
+        map.insert("ratio".to_string(), Value::Number(0.5));
+        map.insert("name".to_string(), Value::String("api".to_string()));
+        map.insert(
+            "tags".to_string(),
+            Value::Array(vec![Value::Boolean(true), Value::Null]),
+        );
+        let value = Value::Object(map);
+
+        let json = serde_json::Value::from(value.clone());
+        assert_eq!(
+            json,
+            serde_json::json!({"port": 8080, "ratio": 0.5, "name": "api", "tags": [true, null]})
+        );
+        assert!(json["port"].is_i64());
+        assert_eq!(Value::try_from(json), Ok(value));
+
+        assert_eq!(
+            serde_json::Value::from(Value::Number(f64::NAN)),
+            serde_json::Value::Null
+        );
+        assert_eq!(
+            Value::try_from(serde_json::json!(9_007_199_254_740_992_i64)),
+            Ok(Value::Number(9_007_199_254_740_992.0))
+        );
+        assert_eq!(
+            Value::try_from(serde_json::json!([9_007_199_254_740_993_u64])),
+            Err(ValueConversionError::InexactNumber {
+                number: "9007199254740993".to_string()
+            })
+        );
+        // Integers beyond 2^53 that an `f64` holds exactly are accepted
+        assert_eq!(
+            Value::try_from(serde_json::json!(1_u64 << 60)),
+            Ok(Value::Number(1_152_921_504_606_846_976.0))
+        );
+        assert_eq!(
+            Value::try_from(serde_json::json!(i64::MIN)),
+            Ok(Value::Number(-9_223_372_036_854_775_808.0))
+        );
+        assert!(Value::try_from(serde_json::json!(i64::MAX)).is_err());
+        assert!(Value::try_from(serde_json::json!(u64::MAX)).is_err());
+    }
 }
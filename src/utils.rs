@@ -1,21 +1,231 @@
-#[cfg(feature = "lsp")]
+use miette::SourceSpan;
+
 /// Calculates the 1-based line and column number for a given byte position in the source text.
 /// This function is designed to be called only when an error occurs, as it iterates through
 /// the source text to determine the position.
-#[allow(dead_code)]
+///
+/// A `\r\n` line ending counts as a single line break, and a leading byte order mark
+/// does not count as a column.
 pub fn get_line_and_column(source: &str, position: usize) -> (usize, usize) {
     let mut line = 1;
     let mut column = 1;
-    for (i, c) in source.chars().enumerate() {
-        if i == position {
+    for (i, c) in source.char_indices() {
+        if i >= position {
             break;
         }
-        if c == '\n' {
-            line += 1;
-            column = 1;
-        } else {
-            column += 1;
+        match c {
+            '\n' => {
+                line += 1;
+                column = 1;
+            }
+            '\r' if source[i + 1..].starts_with('\n') => {}
+            '\u{FEFF}' if i == 0 => {}
+            _ => column += 1,
         }
     }
     (line, column)
 }
+
+/// Builds the [`SourceSpan`] covering the byte range `start..end`.
+///
+/// An inverted range, where `end` lies before `start`, yields an empty span at `end` instead
+/// of a length that underflows.
+#[must_use]
+pub fn source_span(start: usize, end: usize) -> SourceSpan {
+    SourceSpan::new(start.min(end).into(), end.saturating_sub(start))
+}
+
+/// Extracts the MON block fenced by `fence` from a host document, e.g. a ```` ```mon ````
+/// code block in Markdown or a `--- data` header.
+///
+/// The block starts after the first line that equals `fence` and ends before the next line
+/// that equals the fence without its trailing word: ```` ```mon ```` is closed by ```` ``` ````
+/// and `--- data` by `---`. Lines are compared without surrounding whitespace. A block that
+/// is not closed runs to the end of `text`.
+///
+/// Everything outside of the block is blanked out with spaces, keeping line breaks, so the
+/// result is as long as `text` and byte offsets into it, like error spans, are valid in `text`.
+///
+/// Returns `None` if no line equals `fence`.
+#[must_use]
+pub fn extract_mon(text: &str, fence: &str) -> Option<String> {
+    let fence = fence.trim();
+    let closing_fence = match fence
+        .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_')
+        .trim_end()
+    {
+        "" => fence,
+        closing_fence => closing_fence,
+    };
+
+    let mut offset = 0;
+    let mut block_start = None;
+    let mut block_end = text.len();
+    for line in text.split_inclusive('\n') {
+        let line_end = offset + line.len();
+        match block_start {
+            None if line.trim() == fence => block_start = Some(line_end),
+            Some(_) if line.trim() == closing_fence => {
+                block_end = offset;
+                break;
+            }
+            _ => {}
+        }
+        offset = line_end;
+    }
+    let block = block_start?..block_end;
+
+    let mut extracted = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if block.contains(&i) || matches!(c, '\n' | '\r') {
+            extracted.push(c);
+        } else {
+            extracted.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+    Some(extracted)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as standard, padded base64, e.g. `"aGk="` for `b"hi"`.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | u32::from(*byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(char::from(BASE64_ALPHABET[index as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes the text of a `Bytes` value: hex digits after a `0x` prefix, e.g. `"0xdeadbeef"`,
+/// or standard base64 otherwise, e.g. `"aGk="`. Base64 padding is optional.
+///
+/// Returns `None` if the text is not valid in its encoding.
+pub fn decode_bytes(text: &str) -> Option<Vec<u8>> {
+    match text.strip_prefix("0x") {
+        Some(hex) => decode_hex(hex),
+        None => decode_base64(text),
+    }
+}
+
+// `usize::is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digits = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(digits, 16).ok()
+        })
+        .collect()
+}
+
+#[allow(clippy::manual_is_multiple_of)]
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let data = text.trim_end_matches('=');
+    // At most two `=`, and only to complete the last group of four characters
+    let padding = text.len() - data.len();
+    if padding > 2 || (padding > 0 && text.len() % 4 != 0) {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        // A single character holds only six bits, less than a byte
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut group = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|a| a == c)?;
+            group |= (index as u32) << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            decoded.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_mon() {
+        let markdown = "# Conf\u{e9}\n\n```mon\n{ a: 1 }\n```\n\nmore\n";
+        let extracted = extract_mon(markdown, "```mon").unwrap();
+        assert_eq!(extracted.len(), markdown.len());
+        assert_eq!(extracted.trim(), "{ a: 1 }");
+        let offset = markdown.find("a: 1").unwrap();
+        assert_eq!(&extracted[offset..offset + 4], "a: 1");
+        assert_eq!(extracted.lines().count(), markdown.lines().count());
+
+        let frontmatter = "--- data\n{ b: 2 }\n---\nbody";
+        assert_eq!(
+            extract_mon(frontmatter, "--- data").unwrap().trim(),
+            "{ b: 2 }"
+        );
+
+        // An unclosed block runs to the end
+        assert_eq!(extract_mon("```mon\n{}", "```mon").unwrap().trim(), "{}");
+        assert_eq!(extract_mon("no fence here", "```mon"), None);
+    }
+
+    #[test]
+    fn test_source_span_clamps_inverted_ranges() {
+        let span = source_span(3, 8);
+        assert_eq!((span.offset(), span.len()), (3, 5));
+        let span = source_span(8, 3);
+        assert_eq!((span.offset(), span.len()), (3, 0));
+    }
+
+    #[test]
+    fn test_get_line_and_column_crlf_and_bom() {
+        let source = "\u{FEFF}{\r\n  a: 1\r\n}";
+        assert_eq!(
+            get_line_and_column(source, source.find('{').unwrap()),
+            (1, 1)
+        );
+        assert_eq!(
+            get_line_and_column(source, source.find('a').unwrap()),
+            (2, 3)
+        );
+        assert_eq!(
+            get_line_and_column(source, source.find('}').unwrap()),
+            (3, 1)
+        );
+    }
+
+    #[test]
+    fn test_bytes_encoding() {
+        for bytes in [&b""[..], b"h", b"hi", b"hi!", b"\x00\xff\x10\x80"] {
+            assert_eq!(decode_bytes(&encode_base64(bytes)).unwrap(), bytes);
+        }
+        assert_eq!(encode_base64(b"hi"), "aGk=");
+        assert_eq!(decode_bytes("aGk").unwrap(), b"hi");
+        assert_eq!(
+            decode_bytes("0xDEADbeef").unwrap(),
+            [0xde, 0xad, 0xbe, 0xef]
+        );
+
+        for invalid in [
+            "a", "aGkhY", "aGk==", "aG=k", "a-b_", "0xabc", "0xzz", "0x+1", "aGk====",
+        ] {
+            assert_eq!(decode_bytes(invalid), None, "{invalid}");
+        }
+    }
+}
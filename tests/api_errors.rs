@@ -102,3 +102,35 @@ fn test_api_error_display() {
         panic!("Should have errored");
     }
 }
+
+#[test]
+fn test_api_imported_module_parse_error() {
+    use miette::Diagnostic;
+    use mon_core::error::ResolverError;
+    use std::error::Error;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("broken.mon"), "{ a: }").unwrap();
+    let main_path = dir.path().join("main.mon");
+    let source = "import * as broken from \"./broken.mon\"\n{}";
+
+    let err = analyze(source, &main_path.to_string_lossy()).err().unwrap();
+    assert_eq!(err.code(), "resolver::imported_module_invalid");
+    // The error points at the import in the importing file...
+    let span = err.primary_span().unwrap();
+    assert_eq!(
+        &source[span.offset()..span.offset() + span.len()],
+        "import * as broken from \"./broken.mon\""
+    );
+    let MonError::Resolver(resolver_err) = &err else {
+        panic!("Expected a resolver error, got {err:?}");
+    };
+    let ResolverError::ImportedModuleInvalid { path, cause, .. } = resolver_err.as_ref() else {
+        panic!("Expected ImportedModuleInvalid, got {resolver_err:?}");
+    };
+    assert_eq!(path, "./broken.mon");
+    // ...and chains the parser error in the imported module
+    assert_eq!(cause.error_code(), "parser::unexpected_token");
+    assert!(resolver_err.source().is_some());
+    assert!(resolver_err.diagnostic_source().is_some());
+}
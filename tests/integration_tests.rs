@@ -1,5 +1,5 @@
 // Integration tests for mon-core using test fixtures
-use mon_core::analyze;
+use mon_core::{analyze, analyze_multi};
 use std::fs;
 use std::path::PathBuf;
 
@@ -133,6 +133,45 @@ mod ok_tests {
         );
     }
 
+    #[test]
+    fn test_multi_document() {
+        let mon_path = get_test_file_path("ok", "multi_document.mon");
+        let mon_content = read_test_file("ok", "multi_document.mon");
+        let results = analyze_multi(&mon_content, &mon_path.to_string_lossy())
+            .unwrap_or_else(|err| panic!("Should parse successfully: {err:?}"));
+        assert_eq!(results.len(), 2);
+
+        // The second document reuses the already loaded import.
+        let json: serde_json::Value = serde_json::from_str(&results[1].to_json().unwrap()).unwrap();
+        assert_eq!(json["config"]["host"], "localhost");
+        assert_eq!(json["config"]["port"], 9001.0);
+
+        // Each document only reports its own imports.
+        assert_eq!(
+            results[0].imported_files(),
+            vec![
+                get_test_file_path("ok", "imports_base.mon"),
+                get_test_file_path("ok", "imports_schemas.mon"),
+            ]
+        );
+        assert_eq!(
+            results[1].imported_files(),
+            vec![get_test_file_path("ok", "imports_base.mon")]
+        );
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let mon_path = get_test_file_path("ok", "nightmare.mon");
+        let mon_content = read_test_file("ok", "nightmare.mon");
+        let result = analyze(&mon_content, &mon_path.to_string_lossy()).unwrap();
+
+        assert_eq!(
+            result.dependency_graph(),
+            [(mon_path, get_test_file_path("ok", "nightmare_base.mon"))]
+        );
+    }
+
     #[test]
     fn test_pandemonium() {
         let mon_path = get_test_file_path("ok", "pandemonium.mon");
@@ -165,6 +204,16 @@ mod bad_tests {
         let _ = result;
     }
 
+    #[test]
+    fn test_self_import() {
+        let mon_path = get_test_file_path("bad", "self_import.mon");
+        let mon_content = read_test_file("bad", "self_import.mon");
+        let err = analyze(&mon_content, &mon_path.to_string_lossy())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "resolver::self_import");
+    }
+
     #[test]
     fn test_enum_undefined_variant() {
         let mon_content = read_test_file("bad", "enum_undefined_variant.mon");
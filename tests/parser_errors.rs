@@ -1,7 +1,7 @@
 // Additional parser error path tests
 // These systematically test unhappy paths to improve coverage
 
-use mon_core::analyze;
+use mon_core::{analyze, analyze_multi};
 
 #[test]
 fn test_parser_error_missing_closing_brace() {
@@ -107,3 +107,44 @@ fn test_parser_error_empty_collection_type() {
     let result = analyze(source, "test.mon");
     assert!(result.is_err(), "Should fail with empty collection type");
 }
+
+#[test]
+fn test_parser_error_colon_after_validation() {
+    let source = "{ T: #struct { a(Number) }, value :: T : { a: 1 } }";
+    let err = analyze(source, "test.mon").err().unwrap();
+    assert_eq!(err.code(), "parser::colon_after_validation");
+    assert!(err
+        .to_string()
+        .contains("Validated members must use '=' to assign"));
+}
+
+#[test]
+fn test_parser_error_multiple_spreads_in_type() {
+    let source = "{ value :: [String..., Number...] = [] }";
+    let err = analyze(source, "test.mon").err().unwrap();
+    assert_eq!(err.code(), "parser::multiple_spreads_in_type");
+    let span = err.primary_span().unwrap();
+    assert_eq!(
+        &source[span.offset()..span.offset() + span.len()],
+        "Number..."
+    );
+}
+
+#[test]
+fn test_parser_error_trailing_content() {
+    let source = "{ a: 1 }\n{ b: 2 }\n";
+    let err = analyze(source, "test.mon").err().unwrap();
+    assert_eq!(err.code(), "parser::trailing_content");
+    let span = err.primary_span().unwrap();
+    assert_eq!(
+        &source[span.offset()..span.offset() + span.len()],
+        "{ b: 2 }"
+    );
+
+    // A separator only starts a new document when parsing multiple documents
+    let err = analyze("{ a: 1 }\n---\n{ b: 2 }", "test.mon")
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), "parser::trailing_content");
+    assert!(analyze_multi("{ a: 1 }\n---\n{ b: 2 }", "test.mon").is_ok());
+}